@@ -0,0 +1,13 @@
+use std::hash::{BuildHasher, RandomState};
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    if std::env::var_os("CARGO_FEATURE_OBFUSCATE").is_none() {
+        return;
+    }
+    // `RandomState` is seeded from the entropy of the OS, so every build gets its own key.
+    let key = RandomState::new().hash_one(std::time::SystemTime::now());
+    let out_dir = std::env::var_os("OUT_DIR").expect("OUT_DIR is set by cargo");
+    let key_file = std::path::Path::new(&out_dir).join("obfuscate_key.rs");
+    std::fs::write(key_file, format!("const KEY: u64 = {key:#018X};\n")).expect("failed to write the obfuscation key");
+}
@@ -0,0 +1,14 @@
+#[cfg(target_os = "android")]
+fn main() {
+    println!("ro.debuggable: {}", anti_debug::linux::is_android_debug_build());
+    match anti_debug::detect_debugger() {
+        Ok(Some(reason)) => println!("Debugger detected: {}", reason),
+        Ok(None) => println!("No debugger present"),
+        Err(e) => println!("Error checking for debugger: {}", e),
+    }
+}
+
+#[cfg(not(target_os = "android"))]
+fn main() {
+    println!("This example only runs on Android.");
+}
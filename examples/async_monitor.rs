@@ -0,0 +1,27 @@
+#[cfg(feature = "tokio")]
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let mut config = anti_debug::MonitorConfig::default();
+    config.min_interval = std::time::Duration::from_millis(100);
+    config.max_interval = std::time::Duration::from_millis(300);
+    let mut reports = anti_debug::async_monitor(config);
+    let monitor = tokio::spawn(async move {
+        while let Some(report) = reports.recv().await {
+            eprintln!("Debugger detected:\n{}", report);
+        }
+    });
+
+    // A dummy workload sharing the runtime with the monitor.
+    let mut ticks = 0u64;
+    for _ in 0..10 {
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        ticks += 1;
+    }
+    println!("Workload finished after {} ticks", ticks);
+    monitor.abort();
+}
+
+#[cfg(not(feature = "tokio"))]
+fn main() {
+    println!("This example requires the `tokio` feature.");
+}
@@ -1,6 +1,9 @@
 fn main() {
+    if std::env::var("ANTI_DEBUG_VERBOSE").is_ok() {
+        eprintln!("{}", anti_debug::run_all_checks());
+    }
     let enable = std::env::var("ANTI_DEBUG").is_ok();
-    if enable && anti_debug::is_debugger_present().unwrap_or(false) {
+    if enable && anti_debug::is_debugger_present_with_policy(anti_debug::ErrorPolicy::FailOpen) {
         panic!("debugger detected");
     }
 }
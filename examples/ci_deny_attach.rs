@@ -1,4 +1,7 @@
 fn main() {
+    if std::env::var("ANTI_DEBUG_VERBOSE").is_ok() {
+        eprintln!("{}", anti_debug::run_all_checks());
+    }
     let enable = std::env::var("ANTI_DEBUG").is_ok();
     if enable {
         anti_debug::deny_attach().unwrap();
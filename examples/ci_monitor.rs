@@ -0,0 +1,26 @@
+/// Waits for the given number of alternating attach and detach events, such as from a tracer attaching twice.
+#[cfg(feature = "std")]
+fn main() {
+    use std::time::Duration;
+
+    use anti_debug::{Monitor, MonitorConfig, Transition};
+
+    let expected = std::env::args().nth(1).and_then(|count| count.parse().ok()).unwrap_or(4);
+    let mut config = MonitorConfig::default();
+    config.min_interval = Duration::from_millis(10);
+    config.max_interval = Duration::from_millis(50);
+    let (handle, events) = Monitor::channel(config);
+    println!("{}", std::process::id());
+    for i in 0..expected {
+        let event = events.recv_timeout(Duration::from_secs(60)).expect("no detection event");
+        eprintln!("{:?} at {:?}", event.transition, event.timestamp);
+        let transition = if i % 2 == 0 { Transition::Attached } else { Transition::Detached };
+        assert_eq!(event.transition, transition, "{}", event.report);
+    }
+    handle.join().unwrap();
+}
+
+#[cfg(not(feature = "std"))]
+fn main() {
+    println!("This example requires the `std` feature.");
+}
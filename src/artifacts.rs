@@ -0,0 +1,62 @@
+//! Scanning for debugger artifacts on the machine.
+
+use crate::DetectError;
+
+/// The debugger artifacts found by [`debugger_artifacts_present`].
+///
+/// A debugger running somewhere on the machine is not necessarily attached to the current process,
+/// so callers can decide which artifacts are actionable.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DebuggerArtifacts {
+    /// The class names or titles of the top-level windows that belong to known debuggers.
+    pub windows: Vec<String>,
+    /// The image names of the running processes that are known debuggers or memory editors.
+    pub processes: Vec<String>,
+}
+
+impl DebuggerArtifacts {
+    /// Returns `true` if no artifact was found.
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty() && self.processes.is_empty()
+    }
+}
+
+/// Scans the machine for running debuggers, such as x64dbg, OllyDbg, WinDbg or Cheat Engine.
+///
+/// Requires the `artifact-scan` feature. This finds debuggers that attach after launch
+/// with hiding plugins, which the PEB checks of [`is_debugger_present`](crate::is_debugger_present) miss,
+/// but it is slower and may report debuggers that are unrelated to the current process.
+///
+/// # Platform-specific Behavior
+///
+/// - **Windows**: Uses `windows::debugger_windows` and `windows::debugger_processes`.
+/// - **Other platforms**: Returns `Err(DetectError::Unsupported)`.
+///
+/// # Return Value
+///
+/// Returns the found [`DebuggerArtifacts`], which is empty if none is found,
+/// or `Err(DetectError)` if the scan is unsupported or the processes could not be enumerated.
+/// Windows and processes that can't be queried are skipped.
+pub fn debugger_artifacts_present() -> Result<DebuggerArtifacts, DetectError> {
+    #[cfg(target_os = "windows")] {
+        Ok(DebuggerArtifacts {
+            windows: crate::windows::debugger_windows(),
+            processes: crate::windows::debugger_processes()?,
+        })
+    }
+    #[cfg(not(target_os = "windows"))] {
+        Err(DetectError::Unsupported)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_debugger_artifacts_present() {
+        match super::debugger_artifacts_present() {
+            Ok(artifacts) => assert!(artifacts.is_empty()),
+            Err(e) => assert!(matches!(e, super::DetectError::Unsupported)),
+        }
+    }
+}
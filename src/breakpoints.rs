@@ -0,0 +1,224 @@
+//! Scanning caller-specified code for software breakpoints.
+
+use crate::DetectError;
+
+/// The default length scanned by [`breakpoint_scan!`](crate::breakpoint_scan),
+/// which is conservative for small functions such as license checks.
+pub const DEFAULT_BREAKPOINT_SCAN_LENGTH: usize = 64;
+
+/// Scans `len` bytes of code starting at `range` for software breakpoints,
+/// and returns the offsets of the suspicious bytes from `range`.
+///
+/// Requires the `memory-integrity` feature. This is cheap enough to run periodically,
+/// such as from a [`spawn_watchdog`](crate::spawn_watchdog) callback or a dedicated thread,
+/// to check the integrity of a sensitive function.
+///
+/// The code is read through the OS instead of dereferencing `range`,
+/// so unreadable memory fails with `Err(DetectError::MemoryUnreadable)` instead of crashing.
+///
+/// # Platform-specific Behavior
+///
+/// - **Windows**: Reads the code with `ReadProcessMemory`.
+/// - **Linux/Android**: Reads the code with `process_vm_readv`.
+/// - **macOS**: Reads the code with `mach_vm_read_overwrite`.
+/// - **Other platforms**: Returns `Err(DetectError::Unsupported)`.
+///
+/// On x86 and x86-64, `INT3` (`0xCC`) bytes are reported.
+/// Runs of `0xCC` right after a `RET` (`0xC3`) or reaching the end of the range are treated as
+/// padding between functions and skipped. Pass the exact length of the function when it is known,
+/// since a `0xCC` byte of an immediate or a displacement is reported as well.
+///
+/// On ARM64, `BRK` instructions at 4-byte aligned offsets are reported.
+/// Other architectures return `Err(DetectError::Unsupported)`.
+///
+/// # Examples
+///
+/// ```rust
+/// fn license_check() -> bool {
+///     true
+/// }
+///
+/// # fn main() {
+/// let breakpoints = anti_debug::breakpoint_scan!(license_check);
+/// if breakpoints.is_ok_and(|offsets| !offsets.is_empty()) {
+///     println!("Breakpoint found on the license check");
+/// }
+/// # }
+/// ```
+pub fn scan_for_breakpoints(range: *const u8, len: usize) -> Result<Vec<usize>, DetectError> {
+    let mut code = vec![0u8; len];
+    read_memory(range, &mut code)?;
+    breakpoint_offsets(&code)
+}
+
+/// Computes the CRC-32 of `len` bytes of code starting at `range`, for comparing with [`code_integrity_ok`].
+///
+/// Requires the `memory-integrity` feature. The code is read through the OS like [`scan_for_breakpoints`],
+/// so unreadable memory fails with `Err(DetectError::MemoryUnreadable)` instead of crashing.
+///
+/// Take the checksum once at startup, before a debugger could patch the code,
+/// and compare it periodically to catch both software breakpoints and other patches.
+pub fn code_checksum(range: *const u8, len: usize) -> Result<u32, DetectError> {
+    let mut code = vec![0u8; len];
+    read_memory(range, &mut code)?;
+    Ok(crc32(&code))
+}
+
+/// Checks if the CRC-32 of `region` is `expected_crc32`, such as taken by [`code_checksum`].
+///
+/// Requires the `memory-integrity` feature. Returns `false` if any byte of the region was patched,
+/// such as by a software breakpoint. Building the slice of a function is up to the caller:
+/// a slice over code is only valid if the whole range is mapped and readable,
+/// so prefer [`code_checksum`] when it isn't known to be.
+///
+/// # Examples
+///
+/// ```rust
+/// fn license_check() -> bool {
+///     true
+/// }
+///
+/// # fn main() {
+/// let expected = anti_debug::code_checksum(license_check as *const u8, 16).unwrap();
+/// // SAFETY: the checksum above read the same range successfully, so it is mapped and readable.
+/// let region = unsafe { std::slice::from_raw_parts(license_check as *const u8, 16) };
+/// assert!(anti_debug::code_integrity_ok(region, expected));
+/// # }
+/// ```
+pub fn code_integrity_ok(region: &[u8], expected_crc32: u32) -> bool {
+    crc32(region) == expected_crc32
+}
+
+/// Computes the CRC-32 (IEEE 802.3) of `bytes`.
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u32, |crc, _| (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg()))
+    })
+}
+
+/// Reads the memory of the current process at `address` into `buffer` through the OS,
+/// which fails with `Err(DetectError::MemoryUnreadable)` instead of crashing on unreadable memory.
+pub(crate) fn read_memory(address: *const u8, buffer: &mut [u8]) -> Result<(), DetectError> {
+    #[cfg(target_os = "windows")] {
+        crate::windows::read_memory(address, buffer)
+    }
+    #[cfg(any(target_os = "linux", target_os = "android"))] {
+        crate::linux::read_memory(address, buffer)
+    }
+    #[cfg(target_os = "macos")]
+    #[allow(deprecated)]
+    unsafe {
+        unsafe extern "C" {
+            fn mach_vm_read_overwrite(task: libc::mach_port_t, address: u64, size: u64, data: u64, out_size: *mut u64) -> libc::kern_return_t;
+        }
+
+        let mut read = 0u64;
+        let result = mach_vm_read_overwrite(libc::mach_task_self(), address as u64, buffer.len() as u64, buffer.as_mut_ptr() as u64, &mut read);
+        if result != libc::KERN_SUCCESS || read != buffer.len() as u64 {
+            let error = std::io::Error::other(format!("mach_vm_read_overwrite failed with {result}"));
+            return Err(DetectError::MemoryUnreadable(error));
+        }
+        Ok(())
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "android", target_os = "macos")))] {
+        let _ = (address, buffer);
+        Err(DetectError::Unsupported)
+    }
+}
+
+/// Returns the offsets of the software breakpoints in `code`.
+///
+/// See [`scan_for_breakpoints`].
+pub(crate) fn breakpoint_offsets(code: &[u8]) -> Result<Vec<usize>, DetectError> {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))] {
+        let mut offsets = Vec::new();
+        let mut offset = 0;
+        while offset < code.len() {
+            if code[offset] != 0xCC {
+                offset += 1;
+                continue;
+            }
+            let start = offset;
+            while offset < code.len() && code[offset] == 0xCC {
+                offset += 1;
+            }
+            let after_ret = start > 0 && code[start - 1] == 0xC3;
+            if !after_ret && offset != code.len() {
+                offsets.extend(start..offset);
+            }
+        }
+        Ok(offsets)
+    }
+    #[cfg(target_arch = "aarch64")] {
+        // BRK #imm16: 1101_0100_001 imm16 000_00
+        Ok(code.chunks_exact(4).enumerate().filter_map(|(index, word)| {
+            let word = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+            (word & 0xFFE0_001F == 0xD420_0000).then_some(index * 4)
+        }).collect())
+    }
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))] {
+        let _ = code;
+        Err(DetectError::Unsupported)
+    }
+}
+
+/// Scans a function for software breakpoints with [`scan_for_breakpoints`].
+///
+/// Takes the function and optionally the length to scan,
+/// which defaults to [`DEFAULT_BREAKPOINT_SCAN_LENGTH`](crate::DEFAULT_BREAKPOINT_SCAN_LENGTH).
+///
+/// In debug builds with incremental linking on Windows, the address of a function may be
+/// a jump thunk instead of the function itself.
+#[macro_export]
+macro_rules! breakpoint_scan {
+    ($function:expr) => {
+        $crate::breakpoint_scan!($function, $crate::DEFAULT_BREAKPOINT_SCAN_LENGTH)
+    };
+    ($function:expr, $len:expr) => {
+        $crate::scan_for_breakpoints($function as *const u8, $len)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn test_breakpoint_offsets() {
+        let code = [0x55, 0xCC, 0x90, 0xC3, 0xCC, 0xCC, 0x48, 0xCC];
+        assert_eq!(super::breakpoint_offsets(&code).unwrap(), [1]);
+        let code = [0x55, 0xCC, 0xCC, 0x90, 0xC3];
+        assert_eq!(super::breakpoint_offsets(&code).unwrap(), [1, 2]);
+    }
+
+    #[test]
+    #[cfg(target_arch = "aarch64")]
+    fn test_breakpoint_offsets() {
+        let code = [0x1F, 0x20, 0x03, 0xD5, 0x00, 0x00, 0x20, 0xD4, 0xC0, 0x03, 0x5F, 0xD6];
+        assert_eq!(super::breakpoint_offsets(&code).unwrap(), [4]);
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(super::crc32(b""), 0);
+        assert_eq!(super::crc32(b"123456789"), 0xCBF4_3926);
+        assert!(super::code_integrity_ok(b"123456789", 0xCBF4_3926));
+        assert!(!super::code_integrity_ok(b"1234\xCC6789", 0xCBF4_3926));
+    }
+
+    #[test]
+    #[cfg(any(target_os = "windows", target_os = "linux", target_os = "android", target_os = "macos"))]
+    fn test_code_checksum() {
+        static CODE: [u8; 9] = *b"123456789";
+        assert_eq!(super::code_checksum(CODE.as_ptr(), CODE.len()).unwrap(), 0xCBF4_3926);
+        assert!(matches!(super::code_checksum(core::ptr::null(), 16), Err(crate::DetectError::MemoryUnreadable(_))));
+    }
+
+    #[test]
+    #[cfg(any(target_os = "windows", target_os = "linux", target_os = "android", target_os = "macos"))]
+    fn test_scan_for_breakpoints() {
+        static CODE: [u8; 4] = [0x90, 0x90, 0x90, 0xC3];
+        assert!(super::scan_for_breakpoints(CODE.as_ptr(), CODE.len()).unwrap().is_empty());
+        assert!(crate::breakpoint_scan!(test_scan_for_breakpoints, 1).is_ok());
+        assert!(matches!(super::scan_for_breakpoints(core::ptr::null(), 16), Err(crate::DetectError::MemoryUnreadable(_))));
+    }
+}
@@ -0,0 +1,82 @@
+//! Memoizing the result of the detection.
+
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use crate::AntiDebugError;
+
+/// No result is cached.
+const UNKNOWN: u8 = 0;
+/// No debugger was detected.
+const ABSENT: u8 = 1;
+/// A debugger was detected.
+const PRESENT: u8 = 2;
+
+/// The process-global cached result of [`is_debugger_present_cached`].
+static CACHE: AtomicU8 = AtomicU8::new(UNKNOWN);
+
+/// Checks if a debugger is attached like [`is_debugger_present`](crate::is_debugger_present),
+/// but performs the checks only on the first call and returns the cached result afterward.
+///
+/// This is cheap enough for hot paths, such as inside a decryption routine. Errors are not cached,
+/// so a failed check is retried on the next call.
+///
+/// # Notes
+///
+/// - The cache is process-global, i.e. shared by all threads.
+/// - The cached result may be stale: a debugger attaching after the first call is not detected
+///   until [`invalidate_detection_cache`] is called.
+/// - Once a debugger is detected, the result stays `true` until [`invalidate_detection_cache`]
+///   is called, even if the debugger detaches. Debuggers rarely detach, and a debugger detaching
+///   right before a sensitive call must not clear the result.
+///
+/// # Examples
+///
+/// ```rust
+/// # fn main() {
+/// for _ in 0..1000 {
+///     if anti_debug::is_debugger_present_cached().unwrap_or(false) {
+///         break;
+///     }
+/// }
+/// # }
+/// ```
+pub fn is_debugger_present_cached() -> Result<bool, AntiDebugError> {
+    match CACHE.load(Ordering::Acquire) {
+        ABSENT => Ok(false),
+        PRESENT => Ok(true),
+        _ => {
+            let present = crate::is_debugger_present()?;
+            CACHE.store(if present { PRESENT } else { ABSENT }, Ordering::Release);
+            Ok(present)
+        }
+    }
+}
+
+/// Clears the result cached by [`is_debugger_present_cached`], so the next call performs the checks again.
+///
+/// Long-running processes can call this periodically to detect debuggers attaching later.
+pub fn invalidate_detection_cache() {
+    CACHE.store(UNKNOWN, Ordering::Release);
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_is_debugger_present_cached() {
+        use core::sync::atomic::Ordering;
+
+        assert!(!super::is_debugger_present_cached().unwrap());
+        assert_eq!(super::CACHE.load(Ordering::Acquire), super::ABSENT);
+        assert!(!super::is_debugger_present_cached().unwrap());
+        super::invalidate_detection_cache();
+        assert!(!super::is_debugger_present_cached().unwrap());
+
+        // A detection stays cached until invalidated.
+        super::CACHE.store(super::PRESENT, Ordering::Release);
+        assert!(super::is_debugger_present_cached().unwrap());
+        assert!(super::is_debugger_present_cached().unwrap());
+        super::invalidate_detection_cache();
+        assert_eq!(super::CACHE.load(Ordering::Acquire), super::UNKNOWN);
+        assert!(!super::is_debugger_present_cached().unwrap());
+    }
+}
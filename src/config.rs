@@ -0,0 +1,169 @@
+use alloc::vec::Vec;
+
+use crate::DetectionMethod;
+
+/// Controls which checks are performed by [`detect_debugger_with`](crate::detect_debugger_with).
+///
+/// Each field enables a check. Fields of checks for other platforms are ignored,
+/// and so are checks that are not compiled in (such as the `deep-detect` ones without the feature).
+///
+/// Start from [`DetectionConfig::all`], [`DetectionConfig::fast`] or [`DetectionConfig::none`],
+/// then set the fields to opt in or out of individual checks:
+///
+/// ```rust
+/// let mut config = anti_debug::DetectionConfig::all();
+/// config.check_heap_flags = false;
+/// ```
+///
+/// [`Default`] enables the checks [`detect_debugger`](crate::detect_debugger) performs
+/// with the enabled features.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct DetectionConfig {
+    /// Check with `IsDebuggerPresent`. (Windows)
+    pub check_is_debugger_present: bool,
+    /// Check with `CheckRemoteDebuggerPresent`. (Windows)
+    pub check_remote_debugger: bool,
+    /// Check the debug port, debug object handle and debug flags with `NtQueryInformationProcess`. (Windows)
+    pub check_nt_query: bool,
+    /// Check the `NtGlobalFlag` field of the PEB. Requires `deep-detect`. (Windows)
+    pub check_nt_global_flag: bool,
+    /// Check the flags of the process heap. Requires `deep-detect`. (Windows)
+    pub check_heap_flags: bool,
+    /// Check for hardware breakpoints. Requires `deep-detect`. (Windows/Linux/Android)
+    pub check_hardware_breakpoints: bool,
+    /// Check for a kernel debugger with [`kernel_debugger_present`](crate::kernel_debugger_present). (Windows)
+    pub check_kernel_debugger: bool,
+    /// Check the `TracerPid` field in `/proc/self/status`. (Linux/Android)
+    pub check_tracerpid: bool,
+    /// Check if the tracer slot is taken with `ptrace`. Requires `deep-detect` and `std`. (Linux/Android/macOS)
+    ///
+    /// On Linux/Android under the restricted Yama `ptrace_scope` (`1`), this resets the ptracer exception
+    /// set with `prctl(PR_SET_PTRACER)` of the current process.
+    pub check_ptrace_traceme: bool,
+    /// Check the `wchan` of the threads for `ptrace_stop`. Requires `deep-detect` and `std`. (Linux/Android)
+    pub check_wchan: bool,
+    /// Check if the OS is a debuggable build with the `ro.debuggable` system property.
+    /// Requires `deep-detect`. (Android)
+    pub check_debuggable_build: bool,
+    /// Check the `pbi_flags` field of `proc_bsdinfo`. (macOS)
+    pub check_proc_bsdinfo: bool,
+    /// Check the `P_TRACED` flag of `kinfo_proc` with `sysctl`. (macOS/iOS/FreeBSD/NetBSD/OpenBSD)
+    pub check_sysctl_traced: bool,
+}
+
+impl DetectionConfig {
+    /// Enables all checks.
+    pub const fn all() -> Self {
+        Self {
+            check_is_debugger_present: true,
+            check_remote_debugger: true,
+            check_nt_query: true,
+            check_nt_global_flag: true,
+            check_heap_flags: true,
+            check_hardware_breakpoints: true,
+            check_kernel_debugger: true,
+            check_tracerpid: true,
+            check_ptrace_traceme: true,
+            check_wchan: true,
+            check_debuggable_build: true,
+            check_proc_bsdinfo: true,
+            check_sysctl_traced: true,
+        }
+    }
+
+    /// Enables only the cheapest check per platform:
+    /// `IsDebuggerPresent` on Windows, `TracerPid` on Linux/Android and `proc_bsdinfo` on macOS.
+    pub const fn fast() -> Self {
+        Self {
+            check_is_debugger_present: true,
+            check_tracerpid: true,
+            check_proc_bsdinfo: true,
+            ..Self::none()
+        }
+    }
+
+    /// Disables all checks.
+    pub const fn none() -> Self {
+        Self {
+            check_is_debugger_present: false,
+            check_remote_debugger: false,
+            check_nt_query: false,
+            check_nt_global_flag: false,
+            check_heap_flags: false,
+            check_hardware_breakpoints: false,
+            check_kernel_debugger: false,
+            check_tracerpid: false,
+            check_ptrace_traceme: false,
+            check_wchan: false,
+            check_debuggable_build: false,
+            check_proc_bsdinfo: false,
+            check_sysctl_traced: false,
+        }
+    }
+}
+
+impl DetectionConfig {
+    /// Returns the [supported](DetectionMethod::is_supported) methods enabled by the config,
+    /// in the order of [`DetectionMethod::ALL`].
+    pub(crate) fn methods(&self) -> Vec<DetectionMethod> {
+        let enabled = |method| match method {
+            DetectionMethod::IsDebuggerPresent => self.check_is_debugger_present,
+            DetectionMethod::RemoteDebuggerPresent => self.check_remote_debugger,
+            DetectionMethod::DebugPort |
+            DetectionMethod::DebugObjectHandle |
+            DetectionMethod::DebugFlags => self.check_nt_query,
+            DetectionMethod::NtGlobalFlag => self.check_nt_global_flag,
+            DetectionMethod::HeapFlags => self.check_heap_flags,
+            DetectionMethod::HardwareBreakpoints => self.check_hardware_breakpoints,
+            DetectionMethod::KernelDebugger => self.check_kernel_debugger,
+            DetectionMethod::TracerPid => self.check_tracerpid,
+            DetectionMethod::PtraceSlot => self.check_ptrace_traceme,
+            DetectionMethod::Wchan => self.check_wchan,
+            // `ro.debuggable` is only part of `detect_debugger_with` with `deep-detect`.
+            DetectionMethod::DebuggableBuild => self.check_debuggable_build && cfg!(feature = "deep-detect"),
+            DetectionMethod::ProcBsdInfoFlags => self.check_proc_bsdinfo,
+            DetectionMethod::SysctlTraced => self.check_sysctl_traced,
+        };
+        DetectionMethod::ALL.iter().copied().filter(|&method| method.is_supported() && enabled(method)).collect()
+    }
+}
+
+impl Default for DetectionConfig {
+    fn default() -> Self {
+        let deep = cfg!(feature = "deep-detect");
+        Self {
+            check_remote_debugger: deep,
+            check_nt_query: deep,
+            check_nt_global_flag: deep,
+            check_heap_flags: deep,
+            check_hardware_breakpoints: deep,
+            check_kernel_debugger: cfg!(feature = "kernel-detect"),
+            check_ptrace_traceme: deep,
+            check_wchan: deep,
+            check_debuggable_build: deep,
+            check_sysctl_traced: true,
+            ..Self::fast()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_default() {
+        let config = super::DetectionConfig::default();
+        assert!(config.check_is_debugger_present && config.check_tracerpid && config.check_proc_bsdinfo);
+        assert_eq!(config.check_nt_query, cfg!(feature = "deep-detect"));
+        assert_eq!(config.check_kernel_debugger, cfg!(feature = "kernel-detect"));
+    }
+
+    #[test]
+    fn test_methods() {
+        assert_eq!(super::DetectionConfig::none().methods(), []);
+        let methods = super::DetectionConfig::fast().methods();
+        assert!(methods.len() <= 1 && methods.iter().all(|method| method.is_supported()), "{methods:?}");
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        assert!(super::DetectionConfig::default().methods().contains(&crate::DetectionMethod::TracerPid));
+    }
+}
@@ -0,0 +1,365 @@
+//! Composing the detection checks fluently.
+
+use crate::{DetectError, DetectionConfig, DetectionMethod, DetectionReport};
+
+/// Builds a [`Detector`] running only the chosen checks.
+///
+/// Some checks have side effects that certain environments can't tolerate, such as the forked
+/// `ptrace` helper or the raised `SIGTRAP`, so the builder lets callers compose only the safe subset.
+/// [`DetectorBuilder::new`] starts from the checks of [`is_debugger_present`](crate::is_debugger_present).
+///
+/// # Examples
+///
+/// ```rust
+/// # fn main() {
+/// let detector = anti_debug::DetectorBuilder::new()
+///     .without_ptrace()
+///     .build();
+/// match detector.detect() {
+///     Ok(true) => println!("Debugger detected!"),
+///     Ok(false) => println!("No debugger present"),
+///     Err(e) => println!("Error checking for debugger: {}", e),
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[must_use]
+pub struct DetectorBuilder {
+    detector: Detector,
+}
+
+impl DetectorBuilder {
+    /// Creates a builder with the checks of [`is_debugger_present`](crate::is_debugger_present).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the checks performed by [`detect_debugger_with`](crate::detect_debugger_with).
+    pub fn with_config(mut self, config: DetectionConfig) -> Self {
+        self.detector.config = config;
+        self
+    }
+
+    /// Disables the checks reading `/proc/self/status`, i.e. `TracerPid` and `HWBreakpoint`. (Linux/Android)
+    pub fn without_proc_status(mut self) -> Self {
+        self.detector.config.check_tracerpid = false;
+        self.detector.config.check_hardware_breakpoints = false;
+        self
+    }
+
+    /// Disables the check forking a helper which attaches with `ptrace`. (Linux/Android/macOS)
+    pub fn without_ptrace(mut self) -> Self {
+        self.detector.config.check_ptrace_traceme = false;
+        self
+    }
+
+    /// Enables [`timing_check`](crate::timing_check) with `threshold`. Requires the `timing` feature.
+    #[cfg(feature = "timing")]
+    pub fn with_timing(mut self, threshold: core::time::Duration) -> Self {
+        self.detector.timing = Some(threshold);
+        self
+    }
+
+    /// Enables `linux::sigtrap_detected`, which raises `SIGTRAP` under a temporary handler. (Linux/Android)
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn with_sigtrap(mut self) -> Self {
+        self.detector.sigtrap = true;
+        self
+    }
+
+    /// Detects a debugger only if the [score](Detector::score) of the checks reaches `threshold`,
+    /// instead of on the first check that detects one.
+    ///
+    /// All checks run in this mode, and failed checks are ignored instead of returned.
+    /// The timing check weighs 0.4 and the `SIGTRAP` check weighs 0.9.
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.detector.threshold = Some(threshold);
+        self
+    }
+
+    /// Replaces the weight of `method`, [`DetectionMethod::weight`] by default, for [`with_threshold`](Self::with_threshold).
+    pub fn with_weight(mut self, method: DetectionMethod, weight: f64) -> Self {
+        self.detector.weights[method as usize] = Some(weight);
+        self
+    }
+
+    /// Builds the [`Detector`].
+    pub fn build(self) -> Detector {
+        self.detector
+    }
+}
+
+/// Checks if a debugger is attached with the checks chosen by a [`DetectorBuilder`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Detector {
+    config: DetectionConfig,
+    threshold: Option<f64>,
+    weights: [Option<f64>; DetectionMethod::ALL.len()],
+    #[cfg(feature = "timing")]
+    timing: Option<core::time::Duration>,
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    sigtrap: bool,
+}
+
+impl Detector {
+    /// Returns the checks performed by [`detect_debugger_with`](crate::detect_debugger_with).
+    pub fn config(&self) -> &DetectionConfig {
+        &self.config
+    }
+
+    /// Checks if a debugger is attached.
+    ///
+    /// The checks of [`config`](Self::config) run first, followed by the timing and `SIGTRAP` checks
+    /// if enabled. Returns on the first check that detects a debugger or fails.
+    ///
+    /// With [`with_threshold`](DetectorBuilder::with_threshold), returns if the [score](Self::score)
+    /// reaches the threshold instead, which never fails.
+    ///
+    /// With the `disarm` feature, or the development override of `init` (`dev-override` feature),
+    /// no check runs and this always returns `Ok(false)`.
+    pub fn detect(&self) -> Result<bool, DetectError> {
+        if crate::disarm::disarmed() {
+            return Ok(false);
+        }
+        if let Some(threshold) = self.threshold {
+            return Ok(self.score() >= threshold);
+        }
+        if crate::detect_debugger_with(&self.config)?.is_some() {
+            return Ok(true);
+        }
+        #[cfg(feature = "timing")]
+        if let Some(threshold) = self.timing {
+            if crate::timing_check(threshold)? {
+                return Ok(true);
+            }
+        }
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        if self.sigtrap && crate::linux::sigtrap_detected()? {
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Runs all checks, and returns the confidence that a debugger is attached
+    /// like [`DetectionReport::score`], with the weights of the [`DetectorBuilder`].
+    ///
+    /// With the `disarm` feature, or the development override of `init` (`dev-override` feature),
+    /// no check runs and this always returns `0.0`.
+    pub fn score(&self) -> f64 {
+        if crate::disarm::disarmed() {
+            return 0.0;
+        }
+        #[cfg_attr(not(any(feature = "timing", target_os = "linux", target_os = "android")), allow(unused_mut))]
+        let mut report = crate::method::run_methods(&self.config.methods());
+        #[cfg(feature = "timing")]
+        if let Some(threshold) = self.timing {
+            crate::method::run_probe(&TimingProbe(threshold), &mut report);
+        }
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        if self.sigtrap {
+            crate::method::run_probe(&SigtrapProbe, &mut report);
+        }
+        self.score_of(&report)
+    }
+
+    /// Returns the score of `report` with the weights of the [`DetectorBuilder`].
+    fn score_of(&self, report: &DetectionReport) -> f64 {
+        report.score_with(|method| self.weights[method as usize].unwrap_or(method.weight()))
+    }
+}
+
+/// The timing check of a [`Detector`] as a probe.
+#[cfg(feature = "timing")]
+struct TimingProbe(core::time::Duration);
+
+#[cfg(feature = "timing")]
+impl crate::DetectionProbe for TimingProbe {
+    fn name(&self) -> &str {
+        "Timing"
+    }
+
+    fn probe(&self) -> Result<bool, DetectError> {
+        crate::timing_check(self.0)
+    }
+
+    fn weight(&self) -> f64 {
+        0.4
+    }
+}
+
+/// The `SIGTRAP` check of a [`Detector`] as a probe.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+struct SigtrapProbe;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl crate::DetectionProbe for SigtrapProbe {
+    fn name(&self) -> &str {
+        "Sigtrap"
+    }
+
+    fn probe(&self) -> Result<bool, DetectError> {
+        crate::linux::sigtrap_detected()
+    }
+
+    fn weight(&self) -> f64 {
+        0.9
+    }
+}
+
+/// The poll of a [`DebouncedDetector`].
+#[cfg(feature = "std")]
+type Probe = Box<dyn Fn() -> crate::DetectionReport + Send + Sync>;
+
+/// Checks if a debugger is attached, reporting only detections that persist over several polls.
+///
+/// Some system services inspect processes briefly, such as `debuggerd` or the ANR watchdog on Android,
+/// which makes `TracerPid` flicker non-zero for a few milliseconds. Requiring consecutive positive polls
+/// filters such blips out, at the cost of a delay of the real detections.
+///
+/// The detector is `Send + Sync`, so it can be shared between threads behind an `Arc`.
+///
+/// # Examples
+///
+/// ```rust
+/// # fn main() {
+/// let detector = anti_debug::DebouncedDetector::new(3, std::time::Duration::from_millis(20));
+/// if detector.check() {
+///     println!("Debugger detected!");
+/// }
+/// # }
+/// ```
+#[cfg(feature = "std")]
+pub struct DebouncedDetector {
+    required_consecutive: u32,
+    interval: core::time::Duration,
+    probe: Probe,
+    last_report: std::sync::Mutex<Option<crate::DetectionReport>>,
+}
+
+#[cfg(feature = "std")]
+impl DebouncedDetector {
+    /// Creates a detector polling [`run_all_checks`](crate::run_all_checks), which reports a debugger
+    /// only after `required_consecutive` consecutive positive polls `interval` apart.
+    pub fn new(required_consecutive: u32, interval: core::time::Duration) -> Self {
+        Self::with_probe(required_consecutive, interval, Box::new(crate::run_all_checks))
+    }
+
+    /// Creates a detector with a custom poll.
+    fn with_probe(required_consecutive: u32, interval: core::time::Duration, probe: Probe) -> Self {
+        Self { required_consecutive, interval, probe, last_report: std::sync::Mutex::new(None) }
+    }
+
+    /// Checks if a debugger is attached.
+    ///
+    /// Polls until a poll detects no debugger, which returns `false`,
+    /// or `required_consecutive` polls in a row detect one, which returns `true`.
+    /// Blocks for `interval` between the polls, so a detection takes at least
+    /// `(required_consecutive - 1) * interval`. Failed methods don't count as positive.
+    pub fn check(&self) -> bool {
+        let mut consecutive = 0;
+        loop {
+            let report = (self.probe)();
+            let detected = report.any_detected();
+            *self.last_report.lock().unwrap_or_else(|e| e.into_inner()) = Some(report);
+            if !detected {
+                return false;
+            }
+            consecutive += 1;
+            if consecutive >= self.required_consecutive {
+                return true;
+            }
+            std::thread::sleep(self.interval);
+        }
+    }
+
+    /// Returns the report of the latest poll, which is `None` if [`check`](Self::check) was never called.
+    ///
+    /// The report is locked while the guard is held, which blocks [`check`](Self::check) in other threads.
+    pub fn last_report(&self) -> std::sync::MutexGuard<'_, Option<crate::DetectionReport>> {
+        self.last_report.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::fmt::Debug for DebouncedDetector {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DebouncedDetector")
+            .field("required_consecutive", &self.required_consecutive)
+            .field("interval", &self.interval)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_default() {
+        let detector = super::DetectorBuilder::new().build();
+        assert_eq!(detector.config(), &crate::DetectionConfig::default());
+        assert_eq!(detector.detect().unwrap(), crate::is_debugger_present().unwrap());
+    }
+
+    #[test]
+    fn test_builder() {
+        let detector = super::DetectorBuilder::new().without_proc_status().without_ptrace().build();
+        assert!(!detector.config().check_tracerpid && !detector.config().check_ptrace_traceme);
+        assert!(!detector.detect().unwrap());
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        assert!(!super::DetectorBuilder::new().with_sigtrap().build().detect().unwrap());
+        #[cfg(all(feature = "timing", not(feature = "disarm")))]
+        assert!(super::DetectorBuilder::new().with_timing(core::time::Duration::ZERO).build().detect().unwrap());
+    }
+
+    #[test]
+    fn test_threshold() {
+        let detector = super::DetectorBuilder::new().with_threshold(0.5).build();
+        assert_eq!(detector.score(), 0.0);
+        assert!(!detector.detect().unwrap());
+        #[cfg(feature = "timing")] {
+            let timing = super::DetectorBuilder::new().with_timing(core::time::Duration::ZERO);
+            assert!(!timing.with_threshold(0.5).build().detect().unwrap());
+            #[cfg(not(feature = "disarm"))]
+            assert!(timing.with_threshold(0.4).build().detect().unwrap());
+        }
+
+        let method = crate::DetectionMethod::TracerPid;
+        let mut report = crate::DetectionReport::default();
+        report.results.push((method, Ok(Some(crate::DetectionReason::TracerPid(1)))));
+        assert_eq!(detector.score_of(&report), 1.0);
+        assert_eq!(super::DetectorBuilder::new().with_weight(method, 0.25).build().score_of(&report), 0.25);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_debounced_detector() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<super::DebouncedDetector>();
+
+        fn detector(polls: &'static [bool]) -> (super::DebouncedDetector, Arc<AtomicUsize>) {
+            let index = Arc::new(AtomicUsize::new(0));
+            let probe = {
+                let index = index.clone();
+                move || {
+                    let detected = polls[index.fetch_add(1, Ordering::SeqCst)];
+                    let result = Ok(detected.then_some(crate::DetectionReason::TracerPid(1)));
+                    crate::DetectionReport { results: vec![(crate::DetectionMethod::TracerPid, result)], probes: Vec::new() }
+                }
+            };
+            (super::DebouncedDetector::with_probe(3, core::time::Duration::ZERO, Box::new(probe)), index)
+        }
+
+        let (detector, index) = detector(&[true, false, true, true, true]);
+        assert!(detector.last_report().is_none());
+        assert!(!detector.check());
+        assert_eq!(index.load(Ordering::SeqCst), 2);
+        assert!(!detector.last_report().as_ref().unwrap().any_detected());
+        assert!(detector.check());
+        assert_eq!(index.load(Ordering::SeqCst), 5);
+        assert!(detector.last_report().as_ref().unwrap().any_detected());
+
+        assert!(!super::DebouncedDetector::new(2, core::time::Duration::ZERO).check());
+    }
+}
@@ -0,0 +1,150 @@
+//! Disarming the detection for development.
+
+/// Returns `true` if the detection is disarmed by the `disarm` feature or the development override.
+pub(crate) fn disarmed() -> bool {
+    #[cfg(feature = "dev-override")]
+    if OVERRIDDEN.load(core::sync::atomic::Ordering::Acquire) {
+        return true;
+    }
+    cfg!(feature = "disarm")
+}
+
+/// The environment variable carrying the token of the development override, see [`init`].
+#[cfg(feature = "dev-override")]
+pub const OVERRIDE_ENV_VAR: &str = "ANTI_DEBUG_OVERRIDE";
+
+/// Whether [`init`] found the development override.
+#[cfg(feature = "dev-override")]
+static OVERRIDDEN: core::sync::atomic::AtomicBool = core::sync::atomic::AtomicBool::new(false);
+
+/// Configures the crate for the current process, see [`init`].
+///
+/// Start from [`InitConfig::default`], then set the fields:
+///
+/// ```rust
+/// let mut config = anti_debug::InitConfig::default();
+/// config.override_token = Some(String::from("0f3c9a..."));
+/// ```
+#[cfg(feature = "dev-override")]
+#[derive(Clone, Default)]
+#[non_exhaustive]
+pub struct InitConfig {
+    /// The token which disarms the detection if [`OVERRIDE_ENV_VAR`] carries it. Defaults to `None`,
+    /// which never disarms.
+    ///
+    /// Use a secret of each developer or build, such as an HMAC of the build ID with a key kept
+    /// off the shipped binary, so one leaked token doesn't disarm every binary.
+    pub override_token: Option<alloc::string::String>,
+}
+
+#[cfg(feature = "dev-override")]
+impl core::fmt::Debug for InitConfig {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("InitConfig")
+            .field("override_token", &self.override_token.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+/// Configures the crate for the current process, and returns whether the development override is active.
+///
+/// Requires the `dev-override` feature. If [`OVERRIDE_ENV_VAR`] carries the
+/// [`override_token`](InitConfig::override_token) of `config`, the detection is disarmed like with
+/// the `disarm` feature for the rest of the process. The variable is only read here,
+/// so setting it later has no effect, and without calling this the override is never active.
+///
+/// # Examples
+///
+/// ```rust
+/// # fn main() {
+/// let mut config = anti_debug::InitConfig::default();
+/// config.override_token = option_env!("DEV_OVERRIDE_TOKEN").map(String::from);
+/// if anti_debug::init(config) {
+///     eprintln!("Debugger detection disarmed for development");
+/// }
+/// # }
+/// ```
+#[cfg(feature = "dev-override")]
+pub fn init(config: InitConfig) -> bool {
+    let token = std::env::var_os(OVERRIDE_ENV_VAR);
+    if override_matches(config.override_token.as_deref(), token.as_deref()) {
+        OVERRIDDEN.store(true, core::sync::atomic::Ordering::Release);
+    }
+    OVERRIDDEN.load(core::sync::atomic::Ordering::Acquire)
+}
+
+/// Checks if the `actual` token from the environment is the `expected` one.
+///
+/// Tokens of the same length are compared in constant time, so the token can't be guessed by timing.
+#[cfg(feature = "dev-override")]
+fn override_matches(expected: Option<&str>, actual: Option<&std::ffi::OsStr>) -> bool {
+    let (Some(expected), Some(actual)) = (expected, actual) else {
+        return false;
+    };
+    let (expected, actual) = (expected.as_bytes(), actual.as_encoded_bytes());
+    !expected.is_empty() && expected.len() == actual.len()
+        && expected.iter().zip(actual).fold(0, |difference, (a, b)| difference | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    #[cfg(feature = "disarm")]
+    fn test_disarmed() {
+        assert!(super::disarmed());
+        assert!(!crate::is_debugger_present().unwrap());
+        assert!(crate::deny_attach().is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "disarm")]
+    fn test_disarmed_paths() {
+        let config = crate::DetectionConfig::all();
+        assert!(crate::deny_attach_with(&config).is_ok());
+        assert!(matches!(crate::detect_debugger_with(&config), Ok(None)));
+        assert!(matches!(crate::detect_with(&crate::supported_methods()), Ok(false)));
+        assert!(crate::run_all_checks().all_clean());
+        #[cfg(feature = "timing")] {
+            let detector = crate::DetectorBuilder::new().with_timing(core::time::Duration::ZERO);
+            assert!(!detector.build().detect().unwrap());
+            assert_eq!(detector.with_threshold(0.4).build().score(), 0.0);
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "disarm", feature = "std"))]
+    fn test_disarmed_monitor() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let config = crate::MonitorConfig {
+            min_interval: std::time::Duration::from_millis(5),
+            max_interval: std::time::Duration::from_millis(10),
+            every_poll: true,
+            ..Default::default()
+        };
+        let handle = crate::Monitor::start(config, move |report| sender.send(report).unwrap());
+        let report = receiver.recv().unwrap();
+        assert!(report.all_clean(), "{report}");
+        handle.join().unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "dev-override")]
+    fn test_override_matches() {
+        use std::ffi::OsStr;
+
+        assert!(super::override_matches(Some("secret"), Some(OsStr::new("secret"))));
+        assert!(!super::override_matches(Some("secret"), Some(OsStr::new("secreT"))));
+        assert!(!super::override_matches(Some("secret"), Some(OsStr::new("secret!"))));
+        assert!(!super::override_matches(Some("secret"), None));
+        assert!(!super::override_matches(None, Some(OsStr::new("secret"))));
+        assert!(!super::override_matches(Some(""), Some(OsStr::new(""))));
+    }
+
+    #[test]
+    #[cfg(feature = "dev-override")]
+    fn test_init() {
+        // A matching token would disarm the other tests, so only check that no token never disarms.
+        assert!(!super::init(super::InitConfig::default()));
+        assert_eq!(super::disarmed(), cfg!(feature = "disarm"));
+    }
+}
@@ -0,0 +1,155 @@
+//! Hiding memory from dumps.
+
+use crate::AntiDebugError;
+
+/// Marks the pages containing `len` bytes at `ptr` as inaccessible, so they can't be dumped.
+///
+/// Requires the `anti-dump` feature. Memory dumpers reading the process, such as through
+/// `/proc/<pid>/mem` or `ReadProcessMemory`, fail on the pages or skip them.
+/// Use [`mark_region_executable`] or [`protect_function`] to access them again.
+///
+/// # Platform-specific Behavior
+///
+/// - **Windows**: Uses `VirtualProtect` with `PAGE_NOACCESS`.
+/// - **Unix**: Uses `mprotect` with `PROT_NONE`.
+///
+/// # Safety
+///
+/// The protection applies to whole pages, so everything sharing a page with the region,
+/// such as neighbouring functions or data, becomes inaccessible as well.
+/// Any access to the pages, including executing them, crashes the process until their access is restored.
+/// The region must not contain the stack, the code calling this, or memory which is still referenced.
+pub unsafe fn mark_region_no_access(ptr: *const u8, len: usize) -> Result<(), AntiDebugError> {
+    #[cfg(target_os = "windows")]
+    let protection = windows_sys::Win32::System::Memory::PAGE_NOACCESS;
+    #[cfg(not(target_os = "windows"))]
+    let protection = libc::PROT_NONE;
+    unsafe { protect(ptr, len, protection) }
+}
+
+/// Marks the pages containing `len` bytes at `ptr` as readable and executable,
+/// which is the protection of code, such as after [`mark_region_no_access`].
+///
+/// Requires the `anti-dump` feature.
+///
+/// # Safety
+///
+/// The protection applies to whole pages, and removes write access from the data sharing them.
+/// The pages must be mapped.
+pub unsafe fn mark_region_executable(ptr: *const u8, len: usize) -> Result<(), AntiDebugError> {
+    #[cfg(target_os = "windows")]
+    let protection = windows_sys::Win32::System::Memory::PAGE_EXECUTE_READ;
+    #[cfg(not(target_os = "windows"))]
+    let protection = libc::PROT_READ | libc::PROT_EXEC;
+    unsafe { protect(ptr, len, protection) }
+}
+
+/// Calls `f` while the code of `len` bytes at `code` is accessible, keeping it inaccessible otherwise.
+///
+/// Requires the `anti-dump` feature. The code is made executable with [`mark_region_executable`]
+/// before calling `f`, and inaccessible with [`mark_region_no_access`] after `f` returns or panics,
+/// so a dump taken outside of `f` doesn't contain the code.
+///
+/// The address of a closure's code can't be taken, so `code` and `len` are the range of the
+/// function `f` calls, such as `sensitive as *const u8` for a non-inlined `fn sensitive()`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// #[inline(never)]
+/// fn sensitive() -> u32 {
+///     42
+/// }
+///
+/// # fn main() {
+/// let code = sensitive as *const u8;
+/// // SAFETY: `sensitive` must share its pages only with code unused meanwhile,
+/// // such as by placing it in a page-aligned section of its own.
+/// unsafe { anti_debug::mark_region_no_access(code, 64).unwrap() };
+/// let answer = unsafe { anti_debug::protect_function(code, 64, sensitive) }.unwrap();
+/// assert_eq!(answer, 42);
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns the error of changing the protection, in which case `f` is not called,
+/// or its result is discarded if the code could not be made inaccessible again.
+///
+/// # Safety
+///
+/// The same requirements as for [`mark_region_no_access`] apply, including while `f` runs on other threads.
+pub unsafe fn protect_function<R>(code: *const u8, len: usize, f: impl FnOnce() -> R) -> Result<R, AntiDebugError> {
+    /// Makes the code inaccessible again on drop, even if `f` panics.
+    struct Guard {
+        code: *const u8,
+        len: usize,
+    }
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            // Unwinding can't report errors, and a failure leaves the code accessible but working.
+            let _ = unsafe { mark_region_no_access(self.code, self.len) };
+        }
+    }
+
+    unsafe { mark_region_executable(code, len)? };
+    let guard = Guard { code, len };
+    let result = f();
+    core::mem::forget(guard);
+    unsafe { mark_region_no_access(code, len)? };
+    Ok(result)
+}
+
+/// Changes the protection of the pages containing `len` bytes at `ptr`.
+#[cfg(target_os = "windows")]
+unsafe fn protect(ptr: *const u8, len: usize, protection: u32) -> Result<(), AntiDebugError> {
+    // `VirtualProtect` rounds the range out to whole pages itself.
+    let mut previous = 0;
+    let result = unsafe { windows_sys::Win32::System::Memory::VirtualProtect(ptr.cast(), len, protection, &mut previous) };
+    if result == windows_sys::Win32::Foundation::FALSE {
+        return Err(crate::error::last_os_error!(MemoryProtectFailed).into());
+    }
+    Ok(())
+}
+
+/// Changes the protection of the pages containing `len` bytes at `ptr`.
+#[cfg(not(target_os = "windows"))]
+unsafe fn protect(ptr: *const u8, len: usize, protection: libc::c_int) -> Result<(), AntiDebugError> {
+    // `mprotect` requires a page-aligned address, so round the range out to whole pages.
+    let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+    let start = ptr as usize & !(page - 1);
+    let end = (ptr as usize).saturating_add(len.max(1)).next_multiple_of(page);
+    let result = unsafe { libc::mprotect(ptr.with_addr(start).cast_mut().cast(), end - start, protection) };
+    if result == -1 {
+        return Err(crate::error::last_os_error!(MemoryProtectFailed).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    /// A page of its own, and of every supported page size.
+    #[repr(C, align(65536))]
+    struct Page([u8; 65536]);
+
+    static PAGE: Page = Page([7; 65536]);
+
+    #[test]
+    fn test_protect_function() {
+        let ptr = PAGE.0.as_ptr();
+        unsafe {
+            super::mark_region_no_access(ptr.add(100), 10).unwrap();
+            let value = super::protect_function(ptr.add(100), 10, || ptr.add(4000).read_volatile()).unwrap();
+            assert_eq!(value, 7);
+            super::mark_region_executable(ptr, PAGE.0.len()).unwrap();
+            assert_eq!(ptr.add(100).read_volatile(), 7);
+        }
+    }
+
+    #[test]
+    fn test_protect_unmapped() {
+        let error = unsafe { super::mark_region_no_access(core::ptr::null(), 1) }.unwrap_err();
+        assert!(matches!(error, crate::AntiDebugError::OsError(_) | crate::AntiDebugError::PermissionDenied), "{error}");
+    }
+}
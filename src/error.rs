@@ -0,0 +1,562 @@
+/// The error returned when a debugger check could not be performed.
+///
+/// Each variant names the detection step that failed,
+/// so callers can tell "couldn't read `/proc/self/status`" from "found a tracer".
+#[non_exhaustive]
+pub enum DetectError {
+    /// `/proc/self/status` could not be read.
+    #[cfg(feature = "std")]
+    ProcStatusUnreadable(std::io::Error),
+    /// The `TracerPid` field of `/proc/self/status` is missing or malformed.
+    ///
+    /// Contains the raw value of the field, which is empty if the field is missing.
+    InvalidTracerPid(alloc::string::String),
+    /// The `ptrace` probe could not be performed.
+    #[cfg(feature = "std")]
+    PtraceFailed(std::io::Error),
+    /// `CheckRemoteDebuggerPresent` failed.
+    #[cfg(feature = "std")]
+    RemoteDebuggerCheckFailed(std::io::Error),
+    /// `NtQueryInformationProcess` failed with the contained `NTSTATUS`.
+    NtQueryFailed(i32),
+    /// `NtQuerySystemInformation` failed with the contained `NTSTATUS`.
+    SystemQueryFailed(i32),
+    /// `NtSetInformationThread(ThreadHideFromDebugger)` failed with the contained `NTSTATUS`.
+    HideThreadFailed(i32),
+    /// `IsWow64Process` failed.
+    #[cfg(feature = "std")]
+    Wow64CheckFailed(std::io::Error),
+    /// `GetThreadContext` or `SetThreadContext` failed.
+    #[cfg(feature = "std")]
+    ThreadContextFailed(std::io::Error),
+    /// `GetProcessHeap` returned a null heap.
+    ProcessHeapUnavailable,
+    /// `AddVectoredExceptionHandler` failed to install the exception handler.
+    ExceptionHandlerUnavailable,
+    /// Handle tracing is enabled, which makes invalid handles raise exceptions without a debugger.
+    HandleTracingEnabled,
+    /// `/proc/self/maps` could not be read.
+    #[cfg(feature = "std")]
+    ProcMapsUnreadable(std::io::Error),
+    /// The `wchan` of a thread under `/proc/self/task` could not be read.
+    #[cfg(feature = "std")]
+    ProcWchanUnreadable(std::io::Error),
+    /// The memory of the current process could not be read.
+    #[cfg(feature = "std")]
+    MemoryUnreadable(std::io::Error),
+    /// The executable of the current process could not be read from disk.
+    #[cfg(feature = "std")]
+    ExecutableUnreadable(std::io::Error),
+    /// The parent process could not be queried.
+    #[cfg(feature = "std")]
+    ParentProcessQueryFailed(std::io::Error),
+    /// The running processes could not be enumerated.
+    #[cfg(feature = "std")]
+    ProcessSnapshotFailed(std::io::Error),
+    /// The registry could not be queried.
+    #[cfg(feature = "std")]
+    RegistryQueryFailed(std::io::Error),
+    /// The monotonic clock could not be read.
+    #[cfg(feature = "std")]
+    ClockFailed(std::io::Error),
+    /// `sigaction` failed to install the signal handler.
+    #[cfg(feature = "std")]
+    SignalHandlerFailed(std::io::Error),
+    /// `proc_pidinfo` failed.
+    ///
+    /// Contains the message of the OS error, which is reported by `libproc` with the `macos-libproc` feature,
+    /// and is only the `errno` without the `std` feature.
+    ProcPidInfoFailed(alloc::string::String),
+    /// `sysctl` failed.
+    #[cfg(feature = "std")]
+    SysctlFailed(std::io::Error),
+    /// `task_set_exception_ports` failed with the contained `kern_return_t`.
+    ExceptionPortsFailed(i32),
+    /// `ptrace(PT_DENY_ATTACH)`, `procctl(PROC_TRACE_CTL)` or `prctl(PR_SET_DUMPABLE)` failed.
+    #[cfg(feature = "std")]
+    DenyAttachFailed(std::io::Error),
+    /// `mprotect` or `VirtualProtect` failed to change the protection of memory.
+    #[cfg(feature = "std")]
+    MemoryProtectFailed(std::io::Error),
+    /// The check is not supported on the current platform.
+    Unsupported,
+    /// A system call failed with the contained OS error code.
+    ///
+    /// Reported in place of the variants carrying a `std::io::Error` without the `std` feature.
+    OsError(i32),
+}
+
+impl DetectError {
+    /// Returns the name of the API or file whose failure caused this error, such as `sysctl`.
+    ///
+    /// Returns `None` if the error isn't caused by a failed call,
+    /// or if the call is unknown as for [`DetectError::OsError`].
+    pub fn api(&self) -> Option<&'static str> {
+        match self {
+            #[cfg(feature = "std")]
+            DetectError::ProcStatusUnreadable(_) => Some(crate::obfuscate::obfuscated_str!("/proc/self/status")),
+            DetectError::InvalidTracerPid(_) => Some(crate::obfuscate::obfuscated_str!("/proc/self/status")),
+            #[cfg(feature = "std")]
+            DetectError::PtraceFailed(_) => Some("ptrace"),
+            #[cfg(feature = "std")]
+            DetectError::RemoteDebuggerCheckFailed(_) => Some("CheckRemoteDebuggerPresent"),
+            DetectError::NtQueryFailed(_) => Some("NtQueryInformationProcess"),
+            DetectError::SystemQueryFailed(_) => Some("NtQuerySystemInformation"),
+            DetectError::HideThreadFailed(_) => Some("NtSetInformationThread"),
+            #[cfg(feature = "std")]
+            DetectError::Wow64CheckFailed(_) => Some("IsWow64Process"),
+            #[cfg(feature = "std")]
+            DetectError::ThreadContextFailed(_) => Some("GetThreadContext"),
+            DetectError::ProcessHeapUnavailable => Some("GetProcessHeap"),
+            DetectError::ExceptionHandlerUnavailable => Some("AddVectoredExceptionHandler"),
+            #[cfg(feature = "std")]
+            DetectError::ProcMapsUnreadable(_) => Some(crate::obfuscate::obfuscated_str!("/proc/self/maps")),
+            #[cfg(feature = "std")]
+            DetectError::ProcWchanUnreadable(_) => Some(crate::obfuscate::obfuscated_str!("/proc/self/task/*/wchan")),
+            #[cfg(feature = "std")]
+            DetectError::MemoryUnreadable(_) => Some(if cfg!(target_os = "windows") {
+                "ReadProcessMemory"
+            } else if cfg!(target_os = "macos") {
+                "mach_vm_read_overwrite"
+            } else {
+                "process_vm_readv"
+            }),
+            #[cfg(feature = "std")]
+            DetectError::ExecutableUnreadable(_) => Some("executable"),
+            #[cfg(feature = "std")]
+            DetectError::ParentProcessQueryFailed(_) => Some(if cfg!(target_os = "windows") {
+                "CreateToolhelp32Snapshot"
+            } else if cfg!(any(target_os = "linux", target_os = "android")) {
+                crate::obfuscate::obfuscated_str!("/proc/self/stat")
+            } else if cfg!(target_os = "macos") {
+                "proc_name"
+            } else {
+                "sysctl"
+            }),
+            #[cfg(feature = "std")]
+            DetectError::ProcessSnapshotFailed(_) => Some("CreateToolhelp32Snapshot"),
+            #[cfg(feature = "std")]
+            DetectError::RegistryQueryFailed(_) => Some("RegGetValueW"),
+            #[cfg(feature = "std")]
+            DetectError::ClockFailed(_) => Some("clock_gettime"),
+            #[cfg(feature = "std")]
+            DetectError::SignalHandlerFailed(_) => Some("sigaction"),
+            DetectError::ProcPidInfoFailed(_) => Some("proc_pidinfo"),
+            #[cfg(feature = "std")]
+            DetectError::SysctlFailed(_) => Some("sysctl"),
+            DetectError::ExceptionPortsFailed(_) => Some("task_set_exception_ports"),
+            #[cfg(feature = "std")]
+            DetectError::DenyAttachFailed(_) => Some(if cfg!(target_os = "freebsd") {
+                "procctl"
+            } else if cfg!(any(target_os = "linux", target_os = "android")) {
+                "prctl"
+            } else {
+                "ptrace"
+            }),
+            #[cfg(feature = "std")]
+            DetectError::MemoryProtectFailed(_) => Some(if cfg!(target_os = "windows") { "VirtualProtect" } else { "mprotect" }),
+            DetectError::HandleTracingEnabled => None,
+            DetectError::Unsupported => None,
+            DetectError::OsError(_) => None,
+        }
+    }
+
+    /// Returns a stable tag naming the kind of this error, which is the name of the variant.
+    ///
+    /// Unlike [`Display`](core::fmt::Display), the tag doesn't contain any OS-specific message,
+    /// so it is suitable for aggregating errors, such as in telemetry.
+    /// With the `serde` feature, the error is serialized as this tag.
+    pub fn tag(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "std")]
+            DetectError::ProcStatusUnreadable(_) => "ProcStatusUnreadable",
+            DetectError::InvalidTracerPid(_) => crate::obfuscate::obfuscated_str!("InvalidTracerPid"),
+            #[cfg(feature = "std")]
+            DetectError::PtraceFailed(_) => "PtraceFailed",
+            #[cfg(feature = "std")]
+            DetectError::RemoteDebuggerCheckFailed(_) => "RemoteDebuggerCheckFailed",
+            DetectError::NtQueryFailed(_) => "NtQueryFailed",
+            DetectError::SystemQueryFailed(_) => "SystemQueryFailed",
+            DetectError::HideThreadFailed(_) => "HideThreadFailed",
+            #[cfg(feature = "std")]
+            DetectError::Wow64CheckFailed(_) => "Wow64CheckFailed",
+            #[cfg(feature = "std")]
+            DetectError::ThreadContextFailed(_) => "ThreadContextFailed",
+            DetectError::ProcessHeapUnavailable => "ProcessHeapUnavailable",
+            DetectError::ExceptionHandlerUnavailable => "ExceptionHandlerUnavailable",
+            DetectError::HandleTracingEnabled => "HandleTracingEnabled",
+            #[cfg(feature = "std")]
+            DetectError::ProcMapsUnreadable(_) => "ProcMapsUnreadable",
+            #[cfg(feature = "std")]
+            DetectError::ProcWchanUnreadable(_) => "ProcWchanUnreadable",
+            #[cfg(feature = "std")]
+            DetectError::MemoryUnreadable(_) => "MemoryUnreadable",
+            #[cfg(feature = "std")]
+            DetectError::ExecutableUnreadable(_) => "ExecutableUnreadable",
+            #[cfg(feature = "std")]
+            DetectError::ParentProcessQueryFailed(_) => "ParentProcessQueryFailed",
+            #[cfg(feature = "std")]
+            DetectError::ProcessSnapshotFailed(_) => "ProcessSnapshotFailed",
+            #[cfg(feature = "std")]
+            DetectError::RegistryQueryFailed(_) => "RegistryQueryFailed",
+            #[cfg(feature = "std")]
+            DetectError::ClockFailed(_) => "ClockFailed",
+            #[cfg(feature = "std")]
+            DetectError::SignalHandlerFailed(_) => "SignalHandlerFailed",
+            DetectError::ProcPidInfoFailed(_) => "ProcPidInfoFailed",
+            #[cfg(feature = "std")]
+            DetectError::SysctlFailed(_) => "SysctlFailed",
+            DetectError::ExceptionPortsFailed(_) => "ExceptionPortsFailed",
+            #[cfg(feature = "std")]
+            DetectError::DenyAttachFailed(_) => "DenyAttachFailed",
+            #[cfg(feature = "std")]
+            DetectError::MemoryProtectFailed(_) => "MemoryProtectFailed",
+            DetectError::Unsupported => "Unsupported",
+            DetectError::OsError(_) => "OsError",
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for DetectError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.tag())
+    }
+}
+
+impl core::fmt::Display for DetectError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            DetectError::ProcStatusUnreadable(e) => write!(f, "failed to read {}: {e}", crate::obfuscate::obfuscated_str!("/proc/self/status")),
+            DetectError::InvalidTracerPid(raw) => {
+                write!(f, "invalid {} {raw:?} in {}", crate::obfuscate::obfuscated_str!("TracerPid"), crate::obfuscate::obfuscated_str!("/proc/self/status"))
+            }
+            #[cfg(feature = "std")]
+            DetectError::PtraceFailed(e) => write!(f, "failed to probe with ptrace: {e}"),
+            #[cfg(feature = "std")]
+            DetectError::RemoteDebuggerCheckFailed(e) => write!(f, "CheckRemoteDebuggerPresent failed: {e}"),
+            DetectError::NtQueryFailed(status) => write!(f, "NtQueryInformationProcess failed with status {:#010X}", *status as u32),
+            DetectError::SystemQueryFailed(status) => write!(f, "NtQuerySystemInformation failed with status {:#010X}", *status as u32),
+            DetectError::HideThreadFailed(status) => write!(f, "failed to hide thread from debugger with status {:#010X}", *status as u32),
+            #[cfg(feature = "std")]
+            DetectError::Wow64CheckFailed(e) => write!(f, "IsWow64Process failed: {e}"),
+            #[cfg(feature = "std")]
+            DetectError::ThreadContextFailed(e) => write!(f, "failed to access thread context: {e}"),
+            DetectError::ProcessHeapUnavailable => write!(f, "process heap unavailable"),
+            DetectError::ExceptionHandlerUnavailable => write!(f, "failed to install exception handler"),
+            DetectError::HandleTracingEnabled => write!(f, "handle tracing is enabled"),
+            #[cfg(feature = "std")]
+            DetectError::ProcMapsUnreadable(e) => write!(f, "failed to read {}: {e}", crate::obfuscate::obfuscated_str!("/proc/self/maps")),
+            #[cfg(feature = "std")]
+            DetectError::ProcWchanUnreadable(e) => write!(f, "failed to read wchan: {e}"),
+            #[cfg(feature = "std")]
+            DetectError::MemoryUnreadable(e) => write!(f, "failed to read memory: {e}"),
+            #[cfg(feature = "std")]
+            DetectError::ExecutableUnreadable(e) => write!(f, "failed to read the executable: {e}"),
+            #[cfg(feature = "std")]
+            DetectError::ParentProcessQueryFailed(e) => write!(f, "failed to query the parent process: {e}"),
+            #[cfg(feature = "std")]
+            DetectError::ProcessSnapshotFailed(e) => write!(f, "failed to enumerate processes: {e}"),
+            #[cfg(feature = "std")]
+            DetectError::RegistryQueryFailed(e) => write!(f, "failed to query the registry: {e}"),
+            #[cfg(feature = "std")]
+            DetectError::ClockFailed(e) => write!(f, "failed to read the monotonic clock: {e}"),
+            #[cfg(feature = "std")]
+            DetectError::SignalHandlerFailed(e) => write!(f, "failed to install the signal handler: {e}"),
+            DetectError::ProcPidInfoFailed(message) => write!(f, "proc_pidinfo failed: {message}"),
+            #[cfg(feature = "std")]
+            DetectError::SysctlFailed(e) => write!(f, "sysctl failed: {e}"),
+            DetectError::ExceptionPortsFailed(code) => write!(f, "task_set_exception_ports failed with {code}"),
+            #[cfg(feature = "std")]
+            DetectError::DenyAttachFailed(e) => write!(f, "failed to deny attach: {e}"),
+            #[cfg(feature = "std")]
+            DetectError::MemoryProtectFailed(e) => write!(f, "failed to change memory protection: {e}"),
+            DetectError::Unsupported => write!(f, "unsupported on current platform"),
+            DetectError::OsError(code) => write!(f, "system call failed with OS error {code}"),
+        }
+    }
+}
+
+// Written by hand with the names of `tag`, so the names obfuscated there don't appear in the binary.
+impl core::fmt::Debug for DetectError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut tuple = f.debug_tuple(self.tag());
+        match self {
+            #[cfg(feature = "std")]
+            DetectError::ProcStatusUnreadable(e) |
+            DetectError::PtraceFailed(e) |
+            DetectError::RemoteDebuggerCheckFailed(e) |
+            DetectError::Wow64CheckFailed(e) |
+            DetectError::ThreadContextFailed(e) |
+            DetectError::ProcMapsUnreadable(e) |
+            DetectError::ProcWchanUnreadable(e) |
+            DetectError::MemoryUnreadable(e) |
+            DetectError::ExecutableUnreadable(e) |
+            DetectError::ParentProcessQueryFailed(e) |
+            DetectError::ProcessSnapshotFailed(e) |
+            DetectError::RegistryQueryFailed(e) |
+            DetectError::ClockFailed(e) |
+            DetectError::SignalHandlerFailed(e) |
+            DetectError::SysctlFailed(e) |
+            DetectError::DenyAttachFailed(e) |
+            DetectError::MemoryProtectFailed(e) => { tuple.field(e); }
+            DetectError::InvalidTracerPid(message) | DetectError::ProcPidInfoFailed(message) => { tuple.field(message); }
+            DetectError::NtQueryFailed(code) |
+            DetectError::SystemQueryFailed(code) |
+            DetectError::HideThreadFailed(code) |
+            DetectError::ExceptionPortsFailed(code) |
+            DetectError::OsError(code) => { tuple.field(code); }
+            DetectError::ProcessHeapUnavailable |
+            DetectError::ExceptionHandlerUnavailable |
+            DetectError::HandleTracingEnabled |
+            DetectError::Unsupported => {}
+        }
+        tuple.finish()
+    }
+}
+
+impl core::error::Error for DetectError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            DetectError::ProcStatusUnreadable(e) |
+            DetectError::PtraceFailed(e) |
+            DetectError::RemoteDebuggerCheckFailed(e) |
+            DetectError::Wow64CheckFailed(e) |
+            DetectError::ThreadContextFailed(e) |
+            DetectError::ProcMapsUnreadable(e) |
+            DetectError::ProcWchanUnreadable(e) |
+            DetectError::MemoryUnreadable(e) |
+            DetectError::ExecutableUnreadable(e) |
+            DetectError::ParentProcessQueryFailed(e) |
+            DetectError::ProcessSnapshotFailed(e) |
+            DetectError::RegistryQueryFailed(e) |
+            DetectError::ClockFailed(e) |
+            DetectError::SignalHandlerFailed(e) |
+            DetectError::SysctlFailed(e) |
+            DetectError::DenyAttachFailed(e) |
+            DetectError::MemoryProtectFailed(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<DetectError> for std::io::Error {
+    fn from(error: DetectError) -> Self {
+        match error {
+            DetectError::ProcStatusUnreadable(e) |
+            DetectError::PtraceFailed(e) |
+            DetectError::RemoteDebuggerCheckFailed(e) |
+            DetectError::Wow64CheckFailed(e) |
+            DetectError::ThreadContextFailed(e) |
+            DetectError::ProcMapsUnreadable(e) |
+            DetectError::ProcWchanUnreadable(e) |
+            DetectError::MemoryUnreadable(e) |
+            DetectError::ExecutableUnreadable(e) |
+            DetectError::ParentProcessQueryFailed(e) |
+            DetectError::ProcessSnapshotFailed(e) |
+            DetectError::RegistryQueryFailed(e) |
+            DetectError::ClockFailed(e) |
+            DetectError::SignalHandlerFailed(e) |
+            DetectError::SysctlFailed(e) |
+            DetectError::DenyAttachFailed(e) |
+            DetectError::MemoryProtectFailed(e) => e,
+            #[cfg(target_os = "windows")]
+            DetectError::NtQueryFailed(status) | DetectError::SystemQueryFailed(status) | DetectError::HideThreadFailed(status) => {
+                let error = unsafe { windows_sys::Win32::Foundation::RtlNtStatusToDosError(status) };
+                std::io::Error::from_raw_os_error(error as _)
+            }
+            DetectError::InvalidTracerPid(_) => std::io::Error::new(std::io::ErrorKind::InvalidData, error),
+            DetectError::Unsupported => std::io::Error::new(std::io::ErrorKind::Unsupported, error),
+            DetectError::OsError(code) => std::io::Error::from_raw_os_error(code),
+            error => std::io::Error::other(error),
+        }
+    }
+}
+
+/// The error returned by [`is_debugger_present`](crate::is_debugger_present) and [`deny_attach`](crate::deny_attach).
+///
+/// This classifies the failure coarsely, so callers can react to it without knowing the
+/// platform-specific detection steps. Use [`detect_debugger`](crate::detect_debugger)
+/// to get the [`DetectError`] naming the failed step instead.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum AntiDebugError {
+    /// A system call failed.
+    #[cfg(feature = "std")]
+    OsError(std::io::Error),
+    /// A field reported by the system could not be parsed.
+    ParseError {
+        /// The name of the field, such as `TracerPid`.
+        field: &'static str,
+        /// The raw value of the field, which is empty if the field is missing.
+        raw: alloc::string::String,
+    },
+    /// The check was denied by the system.
+    PermissionDenied,
+    /// The check is not supported on the current platform.
+    PlatformNotSupported,
+    /// The contained API is not available on the current system.
+    ApiNotAvailable(&'static str),
+    /// A debugger is already attached, so it can't be denied anymore.
+    DebuggerPresent,
+    /// A detection step failed for a reason not covered by the other variants.
+    Detect(DetectError),
+}
+
+impl core::fmt::Display for AntiDebugError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            AntiDebugError::OsError(e) => write!(f, "system call failed: {e}"),
+            AntiDebugError::ParseError { field, raw } => write!(f, "failed to parse {field} from {raw:?}"),
+            AntiDebugError::PermissionDenied => write!(f, "permission denied"),
+            AntiDebugError::PlatformNotSupported => write!(f, "unsupported on current platform"),
+            AntiDebugError::ApiNotAvailable(api) => write!(f, "{api} is not available"),
+            AntiDebugError::DebuggerPresent => write!(f, "debugger present"),
+            AntiDebugError::Detect(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl core::error::Error for AntiDebugError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            AntiDebugError::OsError(e) => Some(e),
+            AntiDebugError::Detect(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<DetectError> for AntiDebugError {
+    fn from(error: DetectError) -> Self {
+        match error {
+            #[cfg(feature = "std")]
+            DetectError::ProcStatusUnreadable(e) |
+            DetectError::PtraceFailed(e) |
+            DetectError::RemoteDebuggerCheckFailed(e) |
+            DetectError::Wow64CheckFailed(e) |
+            DetectError::ThreadContextFailed(e) |
+            DetectError::ProcMapsUnreadable(e) |
+            DetectError::ProcWchanUnreadable(e) |
+            DetectError::MemoryUnreadable(e) |
+            DetectError::ExecutableUnreadable(e) |
+            DetectError::ParentProcessQueryFailed(e) |
+            DetectError::ProcessSnapshotFailed(e) |
+            DetectError::RegistryQueryFailed(e) |
+            DetectError::ClockFailed(e) |
+            DetectError::SignalHandlerFailed(e) |
+            DetectError::SysctlFailed(e) |
+            DetectError::DenyAttachFailed(e) |
+            DetectError::MemoryProtectFailed(e) => match e.kind() {
+                std::io::ErrorKind::PermissionDenied => AntiDebugError::PermissionDenied,
+                _ => AntiDebugError::OsError(e),
+            },
+            DetectError::InvalidTracerPid(raw) => AntiDebugError::ParseError { field: crate::obfuscate::obfuscated_str!("TracerPid"), raw },
+            #[cfg(target_os = "windows")]
+            DetectError::NtQueryFailed(windows_sys::Win32::Foundation::STATUS_ACCESS_DENIED) |
+            DetectError::SystemQueryFailed(windows_sys::Win32::Foundation::STATUS_ACCESS_DENIED) |
+            DetectError::HideThreadFailed(windows_sys::Win32::Foundation::STATUS_ACCESS_DENIED) => AntiDebugError::PermissionDenied,
+            #[cfg(target_os = "windows")]
+            DetectError::NtQueryFailed(windows_sys::Win32::Foundation::STATUS_NOT_IMPLEMENTED | windows_sys::Win32::Foundation::STATUS_INVALID_INFO_CLASS) => {
+                AntiDebugError::ApiNotAvailable("NtQueryInformationProcess")
+            }
+            #[cfg(target_os = "windows")]
+            DetectError::SystemQueryFailed(windows_sys::Win32::Foundation::STATUS_NOT_IMPLEMENTED | windows_sys::Win32::Foundation::STATUS_INVALID_INFO_CLASS) => {
+                AntiDebugError::ApiNotAvailable("NtQuerySystemInformation")
+            }
+            #[cfg(all(target_os = "windows", feature = "std"))]
+            error @ (DetectError::NtQueryFailed(_) | DetectError::SystemQueryFailed(_) | DetectError::HideThreadFailed(_)) => AntiDebugError::OsError(error.into()),
+            DetectError::Unsupported => AntiDebugError::PlatformNotSupported,
+            error => AntiDebugError::Detect(error),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<AntiDebugError> for std::io::Error {
+    fn from(error: AntiDebugError) -> Self {
+        match error {
+            AntiDebugError::OsError(e) => e,
+            AntiDebugError::Detect(e) => e.into(),
+            AntiDebugError::ParseError { .. } => std::io::Error::new(std::io::ErrorKind::InvalidData, error),
+            AntiDebugError::PermissionDenied => std::io::Error::new(std::io::ErrorKind::PermissionDenied, error),
+            AntiDebugError::PlatformNotSupported |
+            AntiDebugError::ApiNotAvailable(_) => std::io::Error::new(std::io::ErrorKind::Unsupported, error),
+            AntiDebugError::DebuggerPresent => std::io::Error::new(std::io::ErrorKind::AlreadyExists, error),
+        }
+    }
+}
+
+/// Returns the last OS error code of the current thread, i.e. `errno` or `GetLastError`.
+#[allow(dead_code)]
+pub(crate) fn raw_os_error() -> i32 {
+    #[cfg(target_os = "windows")]
+    unsafe { windows_sys::Win32::Foundation::GetLastError() as i32 }
+    #[cfg(target_os = "linux")]
+    unsafe { *libc::__errno_location() }
+    #[cfg(any(target_os = "android", target_os = "netbsd", target_os = "openbsd"))]
+    unsafe { *libc::__errno() }
+    #[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+    unsafe { *libc::__error() }
+}
+
+/// Builds `DetectError::$variant` from the last OS error,
+/// or `DetectError::OsError` without the `std` feature.
+#[allow(unused_macros)]
+macro_rules! last_os_error {
+    ($variant:ident) => {{
+        #[cfg(feature = "std")]
+        let error = $crate::DetectError::$variant(std::io::Error::last_os_error());
+        #[cfg(not(feature = "std"))]
+        let error = $crate::DetectError::OsError($crate::error::raw_os_error());
+        error
+    }};
+}
+#[allow(unused_imports)]
+pub(crate) use last_os_error;
+
+/// Builds `DetectError::$variant` from the OS error `$code`, like [`last_os_error!`].
+#[allow(unused_macros)]
+macro_rules! os_error {
+    ($variant:ident, $code:expr) => {{
+        #[cfg(feature = "std")]
+        let error = $crate::DetectError::$variant(std::io::Error::from_raw_os_error($code));
+        #[cfg(not(feature = "std"))]
+        let error = $crate::DetectError::OsError($code);
+        error
+    }};
+}
+#[allow(unused_imports)]
+pub(crate) use os_error;
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_api() {
+        assert_eq!(super::DetectError::NtQueryFailed(0).api(), Some("NtQueryInformationProcess"));
+        assert_eq!(super::DetectError::InvalidTracerPid("x".into()).api(), Some("/proc/self/status"));
+        assert_eq!(super::DetectError::Unsupported.api(), None);
+        assert_eq!(super::DetectError::OsError(1).api(), None);
+        #[cfg(feature = "std")] {
+            assert_eq!(super::DetectError::ProcessSnapshotFailed(std::io::ErrorKind::Other.into()).api(), Some("CreateToolhelp32Snapshot"));
+            assert_eq!(super::DetectError::ClockFailed(std::io::ErrorKind::Other.into()).api(), Some("clock_gettime"));
+        }
+    }
+
+    #[test]
+    fn test_tag() {
+        assert_eq!(super::DetectError::NtQueryFailed(0).tag(), "NtQueryFailed");
+        assert_eq!(super::DetectError::Unsupported.tag(), "Unsupported");
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serialize() {
+        assert_eq!(serde_json::to_string(&super::DetectError::OsError(1)).unwrap(), r#""OsError""#);
+    }
+}
@@ -0,0 +1,93 @@
+//! Callbacks invoked when a debugger is detected.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{LazyLock, OnceLock, RwLock};
+use std::time::Duration;
+
+use crate::Watchdog;
+
+/// The interval the hook watchdog polls [`is_debugger_present`](crate::is_debugger_present) at.
+const HOOK_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A hook registered with [`register_detection_hook`].
+type Hook = Box<dyn Fn() + Send + Sync>;
+
+/// The hooks registered with [`register_detection_hook`].
+static HOOKS: LazyLock<RwLock<HashMap<HookId, Hook>>> = LazyLock::new(Default::default);
+/// The ID of the next hook.
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+/// The watchdog invoking the hooks, spawned by the first registration.
+static WATCHDOG: OnceLock<Watchdog> = OnceLock::new();
+
+/// The ID of a hook registered with [`register_detection_hook`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HookId(u64);
+
+/// Registers `hook` to be invoked as soon as a debugger is detected.
+///
+/// The first registration spawns a watchdog thread with [`spawn_watchdog`](crate::spawn_watchdog),
+/// which invokes every registered hook the first time
+/// [`is_debugger_present`](crate::is_debugger_present) returns `Ok(true)`.
+/// Hooks registered after the detection are not invoked.
+///
+/// Returns the [`HookId`] to remove the hook with [`unregister_detection_hook`].
+///
+/// # Examples
+///
+/// ```rust
+/// # fn main() {
+/// let id = anti_debug::register_detection_hook(Box::new(|| std::process::abort()));
+/// // ...
+/// anti_debug::unregister_detection_hook(id);
+/// # }
+/// ```
+pub fn register_detection_hook(hook: Box<dyn Fn() + Send + Sync>) -> HookId {
+    let id = HookId(NEXT_ID.fetch_add(1, Ordering::Relaxed));
+    HOOKS.write().unwrap_or_else(|e| e.into_inner()).insert(id, hook);
+    WATCHDOG.get_or_init(|| crate::spawn_watchdog(HOOK_POLL_INTERVAL, run_detection_hooks));
+    id
+}
+
+/// Removes the hook registered with [`register_detection_hook`] as `id`.
+///
+/// Returns `false` if no hook is registered as `id`.
+pub fn unregister_detection_hook(id: HookId) -> bool {
+    HOOKS.write().unwrap_or_else(|e| e.into_inner()).remove(&id).is_some()
+}
+
+/// Invokes every registered hook once.
+///
+/// The hooks are taken out of the registry first, so a hook may register or unregister hooks
+/// without deadlocking.
+fn run_detection_hooks() {
+    let hooks = std::mem::take(&mut *HOOKS.write().unwrap_or_else(|e| e.into_inner()));
+    for hook in hooks.into_values() {
+        hook();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_register_detection_hook() {
+        let id = super::register_detection_hook(Box::new(|| panic!("debugger detected")));
+        let other = super::register_detection_hook(Box::new(|| {}));
+        assert_ne!(id, other);
+        assert!(super::unregister_detection_hook(id));
+        assert!(!super::unregister_detection_hook(id));
+        assert!(super::unregister_detection_hook(other));
+
+        // Hooks may use the registry, and run only once.
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let id = super::register_detection_hook(Box::new(move || {
+            let id = super::register_detection_hook(Box::new(|| {}));
+            sender.send(super::unregister_detection_hook(id)).unwrap();
+        }));
+        super::run_detection_hooks();
+        assert_eq!(receiver.try_recv(), Ok(true));
+        super::run_detection_hooks();
+        assert!(receiver.try_recv().is_err());
+        assert!(!super::unregister_detection_hook(id));
+    }
+}
@@ -0,0 +1,80 @@
+//! Identifying the attached debugger.
+
+use crate::{DetectError, DetectionReason};
+
+/// The debugger found by [`debugger_info`].
+///
+/// The identity is best effort: `pid` and `name` are `None` if they could not be resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct DebuggerInfo {
+    /// The PID of the debugger.
+    pub pid: Option<u32>,
+    /// The process name of the debugger, such as `gdb`.
+    pub name: Option<String>,
+    /// The check that found the debugger.
+    pub reason: DetectionReason,
+}
+
+/// Checks if a debugger is attached like [`detect_debugger`](crate::detect_debugger), and identifies it.
+///
+/// The identity is looked up after the detection, so the debugger may have exited in between.
+/// Failures of the lookup never cause an error, they only leave the fields `None`.
+///
+/// # Platform-specific Behavior
+///
+/// - **Windows**: The PID is not resolved. The name is the one of the parent process
+///   if it is a known debugger, see [`debugger_parent_process`](crate::debugger_parent_process).
+/// - **Linux/Android**: The PID is the `TracerPid` field in `/proc/self/status`,
+///   and the name is read from `/proc/<pid>/comm`.
+/// - **macOS/iOS/FreeBSD/NetBSD/OpenBSD**: Attaching reparents the traced process to the debugger,
+///   so the PID and name are the ones of the parent process.
+///
+/// # Return Value
+///
+/// Returns `Ok(Some(info))` if a debugger is detected, `Ok(None)` if no debugger is present,
+/// or `Err(DetectError)` naming the step of the detection that could not be performed.
+pub fn debugger_info() -> Result<Option<DebuggerInfo>, DetectError> {
+    let Some(reason) = crate::detect_debugger()? else {
+        return Ok(None);
+    };
+    let (pid, name) = identify(reason);
+    Ok(Some(DebuggerInfo { pid, name, reason }))
+}
+
+/// Looks up the PID and name of the debugger found with `reason`.
+fn identify(reason: DetectionReason) -> (Option<u32>, Option<String>) {
+    #[cfg(target_os = "windows")] {
+        let _ = reason;
+        (None, crate::debugger_parent_process(&[]).ok().flatten())
+    }
+    #[cfg(any(target_os = "linux", target_os = "android"))] {
+        let pid = match reason {
+            DetectionReason::TracerPid(pid) => Some(pid as u32),
+            _ => crate::linux::tracer_pid().ok().flatten(),
+        };
+        let name = pid.and_then(|pid| crate::linux::read_comm(pid as i32).ok());
+        (pid, name)
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "android")))] {
+        let _ = reason;
+        let ppid = unsafe { libc::getppid() };
+        let name = crate::parent::parent_process_name().ok().flatten();
+        (Some(ppid as u32), name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_debugger_info() {
+        assert_eq!(super::debugger_info().unwrap_or(None), None);
+    }
+
+    #[test]
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn test_identify() {
+        assert_eq!(super::identify(crate::DetectionReason::HardwareBreakpoints), (None, None));
+    }
+}
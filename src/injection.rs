@@ -0,0 +1,133 @@
+//! Detecting libraries injected with the preload mechanisms of the dynamic linker.
+
+use crate::DetectError;
+
+/// Where an injected library was found by [`preload_injection_present`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum InjectionSource {
+    /// The `LD_PRELOAD` environment variable. (Linux/Android)
+    LdPreload,
+    /// The `LD_AUDIT` environment variable. (Linux/Android)
+    LdAudit,
+    /// The `/etc/ld.so.preload` file. (Linux)
+    PreloadFile,
+    /// Loaded at startup without being needed by the executable or its libraries,
+    /// such as a preload whose environment variable was cleared afterward. (Linux/Android)
+    Unneeded,
+    /// Mapped as executable code without being in the link map of the dynamic linker,
+    /// such as a library mapped by a loader of its own. (Linux/Android)
+    UnlinkedMapping,
+    /// The `DYLD_INSERT_LIBRARIES` environment variable. (macOS)
+    DyldInsertLibraries,
+    /// An image loaded from outside the app bundle and the system paths. (macOS)
+    ForeignImage,
+}
+
+/// A library found by [`preload_injection_present`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct InjectedLibrary {
+    /// The path of the library, as listed by its source.
+    pub path: String,
+    /// Where the library was found.
+    pub source: InjectionSource,
+}
+
+impl InjectedLibrary {
+    /// Creates a library found in `source`.
+    #[cfg_attr(not(any(target_os = "linux", target_os = "android", target_os = "macos")), allow(dead_code))]
+    pub(crate) fn new(path: impl Into<String>, source: InjectionSource) -> Self {
+        Self { path: path.into(), source }
+    }
+}
+
+/// The injected libraries found by [`preload_injection_present`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct InjectionInfo {
+    /// The libraries, each path listed once by the first source it was found in.
+    pub libraries: Vec<InjectedLibrary>,
+}
+
+impl InjectionInfo {
+    /// Returns the paths of the libraries.
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.libraries.iter().map(|library| library.path.as_str())
+    }
+}
+
+/// Checks for libraries injected into the current process with the preload mechanisms of the dynamic linker.
+///
+/// Hooks faking the results of the other checks are usually loaded this way.
+/// Libraries whose path starts with one of `allowed_prefixes` are not reported,
+/// so legitimate preloads, such as the runtime of AddressSanitizer or an allocator like jemalloc,
+/// can be allowed. The environment variables are often cleared by the injected library,
+/// so the loaded libraries are inspected as well.
+///
+/// # Platform-specific Behavior
+///
+/// - **Linux/Android**: Reads `LD_PRELOAD`, `LD_AUDIT` and `/etc/ld.so.preload`.
+///   Walks the link map of the dynamic linker for libraries loaded at startup
+///   that the executable and its libraries don't need, and `/proc/self/maps`
+///   for shared objects mapped as code outside of the link map.
+/// - **macOS**: Reads `DYLD_INSERT_LIBRARIES`, and enumerates the loaded images
+///   for ones outside the app bundle (or the directory of the executable) and the system paths
+///   `/usr/lib/`, `/System/` and `/Library/Apple/`.
+/// - **Other platforms**: Returns `Err(DetectError::Unsupported)`.
+///
+/// # Return Value
+///
+/// Returns `Ok(Some(info))` naming the injected libraries, `Ok(None)` if none is found,
+/// or `Err(DetectError)` if the check is unsupported or `/proc/self/maps` could not be read.
+pub fn preload_injection_present(allowed_prefixes: &[&str]) -> Result<Option<InjectionInfo>, DetectError> {
+    #[cfg(any(target_os = "linux", target_os = "android"))] {
+        Ok(collect(crate::linux::injected_libraries()?, allowed_prefixes))
+    }
+    #[cfg(target_os = "macos")] {
+        Ok(collect(crate::macos::injected_libraries(), allowed_prefixes))
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "android", target_os = "macos")))] {
+        let _ = allowed_prefixes;
+        Err(DetectError::Unsupported)
+    }
+}
+
+/// Removes the allowed and duplicated paths from `found`.
+#[cfg_attr(not(any(target_os = "linux", target_os = "android", target_os = "macos")), allow(dead_code))]
+fn collect(found: Vec<InjectedLibrary>, allowed_prefixes: &[&str]) -> Option<InjectionInfo> {
+    let mut info = InjectionInfo::default();
+    for library in found {
+        let allowed = allowed_prefixes.iter().any(|prefix| library.path.starts_with(prefix));
+        if !allowed && !info.paths().any(|path| path == library.path) {
+            info.libraries.push(library);
+        }
+    }
+    (!info.libraries.is_empty()).then_some(info)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_preload_injection_present() {
+        match super::preload_injection_present(&[]) {
+            Ok(info) => assert_eq!(info, None),
+            Err(e) => assert!(matches!(e, super::DetectError::Unsupported)),
+        }
+    }
+
+    #[test]
+    fn test_collect() {
+        use super::{InjectedLibrary, InjectionSource};
+
+        let found = vec![
+            InjectedLibrary::new("/usr/lib/libjemalloc.so.2", InjectionSource::LdPreload),
+            InjectedLibrary::new("/tmp/libhook.so", InjectionSource::LdPreload),
+            InjectedLibrary::new("/tmp/libhook.so", InjectionSource::Unneeded),
+        ];
+        let info = super::collect(found, &["/usr/lib/libjemalloc"]).unwrap();
+        assert_eq!(info.libraries, [InjectedLibrary::new("/tmp/libhook.so", InjectionSource::LdPreload)]);
+        assert_eq!(super::collect(Vec::new(), &[]), None);
+    }
+}
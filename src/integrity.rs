@@ -0,0 +1,153 @@
+//! Monitoring the integrity of the code of the current executable.
+
+use crate::DetectError;
+
+/// The size of the pages the code is hashed along, which is the smallest page size of the supported platforms.
+const PAGE_SIZE: usize = 0x1000;
+
+/// A snapshot of the hashes of the executable code of the current executable,
+/// to detect inline hooks, software breakpoints and other runtime patches.
+///
+/// Requires the `memory-integrity` feature. Take the snapshot with [`CodeIntegrity::new`]
+/// at startup, before a debugger could attach, and check it with [`CodeIntegrity::verify`] later.
+/// The snapshot is `Send` and `Sync`, so it can be verified periodically from a dedicated thread.
+///
+/// # Platform-specific Behavior
+///
+/// - **Windows**: Hashes the executable sections of the main module, located through its PE headers.
+/// - **Linux/Android**: Hashes the executable mappings of `/proc/self/exe` listed in `/proc/self/maps`.
+/// - **macOS**: Hashes the `__TEXT,__text` section of the main executable,
+///   located through the Mach-O header returned by `_dyld_get_image_header`.
+/// - **Other platforms**: [`CodeIntegrity::new`] returns `Err(DetectError::Unsupported)`.
+///
+/// The code is read through the OS as in [`scan_for_breakpoints`](crate::scan_for_breakpoints),
+/// so pages that can't be read, such as execute-only pages, are skipped
+/// instead of crashing the process.
+///
+/// # Notes
+///
+/// - The snapshot is taken after the loader applied relocations, so relocated code isn't reported.
+///   Import address tables live outside the executable sections, so resolved imports aren't hashed.
+/// - Only the executable itself is hashed, not shared libraries and dynamically generated code.
+///   The executable isn't hot-patched by the system, so its hot-patchable padding is hashed as well.
+/// - Patches applied before the snapshot is taken are not detected.
+///
+/// # Examples
+///
+/// ```rust
+/// # fn main() {
+/// let integrity = anti_debug::CodeIntegrity::new().unwrap();
+/// // ...
+/// for offset in integrity.verify().unwrap() {
+///     println!("Code page at offset {offset:#x} was modified");
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeIntegrity {
+    /// The base address of the executable, which the reported offsets are relative to.
+    base: usize,
+    /// The address, size and hash of the readable part of each page.
+    pages: Vec<(usize, usize, u64)>,
+}
+
+impl CodeIntegrity {
+    /// Takes a snapshot of the hashes of every page of the executable code.
+    ///
+    /// Returns `Err(DetectError)` if the code could not be located.
+    pub fn new() -> Result<Self, DetectError> {
+        let (base, regions) = executable_code_regions()?;
+        let mut pages = Vec::new();
+        let mut buffer = [0; PAGE_SIZE];
+        for (start, size) in regions {
+            for (address, size) in page_chunks(start, size) {
+                match crate::breakpoints::read_memory(address as *const u8, &mut buffer[..size]) {
+                    Ok(()) => pages.push((address, size, hash(&buffer[..size]))),
+                    Err(_e) => crate::log_warn!("skipping unreadable code page at {address:#x}: {_e}"),
+                }
+            }
+        }
+        Ok(Self { base, pages })
+    }
+
+    /// Hashes the pages of the snapshot again, and returns the offsets from the base of the executable
+    /// of the pages whose content changed.
+    ///
+    /// A page that became unreadable since the snapshot is reported as changed.
+    /// Code regions that don't start or end on a page boundary are hashed in partial pages,
+    /// which are reported at the offset of the whole page.
+    pub fn verify(&self) -> Result<Vec<usize>, DetectError> {
+        let mut buffer = [0; PAGE_SIZE];
+        let mut offsets: Vec<_> = self.pages.iter().filter(|&&(address, size, expected)| {
+            let read = crate::breakpoints::read_memory(address as *const u8, &mut buffer[..size]);
+            read.is_err() || hash(&buffer[..size]) != expected
+        }).map(|&(address, _, _)| (address & !(PAGE_SIZE - 1)) - self.base).collect();
+        // Two regions may share a page.
+        offsets.dedup();
+        Ok(offsets)
+    }
+
+    /// Returns the number of pages in the snapshot.
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+}
+
+/// Returns the base address of the current executable and the address and size of each region of its code.
+fn executable_code_regions() -> Result<(usize, Vec<(usize, usize)>), DetectError> {
+    #[cfg(target_os = "windows")] {
+        Ok(crate::windows::executable_code_regions())
+    }
+    #[cfg(any(target_os = "linux", target_os = "android"))] {
+        crate::linux::executable_code_regions()
+    }
+    #[cfg(target_os = "macos")] {
+        crate::macos::executable_code_regions()
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "android", target_os = "macos")))] {
+        Err(DetectError::Unsupported)
+    }
+}
+
+/// Splits the region of `size` bytes at `start` along the page boundaries,
+/// returning the address and size of the part of each page in the region.
+fn page_chunks(start: usize, size: usize) -> impl Iterator<Item = (usize, usize)> {
+    let end = start + size;
+    (start & !(PAGE_SIZE - 1)..end).step_by(PAGE_SIZE).map(move |page| {
+        let address = page.max(start);
+        (address, (page + PAGE_SIZE).min(end) - address)
+    })
+}
+
+/// Hashes `bytes` with 64-bit FNV-1a.
+fn hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xCBF2_9CE4_8422_2325, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01B3))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_hash() {
+        assert_eq!(super::hash(b""), 0xCBF2_9CE4_8422_2325);
+        assert_eq!(super::hash(b"a"), 0xAF63_DC4C_8601_EC8C);
+    }
+
+    #[test]
+    fn test_page_chunks() {
+        let chunks: Vec<_> = super::page_chunks(0x1100, 0x2000).collect();
+        assert_eq!(chunks, [(0x1100, 0xF00), (0x2000, 0x1000), (0x3000, 0x100)]);
+        let chunks: Vec<_> = super::page_chunks(0x2000, 0x1000).collect();
+        assert_eq!(chunks, [(0x2000, 0x1000)]);
+        let chunks: Vec<_> = super::page_chunks(0x2010, 0x20).collect();
+        assert_eq!(chunks, [(0x2010, 0x20)]);
+        assert_eq!(super::page_chunks(0x2000, 0).count(), 0);
+    }
+
+    #[test]
+    #[cfg(any(target_os = "windows", target_os = "linux", target_os = "android", target_os = "macos"))]
+    fn test_code_integrity() {
+        let integrity = super::CodeIntegrity::new().unwrap();
+        assert_ne!(integrity.page_count(), 0);
+        assert!(integrity.verify().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,105 @@
+//! iOS-specific detection checks.
+//!
+//! Requires the `ios` feature. `proc_pidinfo` is not available to third-party apps,
+//! so the checks only use `sysctl` and the Mach exception ports.
+
+/// `P_TRACED`: the process is traced by a debugger.
+const P_TRACED: u32 = 0x800;
+/// The size of `struct kinfo_proc` on 64-bit iOS, which is the same as on macOS.
+const KINFO_PROC_SIZE: usize = 648;
+/// The offset of `kp_proc.p_flag` in `struct kinfo_proc`.
+const P_FLAG_OFFSET: usize = 32;
+/// The offset of `kp_proc.p_comm` in `struct kinfo_proc`.
+#[cfg(feature = "std")]
+const P_COMM_OFFSET: usize = 243;
+/// The length of `kp_proc.p_comm`, i.e. `MAXCOMLEN + 1`.
+#[cfg(feature = "std")]
+const P_COMM_SIZE: usize = 17;
+
+/// `EXC_MASK_ALL`: all exceptions which can be forwarded to an exception port.
+const EXC_MASK_ALL: u32 = 0x1BFE;
+/// `EXCEPTION_DEFAULT`: send a `catch_exception_raise` message.
+const EXCEPTION_DEFAULT: i32 = 1;
+/// `THREAD_STATE_NONE`: don't send any thread state with the exception message.
+#[cfg(target_arch = "aarch64")]
+const THREAD_STATE_NONE: i32 = 5;
+#[cfg(target_arch = "x86_64")]
+const THREAD_STATE_NONE: i32 = 13;
+
+unsafe extern "C" {
+    static mach_task_self_: u32;
+    fn task_set_exception_ports(task: u32, exception_mask: u32, new_port: u32, behavior: i32, new_flavor: i32) -> i32;
+}
+
+/// Retrieves the raw `kinfo_proc` of the process `pid` with `sysctl(CTL_KERN, KERN_PROC, KERN_PROC_PID)`.
+fn kinfo_proc(pid: libc::pid_t) -> Result<[u64; KINFO_PROC_SIZE / 8], crate::DetectError> {
+    unsafe {
+        let mut mib = [libc::CTL_KERN, libc::KERN_PROC, libc::KERN_PROC_PID, pid];
+        let mut kinfo_proc = [0u64; KINFO_PROC_SIZE / 8];
+        let mut size = KINFO_PROC_SIZE;
+        let result = libc::sysctl(mib.as_mut_ptr(), mib.len() as _, kinfo_proc.as_mut_ptr() as _, &mut size, core::ptr::null_mut(), 0);
+        if result == -1 {
+            return Err(crate::error::last_os_error!(SysctlFailed));
+        }
+        if size != KINFO_PROC_SIZE {
+            // The process doesn't exist or the layout is unexpected.
+            return Err(crate::DetectError::OsError(libc::ESRCH));
+        }
+        Ok(kinfo_proc)
+    }
+}
+
+/// Checks with `sysctl(CTL_KERN, KERN_PROC, KERN_PROC_PID)`, which reports `P_TRACED`
+/// in the `p_flag` field of `kp_proc` while the current process is traced.
+pub fn check_sysctl_traced() -> Result<bool, crate::DetectError> {
+    let kinfo_proc = kinfo_proc(unsafe { libc::getpid() })?;
+    let p_flag = unsafe { kinfo_proc.as_ptr().cast::<u8>().add(P_FLAG_OFFSET).cast::<i32>().read() };
+    Ok(p_flag as u32 & P_TRACED != 0)
+}
+
+/// Returns the command name of the parent process, which is usually `launchd`.
+///
+/// Returns `Ok(None)` if the parent process has already exited.
+#[cfg(feature = "std")]
+pub fn parent_process_name() -> Result<Option<String>, crate::DetectError> {
+    let ppid = unsafe { libc::getppid() };
+    match kinfo_proc(ppid) {
+        Ok(kinfo_proc) => {
+            let bytes = unsafe { core::slice::from_raw_parts(kinfo_proc.as_ptr().cast::<u8>().add(P_COMM_OFFSET), P_COMM_SIZE) };
+            let name = bytes.split(|&byte| byte == 0).next().unwrap_or_default();
+            Ok(Some(String::from_utf8_lossy(name).into_owned()))
+        }
+        Err(crate::DetectError::OsError(libc::ESRCH)) => Ok(None),
+        Err(e) => Err(crate::DetectError::ParentProcessQueryFailed(e.into())),
+    }
+}
+
+/// Resets the exception ports of the current task with `task_set_exception_ports`,
+/// so a debugger registered on them no longer receives the exceptions of the process.
+///
+/// This also detaches crash reporters which registered on the task exception ports,
+/// so call it before installing them.
+pub fn reset_exception_ports() -> Result<(), crate::DetectError> {
+    const MACH_PORT_NULL: u32 = 0;
+    const KERN_SUCCESS: i32 = 0;
+
+    let result = unsafe { task_set_exception_ports(mach_task_self_, EXC_MASK_ALL, MACH_PORT_NULL, EXCEPTION_DEFAULT, THREAD_STATE_NONE) };
+    if result != KERN_SUCCESS {
+        return Err(crate::DetectError::ExceptionPortsFailed(result));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_check_sysctl_traced() {
+        assert!(!super::check_sysctl_traced().unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_parent_process_name() {
+        assert!(super::parent_process_name().is_ok());
+    }
+}
@@ -1,5 +1,126 @@
 #![doc = include_str!("../README.md")]
 #![warn(missing_docs)]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
+
+extern crate alloc;
+
+/// Logs a warning through the `log` feature, or discards it without.
+#[allow(unused_macros)]
+macro_rules! log_warn {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "log")]
+        log::warn!($($arg)*);
+        #[cfg(not(feature = "log"))]
+        let _ = format_args!($($arg)*);
+    }};
+}
+#[allow(unused_imports)]
+pub(crate) use log_warn;
+
+#[cfg(feature = "artifact-scan")]
+mod artifacts;
+#[cfg(feature = "memory-integrity")]
+mod breakpoints;
+mod cache;
+mod config;
+mod detector;
+mod disarm;
+#[cfg(feature = "anti-dump")]
+mod dump;
+mod error;
+#[cfg(feature = "hooks")]
+mod hooks;
+#[cfg(feature = "std")]
+mod info;
+#[cfg(feature = "std")]
+mod injection;
+#[cfg(feature = "memory-integrity")]
+mod integrity;
+mod method;
+#[cfg(feature = "tokio")]
+mod nonblocking;
+mod obfuscate;
+#[cfg(feature = "std")]
+mod panic;
+#[cfg(feature = "std")]
+mod parent;
+mod reason;
+#[cfg(feature = "std")]
+mod status;
+#[cfg(all(any(target_os = "linux", target_os = "android"), feature = "raw-syscall"))]
+mod syscall;
+#[cfg(feature = "std")]
+mod tamper;
+#[cfg(feature = "std")]
+mod timing;
+#[cfg(feature = "vm-detect")]
+mod vm;
+#[cfg(feature = "std")]
+mod watch;
+pub mod prelude;
+#[cfg(target_os = "windows")]
+pub mod windows;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub mod linux;
+#[cfg(target_os = "macos")]
+pub mod macos;
+#[cfg(all(target_os = "ios", feature = "ios"))]
+pub mod ios;
+#[cfg(target_os = "freebsd")]
+pub mod freebsd;
+#[cfg(target_os = "netbsd")]
+pub mod netbsd;
+#[cfg(target_os = "openbsd")]
+pub mod openbsd;
+
+#[cfg(feature = "artifact-scan")]
+pub use artifacts::{DebuggerArtifacts, debugger_artifacts_present};
+#[cfg(feature = "memory-integrity")]
+pub use breakpoints::{DEFAULT_BREAKPOINT_SCAN_LENGTH, code_checksum, code_integrity_ok, scan_for_breakpoints};
+pub use cache::{invalidate_detection_cache, is_debugger_present_cached};
+pub use config::DetectionConfig;
+pub use detector::{Detector, DetectorBuilder};
+#[cfg(feature = "anti-dump")]
+pub use dump::{mark_region_executable, mark_region_no_access, protect_function};
+#[cfg(feature = "dev-override")]
+pub use disarm::{InitConfig, OVERRIDE_ENV_VAR, init};
+#[cfg(feature = "std")]
+pub use detector::DebouncedDetector;
+pub use error::{AntiDebugError, DetectError};
+#[cfg(feature = "hooks")]
+pub use hooks::{HookId, register_detection_hook, unregister_detection_hook};
+#[cfg(feature = "std")]
+pub use info::{DebuggerInfo, debugger_info};
+#[cfg(feature = "std")]
+pub use injection::{InjectedLibrary, InjectionInfo, InjectionSource, preload_injection_present};
+#[cfg(feature = "memory-integrity")]
+pub use integrity::CodeIntegrity;
+pub use method::{DetectionMethod, DetectionProbe, DetectionReport, ErrorPolicy, ProbeResult, builtin_probes, detect_with, run_all_checks, run_all_checks_with, supported_methods};
+#[cfg(feature = "tokio")]
+pub use nonblocking::{async_deny_attach, async_is_debugger_present, async_monitor};
+#[cfg(feature = "std")]
+pub use panic::{guard_panics, unguard_panics};
+#[cfg(feature = "std")]
+pub use parent::{KNOWN_DEBUGGERS, add_known_debugger, debugger_parent_process, launched_by_debugger};
+pub use reason::DetectionReason;
+#[cfg(feature = "std")]
+pub use status::{DebuggerStatus, full_report};
+#[cfg(feature = "std")]
+pub use tamper::{TamperingReport, environment_tampering};
+#[cfg(feature = "std")]
+pub use timing::timing_anomaly_detected;
+#[cfg(feature = "timing")]
+pub use timing::{timed, timing_check};
+#[cfg(all(feature = "timing", target_os = "windows"))]
+pub use timing::{DEFAULT_OUTPUT_DEBUG_THRESHOLD, is_output_debug_string_slow};
+#[cfg(all(feature = "timing", any(target_arch = "x86", target_arch = "x86_64")))]
+pub use timing::{DEFAULT_TIMING_THRESHOLD, is_execution_suspiciously_slow};
+#[cfg(feature = "vm-detect")]
+pub use vm::{VmVendor, detect_vm, running_in_vm};
+#[cfg(all(feature = "vm-detect", any(target_arch = "x86", target_arch = "x86_64")))]
+pub use vm::is_hypervisor_present;
+#[cfg(feature = "std")]
+pub use watch::{DetectionEvent, Monitor, MonitorConfig, MonitorHandle, Transition, WatchHandle, WatchPolicy, Watchdog, spawn_watchdog, watch_debugger, watch_debugger_with};
 
 /// Checks if a debugger is currently attached to the process.
 ///
@@ -10,15 +131,33 @@
 ///
 /// - **Windows**: Uses `IsDebuggerPresent`.
 ///   When the `deep-detect` feature is enabled, additionally checks
-///   `CheckRemoteDebuggerPresent` and `NtQueryInformationProcess`.
+///   `CheckRemoteDebuggerPresent`, `NtQueryInformationProcess` (debug port, debug object handle and debug flags),
+///   the `NtGlobalFlag` field of the PEB, the flags of the process heap
+///   and the debug registers of the current thread.
+///   When the `kernel-detect` feature is enabled, additionally checks for a kernel debugger
+///   with [`kernel_debugger_present`].
 /// - **Linux/Android**: Checks the `TracerPid` field in `/proc/self/status`.
-/// - **macOS**: Uses `proc_pidinfo` to retrieve `proc_bsdinfo` and checks the `pbi_flags` field.
+///   When the `deep-detect` feature is enabled, additionally checks the `HWBreakpoint` field
+///   where the kernel exposes it, and if the tracer slot is taken with `ptrace` (requires the `std` feature).
+///   Under the restricted Yama `ptrace_scope` (`1`), the latter resets the ptracer exception
+///   set with `prctl(PR_SET_PTRACER)` of the current process.
+///   On Android, additionally checks if the OS is a debuggable build with the `ro.debuggable` system property.
+/// - **macOS**: Uses `proc_pidinfo` to retrieve `proc_bsdinfo` and checks the `pbi_flags` field,
+///   and uses `sysctl` to retrieve `kinfo_proc` and checks the `p_flag` field.
+///   If `proc_pidinfo` fails, such as when denied by the sandbox, the answer of `sysctl` is used instead.
+///   When the `deep-detect` feature is enabled, additionally checks if the tracer slot is taken
+///   with `ptrace(PT_ATTACHEXC)` (requires the `std` feature).
+/// - **iOS**: Uses `sysctl` to retrieve `kinfo_proc` and checks the `p_flag` field. Requires the `ios` feature.
+/// - **FreeBSD**: Uses `sysctl` to retrieve `kinfo_proc` and checks the `ki_flag` field.
+/// - **NetBSD**: Uses `sysctl` to retrieve `kinfo_proc2` and checks the `p_flag` field.
+/// - **OpenBSD**: Uses `sysctl` to retrieve `kinfo_proc` and checks the `p_psflags` field.
 /// - **Other platforms**: Compilation error.
 ///
 /// # Return Value
 ///
 /// Returns `Ok(true)` if a debugger is detected, `Ok(false)` if no debugger is present,
-/// or `Err(std::io::Error)` if the check could not be performed due to a system error.
+/// or `Err(AntiDebugError)` if a check could not be performed due to a system error.
+/// Use [`detect_debugger`] to get the [`DetectError`] naming the failed step instead.
 ///
 /// # Examples
 ///
@@ -38,90 +177,527 @@
 /// - Some debuggers may not be detected depending on their attachment method
 /// - The check is performed at the moment the function is called and may not reflect
 ///   subsequent attachment/detachment of debuggers
-pub fn is_debugger_present() -> Result<bool, std::io::Error> {
-    #[cfg(target_os = "windows")] {
-        // Check with `IsDebuggerPresent`.
-        unsafe {
-            let result = windows_sys::Win32::System::Diagnostics::Debug::IsDebuggerPresent();
-            if result != windows_sys::Win32::Foundation::FALSE {
-                return Ok(true);
+/// - With the `disarm` feature, or the development override of `init` (`dev-override` feature),
+///   this always returns `Ok(false)`
+pub fn is_debugger_present() -> Result<bool, AntiDebugError> {
+    if disarm::disarmed() {
+        return Ok(false);
+    }
+    Ok(detect_debugger()?.is_some())
+}
+
+/// Checks if a debugger is attached like [`is_debugger_present`], counting a failed check by `policy`.
+///
+/// # Examples
+///
+/// ```rust
+/// # fn main() {
+/// // Assume hostility if the detection can't be performed.
+/// if anti_debug::is_debugger_present_with_policy(anti_debug::ErrorPolicy::FailClosed) {
+///     println!("Debugger detected or unverifiable!");
+/// }
+/// # }
+/// ```
+pub fn is_debugger_present_with_policy(policy: ErrorPolicy) -> bool {
+    policy.apply(is_debugger_present())
+}
+
+/// Checks if a debugger is currently attached to the process with the checks enabled in `config`.
+///
+/// See [`detect_debugger_with`] and [`DetectionConfig`].
+///
+/// # Examples
+///
+/// ```rust
+/// # fn main() {
+/// let mut config = anti_debug::DetectionConfig::all();
+/// config.check_hardware_breakpoints = false;
+/// match anti_debug::is_debugger_present_with(&config) {
+///     Ok(true) => println!("Debugger detected!"),
+///     Ok(false) => println!("No debugger present"),
+///     Err(e) => println!("Error checking for debugger: {}", e),
+/// }
+/// # }
+/// ```
+pub fn is_debugger_present_with(config: &DetectionConfig) -> Result<bool, AntiDebugError> {
+    Ok(detect_debugger_with(config)?.is_some())
+}
+
+/// Checks if a debugger is attached by forking a helper process which tries to `ptrace` the current process.
+///
+/// A process can be traced by only one tracer at a time, so the attach of the helper fails while
+/// a debugger holds the slot. This can't be fooled by faking `/proc/self/status` or the flags
+/// reported by the kernel, which makes it the most reliable check, but also the most expensive one:
+/// every call forks the process and waits for the helper.
+///
+/// It also runs in [`is_debugger_present`] if [`DetectionConfig::check_ptrace_traceme`] is enabled.
+///
+/// # Platform-specific Behavior
+///
+/// - **Linux/Android**: Uses `ptrace(PTRACE_SEIZE)`, see `linux::self_ptrace_detected`.
+///   Under the restricted Yama `ptrace_scope` (`1`), the ptracer exception set with `prctl(PR_SET_PTRACER)`
+///   of the current process is reset.
+/// - **macOS**: Uses `ptrace(PT_ATTACHEXC)`, see `macos::self_ptrace_detected`.
+///
+/// # Fork Safety
+///
+/// The helper only issues async-signal-safe system calls before exiting, so it doesn't deadlock
+/// on locks held by other threads at the time of the fork. Still, the process is assumed to
+/// not reap children it doesn't own: a `SIGCHLD` handler or a `waitpid(-1)` in another thread
+/// may steal the exit status of the helper, which makes this return an error.
+///
+/// # Return Value
+///
+/// Returns `Ok(true)` if a debugger is detected, `Ok(false)` if no debugger is present
+/// or `ptrace` is forbidden altogether, or `Err(AntiDebugError)` if the helper could not be run.
+/// With the `disarm` feature, or the development override of `init` (`dev-override` feature),
+/// no helper is forked and this always returns `Ok(false)`.
+#[cfg(all(any(target_os = "linux", target_os = "android", target_os = "macos"), feature = "deep-detect", feature = "std"))]
+pub fn is_debugger_present_fork() -> Result<bool, AntiDebugError> {
+    if disarm::disarmed() {
+        return Ok(false);
+    }
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    return Ok(linux::self_ptrace_detected()?);
+    #[cfg(target_os = "macos")]
+    return Ok(macos::self_ptrace_detected()?);
+}
+
+/// Exits the process with `code` if a debugger is detected by [`is_debugger_present`].
+///
+/// The exit runs no destructors of the stack, but can't be caught by `catch_unwind`.
+/// See [`abort_on_detect`] to terminate without running `atexit` handlers as well.
+///
+/// # Return Value
+///
+/// Returns `Ok(())` if no debugger is present, or `Err(AntiDebugError)` if the check could not be performed.
+/// Never returns if a debugger is detected.
+///
+/// # Examples
+///
+/// ```rust
+/// # fn main() {
+/// anti_debug::exit_on_detect(1).expect("failed to check for debugger");
+/// # }
+/// ```
+#[cfg(feature = "std")]
+pub fn exit_on_detect(code: i32) -> Result<(), AntiDebugError> {
+    if is_debugger_present()? {
+        std::process::exit(code);
+    }
+    Ok(())
+}
+
+/// Aborts the process if a debugger is detected by [`is_debugger_present`].
+///
+/// Unlike a panic, the abort can't be caught by `catch_unwind`, and unlike [`exit_on_detect`],
+/// it runs no `atexit` handlers.
+///
+/// # Return Value
+///
+/// Returns `Ok(())` if no debugger is present, or `Err(AntiDebugError)` if the check could not be performed.
+/// Never returns if a debugger is detected.
+#[cfg(feature = "std")]
+pub fn abort_on_detect() -> Result<(), AntiDebugError> {
+    if is_debugger_present()? {
+        std::process::abort();
+    }
+    Ok(())
+}
+
+/// Checks if a debugger is currently attached to the process, and reports why.
+///
+/// This function performs the same checks as [`is_debugger_present`],
+/// but returns the [`DetectionReason`] of the first check that found a debugger.
+///
+/// # Return Value
+///
+/// Returns `Ok(Some(reason))` if a debugger is detected, `Ok(None)` if no debugger is present,
+/// or `Err(DetectError)` naming the step that could not be performed due to a system error.
+///
+/// # Examples
+///
+/// ```rust
+/// # fn main() {
+/// match anti_debug::detect_debugger() {
+///     Ok(Some(reason)) => println!("Debugger detected: {}", reason),
+///     Ok(None) => println!("No debugger present"),
+///     Err(e) => println!("Error checking for debugger: {}", e),
+/// }
+/// # }
+/// ```
+pub fn detect_debugger() -> Result<Option<DetectionReason>, DetectError> {
+    detect_debugger_with(&DetectionConfig::default())
+}
+
+/// Checks if a debugger is currently attached to the process like [`detect_debugger`],
+/// but without failing when `/proc/self/status` is unavailable.
+///
+/// Locked-down environments, such as sandboxes and some CI containers, may hide `/proc`
+/// or report a `/proc/self/status` without the `TracerPid` field. [`detect_debugger`] returns
+/// an error there, which callers may mistake for a detected debugger. This skips the checks
+/// on `/proc/self/status` instead, logs a warning, and runs the remaining checks.
+/// Use [`detect_debugger`] to learn about the failure.
+///
+/// A malformed `TracerPid` field, and failures of the other checks, are still returned as errors.
+///
+/// # Platform-specific Behavior
+///
+/// - **Linux/Android**: Skips the `TracerPid` and `HWBreakpoint` checks if the file can't be read
+///   or the `TracerPid` field is missing.
+/// - **Other platforms**: Same as [`detect_debugger`].
+///
+/// # Return Value
+///
+/// Returns `Ok(Some(reason))` if a debugger is detected, `Ok(None)` if no debugger is present
+/// or the status is unavailable, or `Err(DetectError)` naming the step that could not be performed.
+/// With the `disarm` feature, or the development override of `init` (`dev-override` feature),
+/// this always returns `Ok(None)`.
+pub fn detect_debugger_lenient() -> Result<Option<DetectionReason>, DetectError> {
+    if disarm::disarmed() {
+        return Ok(None);
+    }
+    #[cfg(any(target_os = "linux", target_os = "android"))] {
+        let mut config = DetectionConfig::default();
+        match linux::read_status().and_then(|status| detect_in_status(&config, &status)) {
+            Ok(Some(reason)) => return Ok(Some(reason)),
+            Ok(None) => {}
+            Err(error) if status_unavailable(&error) => {
+                crate::log_warn!("{} is unavailable, skipped its checks: {error}", obfuscate::obfuscated_str!("/proc/self/status"));
             }
+            Err(error) => return Err(error),
         }
-        // Check with `CheckRemoteDebuggerPresent`.
-        #[cfg(feature = "deep-detect")]
-        unsafe {
-            let mut p_debugger_present = windows_sys::Win32::Foundation::FALSE;
-            let result = windows_sys::Win32::System::Diagnostics::Debug::CheckRemoteDebuggerPresent(
-                windows_sys::Win32::System::Threading::GetCurrentProcess(),
-                &mut p_debugger_present,
-            );
-            if result == windows_sys::Win32::Foundation::FALSE {
-                return Err(std::io::Error::last_os_error());
+        config.check_tracerpid = false;
+        config.check_hardware_breakpoints = false;
+        detect_debugger_with(&config)
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    detect_debugger()
+}
+
+/// Checks if a debugger is currently attached to the process like [`detect_debugger`],
+/// and emits a `tracing` event with the result.
+///
+/// Requires the `tracing` feature. A detected debugger emits a `WARN` event with the
+/// `reason`, `tracer_pid` and `platform` fields, no debugger emits a `DEBUG` event,
+/// and an error emits an `ERROR` event with the `error` field.
+///
+/// # Examples
+///
+/// ```rust
+/// # fn main() {
+/// if let Ok(Some(reason)) = anti_debug::detect_debugger_traced() {
+///     println!("Debugger detected: {}", reason);
+/// }
+/// # }
+/// ```
+#[cfg(feature = "tracing")]
+pub fn detect_debugger_traced() -> Result<Option<DetectionReason>, DetectError> {
+    let platform = std::env::consts::OS;
+    let result = detect_debugger();
+    match &result {
+        Ok(Some(reason)) => {
+            let tracer_pid = match reason {
+                DetectionReason::TracerPid(pid) => Some(*pid),
+                _ => None,
+            };
+            tracing::warn!(reason = %reason, tracer_pid, platform, "debugger detected");
+        }
+        Ok(None) => tracing::debug!(platform, "no debugger detected"),
+        Err(error) => tracing::error!(error = %error, platform, "failed to detect debugger"),
+    }
+    result
+}
+
+/// Checks if a debugger is currently attached to the process with the checks enabled in `config`,
+/// and reports why.
+///
+/// Checks that are not compiled in, such as the `deep-detect` ones without the feature,
+/// are skipped even if enabled in `config`. See [`DetectionConfig`] for the checks.
+///
+/// # Return Value
+///
+/// Returns `Ok(Some(reason))` if a debugger is detected, `Ok(None)` if no debugger is present,
+/// or `Err(DetectError)` naming the step that could not be performed due to a system error.
+/// With the `disarm` feature, or the development override of `init` (`dev-override` feature),
+/// this always returns `Ok(None)`.
+pub fn detect_debugger_with(config: &DetectionConfig) -> Result<Option<DetectionReason>, DetectError> {
+    if disarm::disarmed() {
+        return Ok(None);
+    }
+    #[cfg(target_os = "windows")] {
+        // Errors don't abort the remaining checks, so a check rejected by a sandbox doesn't hide the others.
+        run_checks(&[
+            // Check with `IsDebuggerPresent`.
+            (config.check_is_debugger_present, DetectionReason::IsDebuggerPresent, || Ok(windows::check_is_debugger_present())),
+            // Check with `CheckRemoteDebuggerPresent`.
+            (config.check_remote_debugger, DetectionReason::RemoteDebugger, windows::check_remote_debugger_present),
+            // Check with `NtQueryInformationProcess(ProcessDebugPort)`.
+            (config.check_nt_query, DetectionReason::DebugPort, windows::check_debug_port),
+            // Check with `NtQueryInformationProcess(ProcessDebugObjectHandle)`.
+            (config.check_nt_query, DetectionReason::DebugObjectHandle, windows::check_debug_object_handle),
+            // Check with `NtQueryInformationProcess(ProcessDebugFlags)`.
+            (config.check_nt_query, DetectionReason::DebugFlags, windows::check_debug_flags),
+            // Check with `NtGlobalFlag` in the PEB.
+            #[cfg(feature = "deep-detect")]
+            (config.check_nt_global_flag, DetectionReason::NtGlobalFlag, windows::nt_global_flag_debugged),
+            // Check with the flags of the process heap.
+            #[cfg(feature = "deep-detect")]
+            (config.check_heap_flags, DetectionReason::HeapFlags, windows::heap_flags_debugged),
+            // Check with the debug registers of the current thread.
+            #[cfg(feature = "deep-detect")]
+            (config.check_hardware_breakpoints, DetectionReason::HardwareBreakpoints, windows::has_hardware_breakpoints),
+            // Check with `NtQuerySystemInformation(SystemKernelDebuggerInformation)` and `KUSER_SHARED_DATA`.
+            (config.check_kernel_debugger, DetectionReason::KernelDebugger, kernel_debugger_present),
+        ])
+    }
+    #[cfg(any(target_os = "linux", target_os = "android"))] {
+        if config.check_tracerpid || config.check_hardware_breakpoints {
+            let status = linux::read_status()?;
+            if let Some(reason) = detect_in_status(config, &status)? {
+                return Ok(Some(reason));
             }
-            if p_debugger_present != windows_sys::Win32::Foundation::FALSE {
-                return Ok(true);
+        }
+        // Check with `ptrace`.
+        #[cfg(all(feature = "deep-detect", feature = "std"))]
+        if config.check_ptrace_traceme && linux::self_ptrace_detected()? {
+            return Ok(Some(DetectionReason::PtraceSlot));
+        }
+        // Check with the `wchan` of the threads.
+        #[cfg(all(feature = "deep-detect", feature = "std"))]
+        if config.check_wchan && linux::is_wchan_ptrace_related()? {
+            return Ok(Some(DetectionReason::PtraceStop));
+        }
+        // Check with the `ro.debuggable` system property.
+        #[cfg(all(target_os = "android", feature = "deep-detect"))]
+        if config.check_debuggable_build && linux::is_android_debug_build() {
+            return Ok(Some(DetectionReason::DebuggableBuild));
+        }
+        Ok(None)
+    }
+    #[cfg(target_os = "macos")] {
+        // Check with `proc_pidinfo`.
+        // If it fails, such as when denied by the sandbox, fall back to `sysctl` instead of giving up.
+        let mut error = None;
+        if config.check_proc_bsdinfo {
+            match macos::check_proc_pidinfo_traced() {
+                Ok(true) => return Ok(Some(DetectionReason::ProcTraced)),
+                Ok(false) => {}
+                Err(e) => { error = Some(e); }
             }
         }
-        // Check with `NtQueryInformationProcess`.
-        #[cfg(feature = "deep-detect")]
-        unsafe {
-            let mut p_debug_port = 0i32;
-            let result = windows_sys::Wdk::System::Threading::NtQueryInformationProcess(
-                windows_sys::Win32::System::Threading::GetCurrentProcess(),
-                windows_sys::Wdk::System::Threading::ProcessDebugPort,
-                &mut p_debug_port as *mut _ as _,
-                size_of::<i32>() as _,
-                &mut 0,
-            );
-            let result = windows_sys::Win32::Foundation::RtlNtStatusToDosError(result);
-            if result != 0 {
-                return Err(std::io::Error::from_raw_os_error(result as _));
+        // Check with `sysctl`.
+        if config.check_sysctl_traced || error.is_some() {
+            if macos::check_sysctl_traced()? {
+                return Ok(Some(DetectionReason::SysctlTraced));
             }
-            if p_debug_port != 0 {
-                return Ok(true);
+            if let Some(error) = error {
+                crate::log_warn!("proc_pidinfo failed, answered by sysctl instead: {error}");
             }
         }
-        Ok(false)
+        // Check with `ptrace`.
+        #[cfg(all(feature = "deep-detect", feature = "std"))]
+        if config.check_ptrace_traceme && macos::self_ptrace_detected()? {
+            return Ok(Some(DetectionReason::PtraceSlot));
+        }
+        Ok(None)
     }
-    #[cfg(any(target_os = "linux", target_os = "android"))] {
-        // Check with `/proc/self/status`.
-        {
-            let proc = std::fs::read_to_string("/proc/self/status")?;
-            let pid = proc
-                .lines()
-                .filter_map(|line| line.strip_prefix("TracerPid:"))
-                .filter_map(|pid| pid.trim().parse::<i32>().ok())
-                .next()
-                .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid pid format"))?;
-            if pid != 0 {
-                return Ok(true);
-            }
+    #[cfg(all(target_os = "ios", feature = "ios"))] {
+        // Check with `sysctl`.
+        if config.check_sysctl_traced && ios::check_sysctl_traced()? {
+            return Ok(Some(DetectionReason::SysctlTraced));
         }
-        Ok(false)
+        Ok(None)
     }
-    #[cfg(target_os = "macos")] {
-        // Check with `proc_pidinfo`.
-        {
-            let pid = std::process::id() as i32;
-            let result = libproc::proc_pid::pidinfo::<libproc::bsd_info::BSDInfo>(pid, 0);
-            let proc_bsdinfo = match result {
-                Ok(proc_bsdinfo) => proc_bsdinfo,
-                Err(_message) => return Err(std::io::Error::last_os_error()),
-            };
-            const PROC_FLAG_TRACED: u32 = 2; // use libproc::osx_libproc_bindings::PROC_FLAG_TRACED;
-            if proc_bsdinfo.pbi_flags & PROC_FLAG_TRACED != 0 { return Ok(true); }
+    #[cfg(target_os = "freebsd")] {
+        // Check with `sysctl`.
+        if config.check_sysctl_traced && freebsd::check_sysctl_traced()? {
+            return Ok(Some(DetectionReason::SysctlTraced));
         }
-        Ok(false)
+        Ok(None)
+    }
+    #[cfg(target_os = "netbsd")] {
+        // Check with `sysctl`.
+        if config.check_sysctl_traced && netbsd::check_sysctl_traced()? {
+            return Ok(Some(DetectionReason::SysctlTraced));
+        }
+        Ok(None)
+    }
+    #[cfg(target_os = "openbsd")] {
+        // Check with `sysctl`.
+        if config.check_sysctl_traced && openbsd::check_sysctl_traced()? {
+            return Ok(Some(DetectionReason::SysctlTraced));
+        }
+        Ok(None)
     }
     #[cfg(not(any(
         target_os = "windows",
         target_os = "linux",
         target_os = "android",
         target_os = "macos",
+        all(target_os = "ios", feature = "ios"),
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
     )))]
     compile_error!("Anti-Debug doesn't support current platform.")
 }
 
+/// A check of [`run_checks`]: whether it is enabled, the reason it reports, and the check itself.
+type Check = (bool, DetectionReason, fn() -> Result<bool, DetectError>);
+
+/// Runs the enabled `checks` in order until one detects a debugger.
+///
+/// Errors don't abort the remaining checks. The first error is returned
+/// only if no check detects a debugger.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn run_checks(checks: &[Check]) -> Result<Option<DetectionReason>, DetectError> {
+    let mut error = None;
+    for &(enabled, reason, check) in checks {
+        if !enabled {
+            continue;
+        }
+        match check() {
+            Ok(true) => return Ok(Some(reason)),
+            Ok(false) => {}
+            Err(e) => { error.get_or_insert(e); }
+        }
+    }
+    match error {
+        Some(error) => Err(error),
+        None => Ok(None),
+    }
+}
+
+/// Checks if `error` means that `/proc/self/status` couldn't be read or has no `TracerPid` field.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn status_unavailable(error: &DetectError) -> bool {
+    match error {
+        #[cfg(feature = "std")]
+        DetectError::ProcStatusUnreadable(_) => true,
+        #[cfg(not(feature = "std"))]
+        DetectError::OsError(_) => true,
+        DetectError::InvalidTracerPid(raw) => raw.is_empty(),
+        _ => false,
+    }
+}
+
+/// Runs the checks enabled in `config` on the content of `/proc/self/status`.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn detect_in_status(config: &DetectionConfig, status: &str) -> Result<Option<DetectionReason>, DetectError> {
+    // Check with the `TracerPid` field in `/proc/self/status`.
+    if config.check_tracerpid {
+        let pid = linux::parse_tracer_pid(status)?;
+        if pid != 0 {
+            return Ok(Some(DetectionReason::TracerPid(pid)));
+        }
+    }
+    // Check with the `HWBreakpoint` field in `/proc/self/status`.
+    #[cfg(feature = "deep-detect")]
+    if config.check_hardware_breakpoints && linux::parse_hardware_breakpoints(status) {
+        return Ok(Some(DetectionReason::HardwareBreakpoints));
+    }
+    Ok(None)
+}
+
+/// Checks if hardware breakpoints are set on the current thread.
+///
+/// # Platform-specific Behavior
+///
+/// - **Windows**: Uses `windows::has_hardware_breakpoints`, checking the debug registers of the current thread.
+/// - **Linux/Android**: Uses `linux::has_hardware_breakpoints`, checking the `HWBreakpoint` field
+///   in `/proc/self/status`. Returns `Err(DetectError::Unsupported)` if the kernel doesn't expose it.
+/// - **Other platforms**: Returns `Err(DetectError::Unsupported)`.
+///
+/// # Return Value
+///
+/// Returns `Ok(true)` if hardware breakpoints are set, `Ok(false)` if none is set,
+/// or `Err(DetectError)` if the check is unsupported or could not be performed.
+#[cfg(feature = "deep-detect")]
+pub fn hardware_breakpoints_present() -> Result<bool, DetectError> {
+    #[cfg(target_os = "windows")] {
+        windows::has_hardware_breakpoints()
+    }
+    #[cfg(any(target_os = "linux", target_os = "android"))] {
+        let status = linux::read_status()?;
+        let field = obfuscate::obfuscated!("HWBreakpoint:");
+        if !status.lines().any(|line| line.starts_with(obfuscate::as_str(&field))) {
+            return Err(DetectError::Unsupported);
+        }
+        Ok(linux::parse_hardware_breakpoints(&status))
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "android")))] {
+        Err(DetectError::Unsupported)
+    }
+}
+
+/// Checks if a kernel debugger (such as WinDbg over KD) is enabled and connected.
+///
+/// Unlike [`is_debugger_present`], this detects debuggers of the whole system rather than
+/// of the current process. With the `kernel-detect` feature, it is also part of
+/// [`is_debugger_present`] and [`detect_debugger`].
+///
+/// # Platform-specific Behavior
+///
+/// - **Windows**: Uses `windows::check_kernel_debugger` and `windows::check_kuser_shared_data_kernel_debugger`.
+/// - **Other platforms**: Returns `Err(DetectError::Unsupported)`.
+///
+/// # Return Value
+///
+/// Returns `Ok(true)` if a kernel debugger is detected, `Ok(false)` if not detected,
+/// or `Err(DetectError)` if the check is unsupported or could not be performed.
+pub fn kernel_debugger_present() -> Result<bool, DetectError> {
+    #[cfg(target_os = "windows")] {
+        Ok(windows::check_kernel_debugger()? || windows::check_kuser_shared_data_kernel_debugger())
+    }
+    #[cfg(not(target_os = "windows"))] {
+        Err(DetectError::Unsupported)
+    }
+}
+
+/// Checks if software breakpoints (`INT3`, `0xCC`) were written into the code of the current executable.
+///
+/// Requires the `memory-integrity` feature. The check reads every executable region of the
+/// executable, so it is slow and should not be called in a hot loop. Unreadable regions are
+/// skipped with a warning through the `log` feature.
+///
+/// # Platform-specific Behavior
+///
+/// - **Windows**: Enumerates the executable sections of the main module, and queries their pages with `VirtualQuery`.
+/// - **Linux/Android**: Enumerates the executable mappings of `/proc/self/exe` in `/proc/self/maps`.
+/// - **Other platforms and architectures**: Returns `Err(DetectError::Unsupported)`.
+///   Only x86 and x86-64 are supported, since other architectures don't use `0xCC` as breakpoint.
+///
+/// The code in memory is compared with the executable on disk, and only bytes that are
+/// `0xCC` in memory but not on disk are reported. Legitimate `0xCC` bytes, such as padding
+/// between functions, immediates or data embedded in code, are therefore never flagged.
+///
+/// # Not Covered
+///
+/// - Shared libraries and dynamically generated code are not scanned, only the executable itself.
+/// - Breakpoints set before the executable on disk was read, i.e. a patched file, are not detected.
+/// - Executables modified by the loader beyond base relocations (such as text relocations on Linux)
+///   may be reported falsely.
+/// - Debuggers that temporarily remove their breakpoints while the process runs, or use
+///   other instructions (`INT 3` as `CD 03`, `UD2`) are not detected.
+///
+/// # Return Value
+///
+/// Returns `Ok(true)` if software breakpoints are found, `Ok(false)` if none is found,
+/// or `Err(DetectError)` if the check is unsupported or could not be performed.
+#[cfg(feature = "memory-integrity")]
+pub fn scan_for_software_breakpoints() -> Result<bool, DetectError> {
+    #[cfg(all(target_os = "windows", any(target_arch = "x86", target_arch = "x86_64")))] {
+        windows::image_has_int3_patches()
+    }
+    #[cfg(all(any(target_os = "linux", target_os = "android"), any(target_arch = "x86", target_arch = "x86_64")))] {
+        linux::executable_has_int3_patches()
+    }
+    #[cfg(not(all(any(target_os = "windows", target_os = "linux", target_os = "android"), any(target_arch = "x86", target_arch = "x86_64"))))] {
+        Err(DetectError::Unsupported)
+    }
+}
+
 /// Attempts to prevent debuggers from attaching to the current process.
 ///
 /// This function performs platform-specific operations to prevent debuggers
@@ -133,6 +709,12 @@ pub fn is_debugger_present() -> Result<bool, std::io::Error> {
 ///   Checks if a debugger is currently attached using [`is_debugger_present`].
 ///   If a debugger is detected, returns an error.
 /// - **macOS**: Uses `ptrace` with the `PT_DENY_ATTACH` flag.
+/// - **iOS**: Uses `ios::reset_exception_ports`, so an attached debugger no longer receives
+///   the exceptions of the process. A debugger attaching later registers its exception ports again.
+///   Requires the `ios` feature.
+/// - **FreeBSD**: Uses `procctl` with `PROC_TRACE_CTL_DISABLE`.
+/// - **NetBSD/OpenBSD**: Returns `Err(AntiDebugError::PlatformNotSupported)`,
+///   as there is no primitive to deny attaching.
 /// - **Other platforms**: Compilation error.
 ///
 /// # Return Value
@@ -140,9 +722,13 @@ pub fn is_debugger_present() -> Result<bool, std::io::Error> {
 /// - Returns `Ok(())` if:
 ///   - On Windows/Linux/Android: No debugger is currently attached.
 ///   - On macOS: The `ptrace(PT_DENY_ATTACH)` call succeeded.
-/// - Returns `Err(std::io::Error)` if:
+///   - On iOS: The `task_set_exception_ports` call succeeded.
+///   - On FreeBSD: The `procctl(PROC_TRACE_CTL)` call succeeded.
+/// - Returns `Err(AntiDebugError)` if:
 ///   - On Windows/Linux/Android: A debugger is currently attached.
 ///   - On macOS: The `ptrace` system call failed.
+///   - On iOS: The `task_set_exception_ports` call failed.
+///   - On FreeBSD: The `procctl` system call failed.
 ///   - Any platform-specific system call fails.
 ///
 /// # Examples
@@ -159,31 +745,123 @@ pub fn is_debugger_present() -> Result<bool, std::io::Error> {
 ///
 /// - This detection can be bypassed by skilled attackers using advanced anti-anti-debugging techniques
 /// - Some debuggers may not be detected depending on their attachment method
-/// - On Windows/Linux/Android, this is a detection-based approach. i.e. passive detection
-pub fn deny_attach() -> Result<(), std::io::Error> {
+/// - On Windows/Linux/Android, this is a detection-based approach. i.e. passive detection.
+///   On Linux/Android, `deny_attach_strict` also blocks new attaches, at the cost of making the process non-dumpable
+/// - With the `disarm` feature, or the development override of `init` (`dev-override` feature),
+///   this does nothing and returns `Ok(())`
+pub fn deny_attach() -> Result<(), AntiDebugError> {
+    if disarm::disarmed() {
+        return Ok(());
+    }
     #[cfg(any(target_os = "windows", target_os = "linux", target_os = "android"))] {
         if is_debugger_present()? {
-            return Err(std::io::Error::new(std::io::ErrorKind::AlreadyExists, "Debugger present."));
+            return Err(AntiDebugError::DebuggerPresent);
         }
         Ok(())
     }
     #[cfg(target_os = "macos")] {
         // Deny with `ptrace`.
         unsafe {
-            let result = libc::ptrace(libc::PT_DENY_ATTACH, 0, std::ptr::null_mut(), 0);
-            if result == -1 { return Err(std::io::Error::last_os_error()); }
+            let result = libc::ptrace(libc::PT_DENY_ATTACH, 0, core::ptr::null_mut(), 0);
+            if result == -1 { return Err(crate::error::last_os_error!(DenyAttachFailed).into()); }
         }
         Ok(())
     }
+    #[cfg(all(target_os = "ios", feature = "ios"))] {
+        // `ptrace` is not part of the iOS SDK, so take the exception ports away from the debugger instead.
+        ios::reset_exception_ports()?;
+        Ok(())
+    }
+    #[cfg(target_os = "freebsd")] {
+        // Deny with `procctl`, as FreeBSD has no `PT_DENY_ATTACH`.
+        // `ptrace(PT_TRACE_ME)` would also occupy the tracer slot, but it makes the parent the tracer
+        // and stops the process on every signal until the parent waits for it.
+        unsafe {
+            let mut data = libc::PROC_TRACE_CTL_DISABLE;
+            let result = libc::procctl(libc::P_PID, libc::getpid() as _, libc::PROC_TRACE_CTL, &mut data as *mut _ as _);
+            if result == -1 { return Err(crate::error::last_os_error!(DenyAttachFailed).into()); }
+        }
+        Ok(())
+    }
+    #[cfg(any(target_os = "netbsd", target_os = "openbsd"))] {
+        // There is no primitive to deny attaching: `PT_DENY_ATTACH` and `procctl` don't exist,
+        // and `ptrace(PT_TRACE_ME)` makes the parent the tracer.
+        Err(AntiDebugError::PlatformNotSupported)
+    }
     #[cfg(not(any(
         target_os = "windows",
         target_os = "linux",
         target_os = "android",
         target_os = "macos",
+        all(target_os = "ios", feature = "ios"),
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
     )))]
     compile_error!("Anti-Debug doesn't support current platform.")
 }
 
+/// Attempts to prevent debuggers from attaching, detecting with the checks enabled in `config`.
+///
+/// This is [`deny_attach`] using [`is_debugger_present_with`] on Windows/Linux/Android.
+/// On macOS, iOS and FreeBSD, denying attach doesn't depend on any check, so `config` is ignored.
+/// With the `disarm` feature, or the development override of `init` (`dev-override` feature),
+/// this does nothing and returns `Ok(())`.
+pub fn deny_attach_with(config: &DetectionConfig) -> Result<(), AntiDebugError> {
+    if disarm::disarmed() {
+        return Ok(());
+    }
+    #[cfg(any(target_os = "windows", target_os = "linux", target_os = "android"))] {
+        if is_debugger_present_with(config)? {
+            return Err(AntiDebugError::DebuggerPresent);
+        }
+        Ok(())
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "android")))] {
+        let _ = config;
+        deny_attach()
+    }
+}
+
+/// Attempts to prevent debuggers from attaching like [`deny_attach`],
+/// and additionally makes the current process non-dumpable.
+///
+/// [`deny_attach`] only detects an attached debugger on Linux/Android. This also calls
+/// `prctl(PR_SET_DUMPABLE, 0)` first, after which the kernel denies `ptrace` attaches
+/// from any process without `CAP_SYS_PTRACE`, regardless of the Yama `ptrace_scope`.
+///
+/// # Tradeoffs
+///
+/// Being non-dumpable has side effects on the whole process, which is why [`deny_attach`] doesn't do this:
+///
+/// - No core dumps are written when the process crashes.
+/// - The files under `/proc/<pid>` become owned by root, so tools of the same user,
+///   such as profilers and crash reporters, can no longer read `maps`, `environ` or `mem` of the process.
+/// - It lasts until `execve` or a change of the credentials of the process, as this crate never makes the process dumpable again.
+/// - The fork-based `ptrace` probe (see `is_debugger_present_fork`) is skipped, as its helper could not attach,
+///   so only a debugger visible to the other checks is detected.
+/// - Debuggers running with `CAP_SYS_PTRACE`, such as ones run by root, can still attach.
+///
+/// # Return Value
+///
+/// Returns `Ok(())` if the process was made non-dumpable and no debugger is currently attached,
+/// `Err(AntiDebugError::DebuggerPresent)` if a debugger is already attached,
+/// or `Err(AntiDebugError)` if `prctl` or a check failed.
+///
+/// # Notes
+///
+/// - With the `disarm` feature, or the development override of `init` (`dev-override` feature),
+///   this does nothing and returns `Ok(())`
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn deny_attach_strict() -> Result<(), AntiDebugError> {
+    if disarm::disarmed() {
+        return Ok(());
+    }
+    // Block new attaches before checking for an existing one.
+    linux::prctl(libc::PR_SET_DUMPABLE, 0).map_err(|e| crate::error::os_error!(DenyAttachFailed, e))?;
+    deny_attach()
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -194,9 +872,152 @@ mod tests {
     }
 
     #[test]
+    #[cfg(all(target_os = "windows", feature = "deep-detect"))]
+    fn test_is_debugger_present_deep_detect() {
+        assert!(!super::is_debugger_present().unwrap());
+    }
+
+    #[test]
+    fn test_detect_debugger() {
+        assert_eq!(super::detect_debugger().unwrap_or(None), None);
+    }
+
+    #[test]
+    fn test_detect_debugger_lenient() {
+        assert_eq!(super::detect_debugger_lenient().unwrap(), None);
+    }
+
+    #[test]
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn test_status_unavailable() {
+        assert!(super::status_unavailable(&super::linux::parse_tracer_pid("Name:\tcargo\n").unwrap_err()));
+        assert!(!super::status_unavailable(&super::linux::parse_tracer_pid("TracerPid:\tx\n").unwrap_err()));
+        #[cfg(feature = "std")]
+        assert!(super::status_unavailable(&super::DetectError::ProcStatusUnreadable(std::io::ErrorKind::PermissionDenied.into())));
+    }
+
+    #[test]
+    #[cfg(all(any(target_os = "linux", target_os = "android", target_os = "macos"), feature = "deep-detect", feature = "std"))]
+    fn test_is_debugger_present_fork() {
+        assert!(!super::is_debugger_present_fork().unwrap());
+    }
+
+    #[test]
+    fn test_run_checks() {
+        use super::{DetectError, DetectionReason};
+
+        let fail = || Err(DetectError::Unsupported);
+        let clean = || Ok(false);
+        let found = || Ok(true);
+        let result = super::run_checks(&[(true, DetectionReason::DebugPort, fail), (true, DetectionReason::DebugFlags, found)]);
+        assert_eq!(result.unwrap(), Some(DetectionReason::DebugFlags));
+        let result = super::run_checks(&[(true, DetectionReason::DebugPort, fail), (true, DetectionReason::DebugFlags, clean)]);
+        assert!(matches!(result, Err(DetectError::Unsupported)));
+        let result = super::run_checks(&[(false, DetectionReason::DebugPort, fail), (true, DetectionReason::DebugFlags, clean)]);
+        assert_eq!(result.unwrap(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_exit_on_detect() {
+        assert!(super::exit_on_detect(1).is_ok());
+        assert!(super::abort_on_detect().is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "tracing")]
+    fn test_detect_debugger_traced() {
+        assert_eq!(super::detect_debugger_traced().unwrap_or(None), None);
+    }
+
+    #[test]
+    #[cfg(feature = "deep-detect")]
+    fn test_hardware_breakpoints_present() {
+        match super::hardware_breakpoints_present() {
+            Ok(present) => assert!(!present),
+            Err(e) => assert!(matches!(e, super::DetectError::Unsupported)),
+        }
+    }
+
+    #[test]
+    fn test_kernel_debugger_present() {
+        match super::kernel_debugger_present() {
+            Ok(present) => assert!(!present),
+            Err(e) => assert!(matches!(e, super::DetectError::Unsupported)),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "memory-integrity")]
+    fn test_scan_for_software_breakpoints() {
+        match super::scan_for_software_breakpoints() {
+            Ok(found) => assert!(!found),
+            Err(e) => assert!(matches!(e, super::DetectError::Unsupported)),
+        }
+    }
+
+    #[test]
+    fn test_is_debugger_present_with() {
+        assert!(!super::is_debugger_present_with(&super::DetectionConfig::all()).unwrap());
+        assert!(!super::is_debugger_present_with(&super::DetectionConfig::fast()).unwrap());
+        assert!(!super::is_debugger_present_with(&super::DetectionConfig::none()).unwrap());
+    }
+
+    #[test]
+    #[cfg(not(any(target_os = "netbsd", target_os = "openbsd")))]
+    fn test_deny_attach_with() {
+        super::deny_attach_with(&super::DetectionConfig::fast()).unwrap();
+    }
+
+    #[test]
+    #[cfg(not(any(target_os = "netbsd", target_os = "openbsd")))]
     fn test_deny_attach() {
         super::deny_attach().unwrap();
         super::deny_attach().unwrap();
         super::deny_attach().unwrap();
     }
+
+    #[test]
+    #[cfg(any(target_os = "netbsd", target_os = "openbsd"))]
+    fn test_deny_attach() {
+        assert!(matches!(super::deny_attach(), Err(super::AntiDebugError::PlatformNotSupported)));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_detect_error_into_io_error() {
+        let error = std::io::Error::from(super::DetectError::InvalidTracerPid("abc".into()));
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        let error = std::io::Error::from(super::DetectError::ProcStatusUnreadable(std::io::ErrorKind::NotFound.into()));
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_anti_debug_error_from_detect_error() {
+        let error = super::AntiDebugError::from(super::DetectError::InvalidTracerPid("abc".into()));
+        assert!(matches!(error, super::AntiDebugError::ParseError { field: "TracerPid", raw } if raw == "abc"));
+        let error = super::AntiDebugError::from(super::DetectError::Unsupported);
+        assert!(matches!(error, super::AntiDebugError::PlatformNotSupported));
+        let error = super::AntiDebugError::from(super::DetectError::ProcessHeapUnavailable);
+        assert!(matches!(error, super::AntiDebugError::Detect(super::DetectError::ProcessHeapUnavailable)));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_anti_debug_error_into_io_error() {
+        let error = super::AntiDebugError::from(super::DetectError::ProcStatusUnreadable(std::io::ErrorKind::NotFound.into()));
+        assert!(matches!(error, super::AntiDebugError::OsError(_)));
+        assert!(std::error::Error::source(&error).is_some());
+        assert_eq!(std::io::Error::from(error).kind(), std::io::ErrorKind::NotFound);
+        let error = std::io::Error::from(super::AntiDebugError::DebuggerPresent);
+        assert_eq!(error.kind(), std::io::ErrorKind::AlreadyExists);
+    }
+
+    #[test]
+    #[cfg(all(target_os = "windows", feature = "std"))]
+    fn test_nt_query_failed_into_io_error() {
+        // STATUS_ACCESS_DENIED maps to ERROR_ACCESS_DENIED.
+        let error = std::io::Error::from(super::DetectError::NtQueryFailed(0xC0000022u32 as i32));
+        assert_eq!(error.raw_os_error(), Some(5));
+    }
 }
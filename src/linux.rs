@@ -0,0 +1,983 @@
+//! Linux/Android-specific detection checks.
+
+/// Reads the content of `/proc/self/status`.
+#[cfg(all(feature = "std", not(feature = "raw-syscall")))]
+pub(crate) fn read_status() -> Result<String, crate::DetectError> {
+    let path = crate::obfuscate::obfuscated!("/proc/self/status");
+    std::fs::read_to_string(crate::obfuscate::as_str(&path)).map_err(crate::DetectError::ProcStatusUnreadable)
+}
+
+/// Reads the content of `/proc/self/maps`.
+#[cfg(feature = "std")]
+fn read_maps() -> Result<String, crate::DetectError> {
+    let path = crate::obfuscate::obfuscated!("/proc/self/maps");
+    std::fs::read_to_string(crate::obfuscate::as_str(&path)).map_err(crate::DetectError::ProcMapsUnreadable)
+}
+
+/// Returns the path of the executable of the current process, read from `/proc/self/exe`.
+#[cfg(feature = "memory-integrity")]
+fn executable_path() -> Result<std::path::PathBuf, crate::DetectError> {
+    let path = crate::obfuscate::obfuscated!("/proc/self/exe");
+    std::fs::read_link(crate::obfuscate::as_str(&path)).map_err(crate::DetectError::ExecutableUnreadable)
+}
+
+/// Reads the content of `/proc/self/status` with `open`/`read` system calls.
+#[cfg(not(any(feature = "std", feature = "raw-syscall")))]
+pub(crate) fn read_status() -> Result<alloc::string::String, crate::DetectError> {
+    let path = crate::obfuscate::obfuscated!("/proc/self/status\0");
+    unsafe {
+        let fd = libc::open(path.as_ptr().cast(), libc::O_RDONLY | libc::O_CLOEXEC);
+        if fd == -1 {
+            return Err(crate::error::last_os_error!(ProcStatusUnreadable));
+        }
+        let mut content = alloc::vec::Vec::new();
+        let mut buffer = [0u8; 1024];
+        loop {
+            let read = libc::read(fd, buffer.as_mut_ptr() as _, buffer.len());
+            if read == 0 {
+                break;
+            }
+            if read == -1 {
+                if crate::error::raw_os_error() == libc::EINTR {
+                    continue;
+                }
+                let error = crate::error::last_os_error!(ProcStatusUnreadable);
+                libc::close(fd);
+                return Err(error);
+            }
+            content.extend_from_slice(&buffer[..read as usize]);
+        }
+        libc::close(fd);
+        Ok(alloc::string::String::from_utf8_lossy(&content).into_owned())
+    }
+}
+
+/// Reads the content of `/proc/self/status` with raw `openat`/`read` system calls,
+/// bypassing hooks of the libc wrappers.
+#[cfg(feature = "raw-syscall")]
+pub(crate) fn read_status() -> Result<alloc::string::String, crate::DetectError> {
+    let path = crate::obfuscate::obfuscated!("/proc/self/status\0");
+    let path = core::ffi::CStr::from_bytes_until_nul(&path).unwrap_or_default();
+    let fd = crate::syscall::openat(path, libc::O_RDONLY | libc::O_CLOEXEC)
+        .map_err(|e| crate::error::os_error!(ProcStatusUnreadable, e))?;
+    let mut content = alloc::vec::Vec::new();
+    let mut buffer = [0u8; 1024];
+    let result = loop {
+        match crate::syscall::read(fd, &mut buffer) {
+            Ok(0) => break Ok(()),
+            Ok(read) => content.extend_from_slice(&buffer[..read]),
+            Err(libc::EINTR) => {}
+            Err(e) => break Err(crate::error::os_error!(ProcStatusUnreadable, e)),
+        }
+    };
+    let _ = crate::syscall::close(fd);
+    result?;
+    Ok(alloc::string::String::from_utf8_lossy(&content).into_owned())
+}
+
+/// Calls `prctl(option, arg)`, with a raw system call with the `raw-syscall` feature.
+///
+/// Returns the result, or the `errno` if the call failed.
+pub(crate) fn prctl(option: libc::c_int, arg: libc::c_ulong) -> Result<libc::c_int, i32> {
+    #[cfg(feature = "raw-syscall")]
+    return crate::syscall::prctl(option, arg);
+    #[cfg(not(feature = "raw-syscall"))]
+    match unsafe { libc::prctl(option, arg, 0, 0, 0) } {
+        -1 => Err(crate::error::raw_os_error()),
+        result => Ok(result),
+    }
+}
+
+/// Checks if hardware breakpoints are set on the current process.
+///
+/// Parses the `HWBreakpoint` field of `/proc/self/status`, which only some kernel
+/// configurations expose. Mainline kernels don't, in which case `Ok(false)` is returned.
+///
+/// This is the counterpart of `windows::has_hardware_breakpoints`.
+#[cfg(feature = "deep-detect")]
+pub fn has_hardware_breakpoints() -> Result<bool, crate::DetectError> {
+    Ok(parse_hardware_breakpoints(&read_status()?))
+}
+
+/// Parses the `HWBreakpoint` field from the content of `/proc/self/status`.
+///
+/// A missing or malformed field is reported as no hardware breakpoints.
+#[cfg(feature = "deep-detect")]
+pub(crate) fn parse_hardware_breakpoints(status: &str) -> bool {
+    let field = crate::obfuscate::obfuscated!("HWBreakpoint:");
+    status
+        .lines()
+        .filter_map(|line| line.strip_prefix(crate::obfuscate::as_str(&field)))
+        .filter_map(|count| count.trim().parse::<u64>().ok())
+        .any(|count| count != 0)
+}
+
+/// Checks if the OS is a debuggable build, i.e. the `ro.debuggable` system property is `1`.
+///
+/// Any process can be attached to on such builds (such as `userdebug`/`eng` builds and most emulators)
+/// without being marked debuggable, so this is a secondary signal rather than an attached debugger.
+#[cfg(target_os = "android")]
+pub fn is_android_debug_build() -> bool {
+    let mut value = [0 as libc::c_char; libc::PROP_VALUE_MAX as usize];
+    let length = unsafe { libc::__system_property_get(c"ro.debuggable".as_ptr(), value.as_mut_ptr()) };
+    length == 1 && value[0] == b'1' as libc::c_char
+}
+
+/// The default port `frida-server` listens on.
+#[cfg(all(target_os = "android", feature = "std"))]
+const FRIDA_DEFAULT_PORT: u16 = 27042;
+
+/// Checks for signs of debugging or instrumentation common on Android.
+///
+/// Reports a tracer in the `TracerPid` field of `/proc/self/status`,
+/// a known hook library (such as the Frida agent, Riru or Xposed) found by [`suspicious_mappings`],
+/// or the default port of `frida-server` being bound on the loopback interface.
+/// The port is probed by binding it, as SELinux denies reading `/proc/net/tcp` to apps.
+///
+/// Requires the `std` feature.
+#[cfg(all(target_os = "android", feature = "std"))]
+pub fn android_instrumentation_detected() -> Result<bool, crate::DetectError> {
+    if parse_tracer_pid(&read_status()?)? != 0 {
+        return Ok(true);
+    }
+    if !suspicious_mappings()?.is_empty() {
+        return Ok(true);
+    }
+    let bound = std::net::TcpListener::bind((std::net::Ipv4Addr::LOCALHOST, FRIDA_DEFAULT_PORT))
+        .is_err_and(|e| e.kind() == std::io::ErrorKind::AddrInUse);
+    Ok(bound)
+}
+
+/// Returns the PID of the process tracing the current process,
+/// i.e. the `TracerPid` field in `/proc/self/status`.
+///
+/// Returns `Ok(None)` if the current process is not traced,
+/// or `Err(DetectError)` if the file could not be read or the field is missing or malformed.
+pub fn tracer_pid() -> Result<Option<u32>, crate::DetectError> {
+    let pid = parse_tracer_pid(&read_status()?)?;
+    Ok((pid > 0).then_some(pid as u32))
+}
+
+/// Returns the PID of the process tracing the current process like [`tracer_pid`],
+/// but reports a missing `TracerPid` field as not traced.
+///
+/// Some sandboxes report a `/proc/self/status` without the field.
+/// A malformed field, or a file that could not be read, is still an error.
+pub fn tracer_pid_lenient() -> Result<Option<u32>, crate::DetectError> {
+    match tracer_pid() {
+        Err(crate::DetectError::InvalidTracerPid(raw)) if raw.is_empty() => Ok(None),
+        result => result,
+    }
+}
+
+/// Parses the `TracerPid` field from the content of `/proc/self/status`.
+pub(crate) fn parse_tracer_pid(status: &str) -> Result<i32, crate::DetectError> {
+    let field = crate::obfuscate::obfuscated!("TracerPid:");
+    let raw = status
+        .lines()
+        .find_map(|line| line.strip_prefix(crate::obfuscate::as_str(&field)))
+        .unwrap_or_default()
+        .trim();
+    raw.parse::<i32>().map_err(|_| crate::DetectError::InvalidTracerPid(raw.into()))
+}
+
+/// Parses the PPID field from the content of `/proc/<pid>/stat`.
+///
+/// The process name in the second field may contain spaces and parentheses,
+/// so the fields are counted after its last closing parenthesis.
+#[cfg(feature = "std")]
+pub(crate) fn parse_ppid(stat: &str) -> Option<i32> {
+    let (_, fields) = stat.rsplit_once(')')?;
+    fields.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// Returns the name of the parent process, such as `gdb`.
+///
+/// Reads the PPID from `/proc/self/stat`, and then the name from `/proc/<ppid>/comm`.
+/// Returns `Ok(None)` if the parent process has already exited.
+#[cfg(feature = "std")]
+pub fn parent_process_name() -> Result<Option<String>, crate::DetectError> {
+    let path = crate::obfuscate::obfuscated!("/proc/self/stat");
+    let stat = std::fs::read_to_string(crate::obfuscate::as_str(&path)).map_err(crate::DetectError::ParentProcessQueryFailed)?;
+    let ppid = parse_ppid(&stat)
+        .ok_or_else(|| crate::DetectError::ParentProcessQueryFailed(std::io::ErrorKind::InvalidData.into()))?;
+    if ppid == 0 {
+        return Ok(None);
+    }
+    match read_comm(ppid) {
+        Ok(comm) => Ok(Some(comm)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(crate::DetectError::ParentProcessQueryFailed(e)),
+    }
+}
+
+/// Reads the name of the process `pid` from `/proc/<pid>/comm`.
+#[cfg(feature = "std")]
+pub(crate) fn read_comm(pid: i32) -> std::io::Result<String> {
+    let comm = std::fs::read_to_string(format!("/proc/{pid}/comm"))?;
+    Ok(comm.trim_end_matches('\n').to_owned())
+}
+
+/// Returns the wait channel of the main thread of the current process, i.e. the content of `/proc/self/wchan`.
+///
+/// The wait channel is the kernel function a sleeping thread waits in, such as `ptrace_stop`
+/// while the thread is stopped by a tracer, or `0` while it is running.
+/// Kernels hiding kernel symbols from the process report `0` as well.
+///
+/// Requires the `std` feature.
+#[cfg(feature = "std")]
+pub fn wchan() -> Result<String, crate::DetectError> {
+    let path = crate::obfuscate::obfuscated!("/proc/self/wchan");
+    std::fs::read_to_string(crate::obfuscate::as_str(&path)).map_err(crate::DetectError::ProcWchanUnreadable)
+}
+
+/// Checks if a thread of the current process is stopped by `ptrace`, according to the
+/// `wchan` of the threads in `/proc/self/task`.
+///
+/// The calling thread is running while it checks, so this only catches the other threads,
+/// such as the ones a debugger in non-stop mode keeps stopped, and the main thread while
+/// a debugger steps it and a background thread checks.
+/// Kernels without `wchan`, or threads exiting during the check, are reported as not stopped.
+///
+/// Requires the `std` and `deep-detect` features.
+#[cfg(all(feature = "deep-detect", feature = "std"))]
+pub fn is_wchan_ptrace_related() -> Result<bool, crate::DetectError> {
+    let path = crate::obfuscate::obfuscated!("/proc/self/task");
+    let tasks = match std::fs::read_dir(crate::obfuscate::as_str(&path)) {
+        Ok(tasks) => tasks,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(crate::DetectError::ProcWchanUnreadable(e)),
+    };
+    for task in tasks {
+        let task = task.map_err(crate::DetectError::ProcWchanUnreadable)?;
+        match std::fs::read_to_string(task.path().join("wchan")) {
+            Ok(wchan) if wchan.contains("ptrace") => return Ok(true),
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(crate::DetectError::ProcWchanUnreadable(e)),
+        }
+    }
+    Ok(false)
+}
+
+/// Checks if the tracer slot of the current process is already taken.
+///
+/// A process can be traced by only one tracer at a time, so a `ptrace` attach
+/// fails with `EPERM` while a debugger holds the slot.
+///
+/// `PTRACE_TRACEME` cannot be undone by the tracee itself, so the attempt is made
+/// from a forked helper instead: the helper seizes the current process with
+/// `PTRACE_SEIZE` (which doesn't stop it or inject any signal) and exits right away,
+/// which makes the kernel release the slot again.
+/// When the attach is denied, the helper calls `PTRACE_TRACEME` on itself to tell
+/// a taken slot from `ptrace` being forbidden by seccomp or Yama,
+/// in which case no debugger is reported.
+///
+/// The slot of the current process is never consumed, and this can't be fooled
+/// by a debugger faking the `TracerPid` field in `/proc/self/status`.
+/// Under the restricted Yama `ptrace_scope` (`1`), the helper is allowed to attach with `prctl(PR_SET_PTRACER)`,
+/// so the ptracer exception of the current process is reset afterward. It's left alone under the other scopes.
+/// A non-dumpable process (see [`deny_attach_strict`](crate::deny_attach_strict)) can't be attached to
+/// by the helper, so no helper is forked and no debugger is reported.
+/// Requires the `std` and `deep-detect` features.
+#[cfg(all(feature = "deep-detect", feature = "std"))]
+pub fn self_ptrace_detected() -> Result<bool, crate::DetectError> {
+    const PTRACE_SEIZE: libc::c_int = 0x4206;
+    const EXIT_ATTACHED: libc::c_int = 0;
+    const EXIT_SLOT_TAKEN: libc::c_int = 1;
+    const EXIT_PTRACE_DENIED: libc::c_int = 2;
+    const EXIT_FAILED: libc::c_int = 3;
+
+    // The helper could not attach, and the flag is never changed behind the caller's back.
+    if prctl(libc::PR_GET_DUMPABLE, 0) == Ok(0) {
+        return Ok(false);
+    }
+    let path = crate::obfuscate::obfuscated!("/proc/sys/kernel/yama/ptrace_scope");
+    let yama_restricted = std::fs::read_to_string(crate::obfuscate::as_str(&path)).is_ok_and(|scope| scope.trim() == "1");
+    unsafe {
+        let parent = libc::getpid();
+        let mut fds = [0; 2];
+        if libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) == -1 {
+            return Err(crate::error::last_os_error!(PtraceFailed));
+        }
+        let [read_fd, write_fd] = fds;
+        let child = libc::fork();
+        if child == 0 {
+            // Only async-signal-safe calls are allowed in the forked child.
+            libc::close(write_fd);
+            let mut byte = 0u8;
+            while libc::read(read_fd, &mut byte as *mut _ as _, 1) == -1
+                && crate::error::raw_os_error() == libc::EINTR {}
+            if libc::ptrace(PTRACE_SEIZE as _, parent, core::ptr::null_mut::<libc::c_void>(), 0) == 0 {
+                libc::_exit(EXIT_ATTACHED);
+            }
+            if crate::error::raw_os_error() != libc::EPERM {
+                libc::_exit(EXIT_FAILED);
+            }
+            if libc::ptrace(libc::PTRACE_TRACEME as _, 0, core::ptr::null_mut::<libc::c_void>(), 0) == -1 {
+                libc::_exit(EXIT_PTRACE_DENIED);
+            }
+            libc::_exit(EXIT_SLOT_TAKEN);
+        }
+        libc::close(read_fd);
+        if child == -1 {
+            let error = std::io::Error::last_os_error();
+            libc::close(write_fd);
+            return Err(crate::DetectError::PtraceFailed(error));
+        }
+        // Allow the helper to attach under Yama's restricted mode.
+        if yama_restricted {
+            let _ = prctl(libc::PR_SET_PTRACER, child as libc::c_ulong);
+        }
+        libc::write(write_fd, &0u8 as *const _ as _, 1);
+        libc::close(write_fd);
+        let mut status = 0;
+        let result = loop {
+            let result = libc::waitpid(child, &mut status, 0);
+            if result != -1 || crate::error::raw_os_error() != libc::EINTR {
+                break result;
+            }
+        };
+        if yama_restricted {
+            let _ = prctl(libc::PR_SET_PTRACER, 0);
+        }
+        if result == -1 {
+            return Err(crate::error::last_os_error!(PtraceFailed));
+        }
+        if !libc::WIFEXITED(status) {
+            return Err(crate::DetectError::PtraceFailed(std::io::Error::other("ptrace helper terminated abnormally")));
+        }
+        match libc::WEXITSTATUS(status) {
+            EXIT_ATTACHED | EXIT_PTRACE_DENIED => Ok(false),
+            EXIT_SLOT_TAKEN => Ok(true),
+            _ => Err(crate::DetectError::PtraceFailed(std::io::Error::other("ptrace helper failed"))),
+        }
+    }
+}
+
+/// Checks if a debugger swallows a `SIGTRAP` raised by the current thread.
+///
+/// A temporary `SIGTRAP` handler is installed and the signal is raised: without a debugger
+/// the handler runs, while a debugger stops on the signal and doesn't deliver it by default.
+/// This doesn't depend on `ptrace` or `/proc`, so it also detects debuggers faking them.
+///
+/// `SIGTRAP` is unblocked for the current thread while it's raised, so a blocked signal isn't mistaken
+/// for a swallowed one. The previous `sigaction` and signal mask are restored afterward, and concurrent calls are serialized.
+/// Debuggers configured to pass `SIGTRAP` to the process (such as `handle SIGTRAP pass` in `gdb`)
+/// are not detected.
+///
+/// Returns `Err` without raising the signal if the handler could not be installed,
+/// as raising it would terminate the process.
+pub fn sigtrap_detected() -> Result<bool, crate::DetectError> {
+    use core::sync::atomic::{AtomicBool, Ordering};
+
+    /// Whether a call is in progress, as the `sigaction` is process-wide.
+    static LOCKED: AtomicBool = AtomicBool::new(false);
+    /// Whether the handler ran.
+    static HANDLED: AtomicBool = AtomicBool::new(false);
+
+    extern "C" fn handler(_signal: libc::c_int) {
+        HANDLED.store(true, Ordering::SeqCst);
+    }
+
+    while LOCKED.compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed).is_err() {
+        core::hint::spin_loop();
+    }
+    let result = unsafe {
+        let mut action = core::mem::zeroed::<libc::sigaction>();
+        action.sa_sigaction = handler as *const () as libc::sighandler_t;
+        libc::sigemptyset(&mut action.sa_mask);
+        let mut previous = core::mem::zeroed::<libc::sigaction>();
+        HANDLED.store(false, Ordering::SeqCst);
+        if libc::sigaction(libc::SIGTRAP, &action, &mut previous) == 0 {
+            let mut mask = core::mem::zeroed::<libc::sigset_t>();
+            libc::sigemptyset(&mut mask);
+            libc::sigaddset(&mut mask, libc::SIGTRAP);
+            let mut previous_mask = core::mem::zeroed::<libc::sigset_t>();
+            libc::pthread_sigmask(libc::SIG_UNBLOCK, &mask, &mut previous_mask);
+            libc::raise(libc::SIGTRAP);
+            libc::pthread_sigmask(libc::SIG_SETMASK, &previous_mask, core::ptr::null_mut());
+            libc::sigaction(libc::SIGTRAP, &previous, core::ptr::null_mut());
+            Ok(!HANDLED.load(Ordering::SeqCst))
+        } else {
+            Err(crate::error::last_os_error!(SignalHandlerFailed))
+        }
+    };
+    LOCKED.store(false, Ordering::Release);
+    result
+}
+
+/// The file name fragments of libraries used to hook or instrument processes,
+/// such as anti-anti-debugging shims faking `/proc/self/status`.
+#[cfg(feature = "std")]
+const KNOWN_HOOK_LIBRARIES: &[&str] = &[
+    "frida",
+    "gum-js-loop",
+    "libhook",
+    "libsubstrate",
+    "libdobby",
+    "libxhook",
+    "xposed",
+    "libriru",
+];
+
+/// Checks if libraries are preloaded into the current process or known hook libraries are mapped.
+///
+/// Reports a non-empty `LD_PRELOAD` environment variable,
+/// or any mapping found by [`suspicious_mappings`].
+/// A preloaded shim can fake `/proc/self/status`, which hides the tracer from the `TracerPid` check.
+#[cfg(feature = "std")]
+pub fn suspicious_preload() -> Result<bool, crate::DetectError> {
+    if std::env::var_os("LD_PRELOAD").is_some_and(|preload| !preload.is_empty()) {
+        return Ok(true);
+    }
+    Ok(!suspicious_mappings()?.is_empty())
+}
+
+/// Returns the paths of the known hook libraries, such as Frida, mapped into the current process.
+///
+/// The paths are listed in `/proc/self/maps`, and each path is returned once.
+#[cfg(feature = "std")]
+pub fn suspicious_mappings() -> Result<Vec<String>, crate::DetectError> {
+    let maps = read_maps()?;
+    Ok(parse_suspicious_mappings(&maps))
+}
+
+/// Parses the paths of the known hook libraries from the content of `/proc/self/maps`.
+#[cfg(feature = "std")]
+pub(crate) fn parse_suspicious_mappings(maps: &str) -> Vec<String> {
+    let mut paths = Vec::<String>::new();
+    for line in maps.lines() {
+        // start-end perms offset dev inode [path]
+        let Some(path) = line.splitn(6, ' ').nth(5).map(str::trim_start) else {
+            continue;
+        };
+        let name = path.rsplit('/').next().unwrap_or(path).to_ascii_lowercase();
+        if KNOWN_HOOK_LIBRARIES.iter().any(|library| name.contains(library)) && !paths.iter().any(|known| known == path) {
+            paths.push(path.to_owned());
+        }
+    }
+    paths
+}
+
+/// Returns the libraries injected with the preload mechanisms of the dynamic linker,
+/// see [`preload_injection_present`](crate::preload_injection_present).
+#[cfg(feature = "std")]
+pub(crate) fn injected_libraries() -> Result<Vec<crate::InjectedLibrary>, crate::DetectError> {
+    use crate::{InjectedLibrary, InjectionSource};
+
+    let mut found = Vec::new();
+    for (variable, source) in [("LD_PRELOAD", InjectionSource::LdPreload), ("LD_AUDIT", InjectionSource::LdAudit)] {
+        if let Some(value) = std::env::var_os(variable) {
+            found.extend(parse_preload_list(&value.to_string_lossy()).map(|path| InjectedLibrary::new(path, source)));
+        }
+    }
+    // The dynamic linker reads the file with the permissions of the process,
+    // so a file the process can't read preloaded nothing.
+    let path = crate::obfuscate::obfuscated!("/etc/ld.so.preload");
+    if let Ok(content) = std::fs::read_to_string(crate::obfuscate::as_str(&path)) {
+        found.extend(parse_preload_list(&content).map(|path| InjectedLibrary::new(path, InjectionSource::PreloadFile)));
+    }
+    let objects = link_map();
+    let vdso = unsafe { libc::getauxval(libc::AT_SYSINFO_EHDR) } as usize;
+    found.extend(unneeded_objects(&objects, vdso).map(|path| InjectedLibrary::new(path, InjectionSource::Unneeded)));
+    let maps = read_maps()?;
+    found.extend(unlinked_mappings(&maps, &objects).into_iter().map(|path| InjectedLibrary::new(path, InjectionSource::UnlinkedMapping)));
+    Ok(found)
+}
+
+/// Splits a list of paths of `LD_PRELOAD`, `LD_AUDIT` or `/etc/ld.so.preload`,
+/// which the dynamic linker separates by colons or whitespace.
+#[cfg(feature = "std")]
+fn parse_preload_list(list: &str) -> impl Iterator<Item = &str> {
+    list.split(|c: char| c == ':' || c.is_ascii_whitespace()).filter(|path| !path.is_empty())
+}
+
+/// An object in the link map of the dynamic linker, see [`link_map`].
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+struct LoadedObject {
+    /// The path the object was loaded from, which is empty for the main executable.
+    path: String,
+    /// The `DT_SONAME` entry.
+    soname: Option<String>,
+    /// The `DT_NEEDED` entries.
+    needed: Vec<String>,
+    /// The `PT_INTERP` program header, i.e. the path of the dynamic linker.
+    interpreter: Option<String>,
+    /// The address ranges of the `PT_LOAD` segments.
+    segments: Vec<core::ops::Range<usize>>,
+}
+
+#[cfg(feature = "std")]
+impl LoadedObject {
+    /// Checks if the object is the one named by a `DT_NEEDED` entry of `needed`.
+    fn is_named(&self, needed: &str) -> bool {
+        let file_name = self.path.rsplit('/').next().unwrap_or_default();
+        self.path == needed || file_name == needed || self.soname.as_deref() == Some(needed)
+    }
+
+    /// Checks if the object has a segment overlapping `range`.
+    fn overlaps(&self, range: &core::ops::Range<usize>) -> bool {
+        self.segments.iter().any(|segment| segment.start < range.end && range.start < segment.end)
+    }
+}
+
+/// Returns the objects in the link map of the dynamic linker, in load order, with `dl_iterate_phdr`.
+#[cfg(feature = "std")]
+fn link_map() -> Vec<LoadedObject> {
+    unsafe extern "C" fn callback(info: *mut libc::dl_phdr_info, _size: libc::size_t, data: *mut libc::c_void) -> libc::c_int {
+        let objects = unsafe { &mut *(data as *mut Vec<LoadedObject>) };
+        objects.push(unsafe { loaded_object(&*info) });
+        0
+    }
+
+    let mut objects = Vec::new();
+    unsafe { libc::dl_iterate_phdr(Some(callback), &mut objects as *mut Vec<LoadedObject> as _) };
+    objects
+}
+
+/// Reads the program headers and the dynamic section of an object reported by `dl_iterate_phdr`.
+#[cfg(feature = "std")]
+unsafe fn loaded_object(info: &libc::dl_phdr_info) -> LoadedObject {
+    const PT_LOAD: u32 = 1;
+    const PT_DYNAMIC: u32 = 2;
+    const PT_INTERP: u32 = 3;
+    const DT_NULL: isize = 0;
+    const DT_NEEDED: isize = 1;
+    const DT_STRTAB: isize = 5;
+    const DT_SONAME: isize = 14;
+
+    /// `Elf32_Dyn` or `Elf64_Dyn`, whose fields have the size of a pointer.
+    #[repr(C)]
+    struct Dyn {
+        tag: isize,
+        value: usize,
+    }
+
+    let string = |pointer: *const libc::c_char| unsafe { core::ffi::CStr::from_ptr(pointer) }.to_string_lossy().into_owned();
+    let base = info.dlpi_addr as usize;
+    let mut object = LoadedObject::default();
+    if !info.dlpi_name.is_null() {
+        object.path = string(info.dlpi_name);
+    }
+    let headers = match info.dlpi_phdr.is_null() {
+        true => &[][..],
+        false => unsafe { core::slice::from_raw_parts(info.dlpi_phdr, info.dlpi_phnum as usize) },
+    };
+    let mut dynamic = None;
+    for header in headers {
+        let start = base.wrapping_add(header.p_vaddr as usize);
+        match header.p_type {
+            PT_LOAD => object.segments.push(start..start.wrapping_add(header.p_memsz as usize)),
+            PT_DYNAMIC => dynamic = Some(start as *const Dyn),
+            PT_INTERP => object.interpreter = Some(string(start as _)),
+            _ => {}
+        }
+    }
+    let Some(mut entry) = dynamic else {
+        return object;
+    };
+    let (mut strtab, mut needed, mut soname) = (None, Vec::new(), None);
+    loop {
+        let Dyn { tag, value } = unsafe { entry.read() };
+        match tag {
+            DT_NULL => break,
+            DT_NEEDED => needed.push(value),
+            DT_SONAME => soname = Some(value),
+            // glibc relocates the address in place, while other dynamic linkers and the vDSO leave it relative.
+            DT_STRTAB => strtab = Some(if value < base { base.wrapping_add(value) } else { value }),
+            _ => {}
+        }
+        entry = unsafe { entry.add(1) };
+    }
+    let Some(strtab) = strtab.filter(|&strtab| object.overlaps(&(strtab..strtab + 1))) else {
+        return object;
+    };
+    let name = |offset: usize| string(strtab.wrapping_add(offset) as _);
+    object.needed = needed.into_iter().map(name).collect();
+    object.soname = soname.map(name);
+    object
+}
+
+/// Returns the paths of the objects loaded at startup which neither the executable nor its libraries need.
+///
+/// The dynamic linker loads the executable, then the preloaded objects, and then the needed ones,
+/// while objects loaded later with `dlopen` follow the last needed one.
+/// The dynamic linker itself, and the vDSO mapped at `vdso`, are not reported.
+#[cfg(feature = "std")]
+fn unneeded_objects(objects: &[LoadedObject], vdso: usize) -> impl Iterator<Item = &str> {
+    let Some(executable) = objects.first() else {
+        return Vec::new().into_iter();
+    };
+    let mut needed: Vec<&str> = executable.needed.iter().map(String::as_str).collect();
+    if let Some(interpreter) = &executable.interpreter {
+        needed.push(interpreter);
+    }
+    let mut reachable = vec![false; objects.len()];
+    reachable[0] = true;
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (index, object) in objects.iter().enumerate() {
+            if !reachable[index] && needed.iter().any(|needed| object.is_named(needed)) {
+                reachable[index] = true;
+                needed.extend(object.needed.iter().map(String::as_str));
+                changed = true;
+            }
+        }
+    }
+    let last = reachable.iter().rposition(|&reachable| reachable).unwrap_or_default();
+    objects[..last].iter().zip(&reachable)
+        .filter(|&(object, &reachable)| !reachable && !object.path.is_empty() && !object.overlaps(&(vdso..vdso + 1)))
+        .map(|(object, _)| object.path.as_str())
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
+/// Parses the paths of the shared objects mapped as code outside of all `objects` from the content of `/proc/self/maps`.
+#[cfg(feature = "std")]
+fn unlinked_mappings(maps: &str, objects: &[LoadedObject]) -> Vec<String> {
+    let mut paths = Vec::<String>::new();
+    for line in maps.lines() {
+        // start-end perms offset dev inode [path]
+        let mut fields = line.splitn(6, ' ');
+        let (Some(range), Some(perms), Some(path)) = (fields.next(), fields.next(), fields.nth(3).map(str::trim_start)) else {
+            continue;
+        };
+        let file_name = path.rsplit('/').next().unwrap_or(path);
+        if !perms.contains('x') || path.starts_with('[') || !file_name.contains(".so") {
+            continue;
+        }
+        let Some((start, end)) = range.split_once('-') else {
+            continue;
+        };
+        let (Ok(start), Ok(end)) = (usize::from_str_radix(start, 16), usize::from_str_radix(end, 16)) else {
+            continue;
+        };
+        if !objects.iter().any(|object| object.overlaps(&(start..end))) && !paths.iter().any(|known| known == path) {
+            paths.push(path.to_owned());
+        }
+    }
+    paths
+}
+
+/// Reads the memory of the current process at `address` into `buffer` with `process_vm_readv`,
+/// which fails instead of crashing on unreadable memory.
+#[cfg(feature = "memory-integrity")]
+pub(crate) fn read_memory(address: *const u8, buffer: &mut [u8]) -> Result<(), crate::DetectError> {
+    unsafe {
+        let local = libc::iovec { iov_base: buffer.as_mut_ptr() as _, iov_len: buffer.len() };
+        let remote = libc::iovec { iov_base: address as _, iov_len: buffer.len() };
+        let read = libc::process_vm_readv(libc::getpid(), &local, 1, &remote, 1, 0);
+        if read == -1 {
+            return Err(crate::DetectError::MemoryUnreadable(std::io::Error::last_os_error()));
+        }
+        if read as usize != buffer.len() {
+            return Err(crate::DetectError::MemoryUnreadable(std::io::ErrorKind::UnexpectedEof.into()));
+        }
+    }
+    Ok(())
+}
+
+/// An executable mapping of a file, as listed in `/proc/self/maps`.
+#[cfg(feature = "memory-integrity")]
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct ExecutableMapping {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    pub(crate) readable: bool,
+    pub(crate) offset: u64,
+}
+
+/// Parses the executable mappings of the file at `path` from the content of `/proc/self/maps`.
+#[cfg(feature = "memory-integrity")]
+pub(crate) fn parse_executable_mappings(maps: &str, path: &std::path::Path) -> Vec<ExecutableMapping> {
+    maps.lines().filter_map(|line| {
+        // start-end perms offset dev inode [path]
+        let mut fields = line.splitn(6, ' ');
+        let (start, end) = fields.next()?.split_once('-')?;
+        let perms = fields.next()?;
+        let offset = fields.next()?;
+        let file = fields.nth(2)?.trim_start();
+        if !perms.contains('x') || std::path::Path::new(file) != path {
+            return None;
+        }
+        Some(ExecutableMapping {
+            start: usize::from_str_radix(start, 16).ok()?,
+            end: usize::from_str_radix(end, 16).ok()?,
+            readable: perms.starts_with('r'),
+            offset: u64::from_str_radix(offset, 16).ok()?,
+        })
+    }).collect()
+}
+
+/// Returns the load base of the current executable and the address and size of each of its
+/// readable executable mappings, as listed in `/proc/self/maps`.
+///
+/// Mappings that aren't readable are skipped with a warning through the `log` feature.
+#[cfg(feature = "memory-integrity")]
+pub(crate) fn executable_code_regions() -> Result<(usize, Vec<(usize, usize)>), crate::DetectError> {
+    let path = executable_path()?;
+    let maps = read_maps()?;
+    let mappings = parse_executable_mappings(&maps, &path);
+    let base = mappings.iter().map(|mapping| mapping.start - mapping.offset as usize).min().unwrap_or_default();
+    let regions = mappings.into_iter().filter_map(|mapping| {
+        if !mapping.readable {
+            crate::log_warn!("skipping unreadable mapping {:#x}-{:#x}", mapping.start, mapping.end);
+            return None;
+        }
+        Some((mapping.start, mapping.end - mapping.start))
+    }).collect();
+    Ok((base, regions))
+}
+
+/// Checks if `INT3` (`0xCC`) bytes were written into the executable mappings of the current executable.
+///
+/// Every executable mapping of `/proc/self/exe` listed in `/proc/self/maps` is compared
+/// with the same range of the file on disk. Only bytes that are `0xCC` in memory
+/// but not on disk are reported, so `0xCC` bytes the compiler emitted
+/// (padding, immediates and data embedded in code) are never flagged.
+///
+/// Mappings that aren't readable, or whose file range can't be read, are skipped
+/// with a warning through the `log` feature.
+#[cfg(all(feature = "memory-integrity", any(target_arch = "x86", target_arch = "x86_64")))]
+pub(crate) fn executable_has_int3_patches() -> Result<bool, crate::DetectError> {
+    use std::os::unix::fs::FileExt;
+
+    let path = executable_path()?;
+    let file = std::fs::File::open(&path).map_err(crate::DetectError::ExecutableUnreadable)?;
+    let maps = read_maps()?;
+    for mapping in parse_executable_mappings(&maps, &path) {
+        if !mapping.readable {
+            crate::log_warn!("skipping unreadable mapping {:#x}-{:#x}", mapping.start, mapping.end);
+            continue;
+        }
+        let mut disk = vec![0; mapping.end - mapping.start];
+        let mut length = 0;
+        // The last page of a mapping may extend past the end of the file.
+        while length < disk.len() {
+            match file.read_at(&mut disk[length..], mapping.offset + length as u64) {
+                Ok(0) => break,
+                Ok(read) => length += read,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    crate::log_warn!("skipping mapping {:#x}-{:#x}: {e}", mapping.start, mapping.end);
+                    length = 0;
+                    break;
+                }
+            }
+        }
+        let memory = unsafe { core::slice::from_raw_parts(mapping.start as *const u8, length) };
+        if memory.iter().zip(&disk).any(|(&memory, &disk)| memory == 0xCC && disk != 0xCC) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_read_status() {
+        assert!(super::read_status().unwrap().contains("TracerPid:"));
+    }
+
+    #[test]
+    #[cfg(feature = "raw-syscall")]
+    fn test_read_status_raw_syscall() {
+        let status = super::read_status().unwrap();
+        let expected = std::fs::read_to_string("/proc/self/status").unwrap();
+        assert_eq!(super::parse_tracer_pid(&status).unwrap(), super::parse_tracer_pid(&expected).unwrap());
+        assert_eq!(status.lines().next(), expected.lines().next());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_wchan() {
+        // The main thread of the test harness waits for the tests, but is not traced.
+        assert!(!super::wchan().unwrap().contains("ptrace"));
+    }
+
+    #[test]
+    #[cfg(all(feature = "deep-detect", feature = "std"))]
+    fn test_is_wchan_ptrace_related() {
+        assert!(!super::is_wchan_ptrace_related().unwrap());
+    }
+
+    #[test]
+    fn test_tracer_pid_lenient() {
+        assert_eq!(super::tracer_pid_lenient().unwrap(), super::tracer_pid().unwrap());
+    }
+
+    #[test]
+    fn test_prctl() {
+        assert!(matches!(super::prctl(libc::PR_GET_DUMPABLE, 0), Ok(0 | 1)));
+        assert_eq!(super::prctl(-1, 0), Err(libc::EINVAL));
+    }
+
+    #[test]
+    #[cfg(target_os = "android")]
+    fn test_is_android_debug_build() {
+        let _ = super::is_android_debug_build();
+    }
+
+    #[test]
+    fn test_sigtrap_detected() {
+        assert!(!super::sigtrap_detected().unwrap());
+        assert!(!super::sigtrap_detected().unwrap());
+    }
+
+    #[test]
+    fn test_sigtrap_detected_blocked() {
+        // Runs on a thread of its own, as the signal mask is per thread.
+        std::thread::spawn(|| unsafe {
+            let mut mask = core::mem::zeroed::<libc::sigset_t>();
+            libc::sigemptyset(&mut mask);
+            libc::sigaddset(&mut mask, libc::SIGTRAP);
+            assert_eq!(libc::pthread_sigmask(libc::SIG_BLOCK, &mask, core::ptr::null_mut()), 0);
+            assert!(!super::sigtrap_detected().unwrap());
+            let mut current = core::mem::zeroed::<libc::sigset_t>();
+            libc::pthread_sigmask(libc::SIG_BLOCK, core::ptr::null(), &mut current);
+            assert_eq!(libc::sigismember(&current, libc::SIGTRAP), 1);
+        }).join().unwrap();
+    }
+
+    #[test]
+    fn test_parse_tracer_pid() {
+        assert_eq!(super::parse_tracer_pid("Name:\tcat\nTracerPid:\t0\n").unwrap(), 0);
+        assert_eq!(super::parse_tracer_pid("TracerPid:\t1234\nUid:\t0\n").unwrap(), 1234);
+        assert!(matches!(super::parse_tracer_pid("Name:\tcat\n"), Err(crate::DetectError::InvalidTracerPid(raw)) if raw.is_empty()));
+        assert!(matches!(super::parse_tracer_pid("TracerPid:\tabc\n"), Err(crate::DetectError::InvalidTracerPid(raw)) if raw == "abc"));
+    }
+
+    #[test]
+    fn test_tracer_pid() {
+        assert_eq!(super::tracer_pid().unwrap(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_parse_suspicious_mappings() {
+        let maps = "\
+55d0c0a00000-55d0c0a02000 r--p 00000000 08:01 1234                       /usr/bin/cat
+7f1c2a000000-7f1c2a200000 r-xp 00000000 08:01 5678                       /tmp/frida-agent-64.so
+7f1c2a200000-7f1c2a300000 r--p 00200000 08:01 5678                       /tmp/frida-agent-64.so
+7f1c2b000000-7f1c2b001000 r-xp 00000000 08:01 9012                       /data/local/tmp/libHook.so
+7ffd4e5f0000-7ffd4e611000 rw-p 00000000 00:00 0                          [stack]
+7ffd4e700000-7ffd4e701000 rw-p 00000000 00:00 0
+";
+        assert_eq!(super::parse_suspicious_mappings(maps), ["/tmp/frida-agent-64.so", "/data/local/tmp/libHook.so"]);
+    }
+
+    #[test]
+    #[cfg(all(target_os = "android", feature = "std"))]
+    fn test_android_instrumentation_detected() {
+        assert!(!super::android_instrumentation_detected().unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_suspicious_mappings() {
+        assert!(super::suspicious_mappings().unwrap().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_parse_ppid() {
+        assert_eq!(super::parse_ppid("1234 (cat) R 1000 1234 1000 0 -1"), Some(1000));
+        assert_eq!(super::parse_ppid("1234 (a) b (c)) S 42 1234 1000 0 -1"), Some(42));
+        assert_eq!(super::parse_ppid("1234 (cat"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_parent_process_name() {
+        assert!(super::parent_process_name().unwrap().is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "deep-detect")]
+    fn test_parse_hardware_breakpoints() {
+        assert!(!super::parse_hardware_breakpoints("Name:\tcat\nTracerPid:\t0\n"));
+        assert!(!super::parse_hardware_breakpoints("HWBreakpoint:\t0\n"));
+        assert!(super::parse_hardware_breakpoints("HWBreakpoint:\t2\n"));
+    }
+
+    #[test]
+    #[cfg(feature = "deep-detect")]
+    fn test_has_hardware_breakpoints() {
+        assert!(!super::has_hardware_breakpoints().unwrap());
+    }
+
+    #[test]
+    #[cfg(all(feature = "deep-detect", feature = "std"))]
+    fn test_self_ptrace_detected() {
+        assert!(!super::self_ptrace_detected().unwrap());
+        assert!(!super::self_ptrace_detected().unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_parse_preload_list() {
+        assert_eq!(super::parse_preload_list("/a.so:/b.so /c.so\n\n").collect::<Vec<_>>(), ["/a.so", "/b.so", "/c.so"]);
+        assert_eq!(super::parse_preload_list("").count(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_unneeded_objects() {
+        use super::LoadedObject;
+
+        let object = |path: &str, soname: Option<&str>, needed: &[&str], start: usize| LoadedObject {
+            path: path.into(),
+            soname: soname.map(Into::into),
+            needed: needed.iter().map(|&needed| needed.into()).collect(),
+            interpreter: None,
+            segments: core::iter::once(start..start + 0x1000).collect(),
+        };
+        let mut executable = object("", None, &["libc.so.6"], 0x1000);
+        executable.interpreter = Some("/lib64/ld-linux-x86-64.so.2".into());
+        let objects = [
+            executable,
+            object("linux-vdso.so.1", Some("linux-vdso.so.1"), &[], 0x2000),
+            object("/tmp/libhook.so", None, &["libc.so.6"], 0x3000),
+            object("/lib/libc.so.6", Some("libc.so.6"), &[], 0x4000),
+            object("/lib64/ld-linux-x86-64.so.2", None, &[], 0x5000),
+            object("/lib/libplugin.so", None, &[], 0x6000),
+        ];
+        assert_eq!(super::unneeded_objects(&objects, 0x2000).collect::<Vec<_>>(), ["/tmp/libhook.so"]);
+        assert_eq!(super::unneeded_objects(&[], 0).count(), 0);
+
+        let maps = "\
+00001000-00002000 r-xp 00000000 08:01 1                          /usr/bin/app
+00003000-00004000 r-xp 00000000 08:01 2                          /tmp/libhook.so
+00007000-00008000 r-xp 00000000 00:01 3                          /memfd:agent.so (deleted)
+00008000-00009000 r--p 00000000 08:01 4                          /tmp/libdata.so
+00009000-0000a000 r-xp 00000000 00:00 0                          [vdso]
+";
+        assert_eq!(super::unlinked_mappings(maps, &objects), ["/memfd:agent.so (deleted)"]);
+    }
+
+    #[test]
+    #[cfg(feature = "memory-integrity")]
+    fn test_parse_executable_mappings() {
+        let maps = "\
+55d00000-55d02000 r--p 00000000 08:01 1234                           /usr/bin/cat
+55d02000-55d07000 r-xp 00002000 08:01 1234                           /usr/bin/cat
+55d07000-55d08000 --xp 00007000 08:01 1234                           /usr/bin/cat
+7f1c0000-7f1d0000 r-xp 00028000 08:01 5678                           /usr/lib/libc.so.6
+7ffd0000-7ffe0000 rw-p 00000000 00:00 0                              [stack]
+";
+        assert_eq!(super::parse_executable_mappings(maps, std::path::Path::new("/usr/bin/cat")), [
+            super::ExecutableMapping { start: 0x55d02000, end: 0x55d07000, readable: true, offset: 0x2000 },
+            super::ExecutableMapping { start: 0x55d07000, end: 0x55d08000, readable: false, offset: 0x7000 },
+        ]);
+    }
+
+    #[test]
+    #[cfg(all(feature = "memory-integrity", any(target_arch = "x86", target_arch = "x86_64")))]
+    fn test_executable_has_int3_patches() {
+        assert!(!super::executable_has_int3_patches().unwrap());
+    }
+}
@@ -0,0 +1,277 @@
+//! macOS-specific detection checks.
+//!
+//! The checks use independent APIs, so they can cross-check each other
+//! when one of them is hooked.
+
+/// `P_TRACED`: the process is traced by a debugger.
+const P_TRACED: u32 = 0x800;
+
+/// Checks with `proc_pidinfo(PROC_PIDTBSDINFO)`, which reports `PROC_FLAG_TRACED` in `pbi_flags`
+/// while the current process is traced.
+///
+/// Uses `libproc` with the `macos-libproc` feature, and `libc` without it.
+pub fn check_proc_pidinfo_traced() -> Result<bool, crate::DetectError> {
+    const PROC_FLAG_TRACED: u32 = 2; // use libproc::osx_libproc_bindings::PROC_FLAG_TRACED;
+
+    #[cfg(feature = "macos-libproc")]
+    {
+        let pid = std::process::id() as i32;
+        let result = libproc::proc_pid::pidinfo::<libproc::bsd_info::BSDInfo>(pid, 0);
+        let proc_bsdinfo = match result {
+            Ok(proc_bsdinfo) => proc_bsdinfo,
+            Err(message) => return Err(crate::DetectError::ProcPidInfoFailed(message)),
+        };
+        Ok(proc_bsdinfo.pbi_flags & PROC_FLAG_TRACED != 0)
+    }
+    #[cfg(not(feature = "macos-libproc"))]
+    unsafe {
+        let mut proc_bsdinfo = core::mem::zeroed::<libc::proc_bsdinfo>();
+        let size = size_of::<libc::proc_bsdinfo>() as libc::c_int;
+        let result = libc::proc_pidinfo(libc::getpid(), libc::PROC_PIDTBSDINFO, 0, &mut proc_bsdinfo as *mut _ as _, size);
+        if result != size {
+            #[cfg(feature = "std")]
+            let message = std::io::Error::last_os_error().to_string();
+            #[cfg(not(feature = "std"))]
+            let message = alloc::format!("errno = {}", crate::error::raw_os_error());
+            return Err(crate::DetectError::ProcPidInfoFailed(message));
+        }
+        Ok(proc_bsdinfo.pbi_flags & PROC_FLAG_TRACED != 0)
+    }
+}
+
+/// Checks with `sysctl(CTL_KERN, KERN_PROC, KERN_PROC_PID)`, which reports `P_TRACED`
+/// in the `p_flag` field of `kp_proc` while the current process is traced.
+///
+/// This doesn't depend on `libproc`, so it works as a second opinion on `proc_pidinfo`.
+pub fn check_sysctl_traced() -> Result<bool, crate::DetectError> {
+    /// The size of `struct kinfo_proc` on 64-bit macOS.
+    const KINFO_PROC_SIZE: usize = 648;
+    /// The offset of `kp_proc.p_flag` in `struct kinfo_proc`.
+    const P_FLAG_OFFSET: usize = 32;
+
+    unsafe {
+        let mut mib = [libc::CTL_KERN, libc::KERN_PROC, libc::KERN_PROC_PID, libc::getpid()];
+        let mut kinfo_proc = [0u64; KINFO_PROC_SIZE / 8];
+        let mut size = KINFO_PROC_SIZE;
+        let result = libc::sysctl(mib.as_mut_ptr(), mib.len() as _, kinfo_proc.as_mut_ptr() as _, &mut size, core::ptr::null_mut(), 0);
+        if result == -1 {
+            return Err(crate::error::last_os_error!(SysctlFailed));
+        }
+        if size != KINFO_PROC_SIZE {
+            // The process doesn't exist or the layout is unexpected.
+            return Err(crate::DetectError::OsError(libc::ESRCH));
+        }
+        let p_flag = kinfo_proc.as_ptr().cast::<u8>().add(P_FLAG_OFFSET).cast::<i32>().read();
+        Ok(p_flag as u32 & P_TRACED != 0)
+    }
+}
+
+/// Returns the name of the parent process, such as `lldb`.
+///
+/// Uses `libproc` with the `macos-libproc` feature, and `proc_name` of `libc` without it.
+#[cfg(feature = "std")]
+pub fn parent_process_name() -> Result<Option<String>, crate::DetectError> {
+    let ppid = unsafe { libc::getppid() };
+    #[cfg(feature = "macos-libproc")] {
+        libproc::proc_pid::name(ppid)
+            .map(Some)
+            .map_err(|message| crate::DetectError::ParentProcessQueryFailed(std::io::Error::other(message)))
+    }
+    #[cfg(not(feature = "macos-libproc"))] {
+        /// The size of the buffer of `proc_name`, i.e. `2 * MAXCOMLEN + 1`.
+        const NAME_SIZE: usize = 33;
+
+        let mut buffer = [0u8; NAME_SIZE];
+        let length = unsafe { libc::proc_name(ppid, buffer.as_mut_ptr() as _, NAME_SIZE as u32) };
+        if length <= 0 {
+            let error = std::io::Error::last_os_error();
+            if error.raw_os_error() == Some(libc::ESRCH) {
+                return Ok(None);
+            }
+            return Err(crate::DetectError::ParentProcessQueryFailed(error));
+        }
+        Ok(Some(String::from_utf8_lossy(&buffer[..length as usize]).into_owned()))
+    }
+}
+
+/// Checks if the current process is already traced with `ptrace(PT_ATTACHEXC)` from a forked helper.
+///
+/// A process can be traced by only one tracer at a time, so the attach fails with `EBUSY`
+/// while a debugger holds the slot. When the attach succeeds, the helper detaches again and
+/// sends `SIGCONT` to discard the `SIGSTOP` queued by the attach.
+/// An attach denied for lack of privileges fails with `EPERM`, in which case no debugger is reported.
+///
+/// Requires the `std` and `deep-detect` features.
+#[cfg(all(feature = "deep-detect", feature = "std"))]
+pub fn self_ptrace_detected() -> Result<bool, crate::DetectError> {
+    const EXIT_ATTACHED: libc::c_int = 0;
+    const EXIT_SLOT_TAKEN: libc::c_int = 1;
+    const EXIT_PTRACE_DENIED: libc::c_int = 2;
+    const EXIT_FAILED: libc::c_int = 3;
+
+    unsafe {
+        let parent = libc::getpid();
+        let child = libc::fork();
+        if child == 0 {
+            // Only async-signal-safe calls are allowed in the forked child.
+            if libc::ptrace(libc::PT_ATTACHEXC, parent, core::ptr::null_mut(), 0) == 0 {
+                // An address of 1 resumes the process where it stopped.
+                libc::ptrace(libc::PT_DETACH, parent, core::ptr::without_provenance_mut(1), 0);
+                libc::kill(parent, libc::SIGCONT);
+                libc::_exit(EXIT_ATTACHED);
+            }
+            libc::_exit(match crate::error::raw_os_error() {
+                libc::EBUSY => EXIT_SLOT_TAKEN,
+                libc::EPERM => EXIT_PTRACE_DENIED,
+                _ => EXIT_FAILED,
+            });
+        }
+        if child == -1 {
+            return Err(crate::error::last_os_error!(PtraceFailed));
+        }
+        let mut status = 0;
+        let result = loop {
+            let result = libc::waitpid(child, &mut status, 0);
+            if result != -1 || crate::error::raw_os_error() != libc::EINTR {
+                break result;
+            }
+        };
+        if result == -1 {
+            return Err(crate::error::last_os_error!(PtraceFailed));
+        }
+        if !libc::WIFEXITED(status) {
+            return Err(crate::DetectError::PtraceFailed(std::io::Error::other("ptrace helper terminated abnormally")));
+        }
+        match libc::WEXITSTATUS(status) {
+            EXIT_ATTACHED | EXIT_PTRACE_DENIED => Ok(false),
+            EXIT_SLOT_TAKEN => Ok(true),
+            _ => Err(crate::DetectError::PtraceFailed(std::io::Error::other("ptrace helper failed"))),
+        }
+    }
+}
+
+/// Returns the libraries inserted with `DYLD_INSERT_LIBRARIES` and the loaded images
+/// outside the app bundle and the system paths,
+/// see [`preload_injection_present`](crate::preload_injection_present).
+#[cfg(feature = "std")]
+#[allow(deprecated)]
+pub(crate) fn injected_libraries() -> Vec<crate::InjectedLibrary> {
+    use crate::{InjectedLibrary, InjectionSource};
+
+    let mut found = Vec::new();
+    if let Some(value) = std::env::var_os("DYLD_INSERT_LIBRARIES") {
+        let value = value.to_string_lossy();
+        let paths = value.split(':').filter(|path| !path.is_empty());
+        found.extend(paths.map(|path| InjectedLibrary::new(path, InjectionSource::DyldInsertLibraries)));
+    }
+    let image = |index| {
+        let name = unsafe { libc::_dyld_get_image_name(index) };
+        (!name.is_null()).then(|| unsafe { core::ffi::CStr::from_ptr(name) }.to_string_lossy().into_owned())
+    };
+    let images = (0..unsafe { libc::_dyld_image_count() }).filter_map(image).collect::<Vec<_>>();
+    if let Some((executable, libraries)) = images.split_first() {
+        let root = bundle_root(executable);
+        let foreign = libraries.iter().filter(|path| is_foreign_image(path, root));
+        found.extend(foreign.map(|path| InjectedLibrary::new(path.as_str(), InjectionSource::ForeignImage)));
+    }
+    found
+}
+
+/// Returns the directory of the app bundle containing `executable`,
+/// or the directory of `executable` if it isn't in a bundle, including the trailing slash.
+#[cfg(feature = "std")]
+fn bundle_root(executable: &str) -> &str {
+    let bundle = executable.match_indices(".app/").next().map(|(index, _)| index + ".app/".len());
+    let directory = executable.rfind('/').map(|index| index + 1);
+    &executable[..bundle.or(directory).unwrap_or_default()]
+}
+
+/// Checks if the image at `path` is loaded from outside the system paths and `root`.
+#[cfg(feature = "std")]
+fn is_foreign_image(path: &str, root: &str) -> bool {
+    const SYSTEM_PATHS: &[&str] = &["/usr/lib/", "/System/", "/Library/Apple/"];
+    let in_bundle = !root.is_empty() && path.starts_with(root);
+    !in_bundle && !SYSTEM_PATHS.iter().any(|prefix| path.starts_with(prefix))
+}
+
+/// Returns the address of the Mach-O header of the main executable
+/// and the address and size of its `__TEXT,__text` section.
+///
+/// The load commands are read from the image loaded by `dyld`, and the section address is slid by ASLR.
+#[cfg(feature = "memory-integrity")]
+#[allow(deprecated)]
+pub(crate) fn executable_code_regions() -> Result<(usize, Vec<(usize, usize)>), crate::DetectError> {
+    const MH_MAGIC_64: u32 = 0xFEEDFACF;
+    const MACH_HEADER_64_SIZE: usize = 32;
+    const LC_SEGMENT_64: u32 = 0x19;
+    const SEGMENT_COMMAND_64_SIZE: usize = 72;
+    const SECTION_64_SIZE: usize = 80;
+
+    unsafe {
+        let header = libc::_dyld_get_image_header(0) as *const u8;
+        if header.is_null() || header.cast::<u32>().read_unaligned() != MH_MAGIC_64 {
+            return Err(crate::DetectError::Unsupported);
+        }
+        let slide = libc::_dyld_get_image_vmaddr_slide(0) as usize;
+        let read_u32 = |offset: usize| header.add(offset).cast::<u32>().read_unaligned();
+        let read_u64 = |offset: usize| header.add(offset).cast::<u64>().read_unaligned();
+        let name = |offset: usize| core::slice::from_raw_parts(header.add(offset), 16);
+        let matches = |offset: usize, expected: &[u8]| name(offset).split(|&byte| byte == 0).next() == Some(expected);
+
+        let mut command = MACH_HEADER_64_SIZE;
+        for _ in 0..read_u32(16) {
+            if read_u32(command) == LC_SEGMENT_64 && matches(command + 8, b"__TEXT") {
+                for index in 0..read_u32(command + 64) as usize {
+                    let section = command + SEGMENT_COMMAND_64_SIZE + index * SECTION_64_SIZE;
+                    if matches(section, b"__text") {
+                        let address = (read_u64(section + 32) as usize).wrapping_add(slide);
+                        return Ok((header as usize, vec![(address, read_u64(section + 40) as usize)]));
+                    }
+                }
+            }
+            command += read_u32(command + 4) as usize;
+        }
+        Ok((header as usize, Vec::new()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_is_foreign_image() {
+        let root = super::bundle_root("/Applications/App.app/Contents/MacOS/App");
+        assert_eq!(root, "/Applications/App.app/");
+        assert!(!super::is_foreign_image("/Applications/App.app/Contents/Frameworks/libapp.dylib", root));
+        assert!(!super::is_foreign_image("/usr/lib/libSystem.B.dylib", root));
+        assert!(super::is_foreign_image("/tmp/libhook.dylib", root));
+        assert_eq!(super::bundle_root("/usr/local/bin/tool"), "/usr/local/bin/");
+    }
+
+    #[test]
+    fn test_check_proc_pidinfo_traced() {
+        assert!(!super::check_proc_pidinfo_traced().unwrap());
+    }
+
+    #[test]
+    fn test_check_sysctl_traced() {
+        assert!(!super::check_sysctl_traced().unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_parent_process_name() {
+        assert!(super::parent_process_name().is_ok());
+    }
+
+    #[test]
+    #[cfg(all(feature = "deep-detect", feature = "std"))]
+    fn test_self_ptrace_detected() {
+        assert!(!super::self_ptrace_detected().unwrap());
+    }
+
+    #[test]
+    fn test_sysctl_fallback_agrees() {
+        assert_eq!(super::check_sysctl_traced().unwrap(), super::check_proc_pidinfo_traced().unwrap());
+    }
+}
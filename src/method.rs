@@ -0,0 +1,674 @@
+//! Running individual detection methods.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{DetectError, DetectionReason};
+
+/// A single detection method, which can be run with [`detect_with`].
+///
+/// Unlike [`DetectionConfig`](crate::DetectionConfig), which enables checks by platform,
+/// this selects the exact methods and their order.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum DetectionMethod {
+    /// `IsDebuggerPresent`. (Windows)
+    IsDebuggerPresent,
+    /// `CheckRemoteDebuggerPresent`. (Windows)
+    RemoteDebuggerPresent,
+    /// `NtQueryInformationProcess(ProcessDebugPort)`. (Windows)
+    DebugPort,
+    /// `NtQueryInformationProcess(ProcessDebugObjectHandle)`. (Windows)
+    DebugObjectHandle,
+    /// `NtQueryInformationProcess(ProcessDebugFlags)`. (Windows)
+    DebugFlags,
+    /// The `NtGlobalFlag` field of the PEB. Requires `deep-detect`. (Windows)
+    NtGlobalFlag,
+    /// The flags of the process heap. Requires `deep-detect`. (Windows)
+    HeapFlags,
+    /// [`hardware_breakpoints_present`](crate::hardware_breakpoints_present). Requires `deep-detect`. (Windows/Linux/Android)
+    HardwareBreakpoints,
+    /// [`kernel_debugger_present`](crate::kernel_debugger_present). (Windows)
+    KernelDebugger,
+    /// The `TracerPid` field in `/proc/self/status`. (Linux/Android)
+    TracerPid,
+    /// The tracer slot with `ptrace`. Requires `deep-detect` and `std`. (Linux/Android/macOS)
+    PtraceSlot,
+    /// The `wchan` of the threads in `/proc/self/task`. Requires `deep-detect` and `std`. (Linux/Android)
+    Wchan,
+    /// The `ro.debuggable` system property. (Android)
+    DebuggableBuild,
+    /// The `pbi_flags` field of `proc_bsdinfo`. (macOS)
+    ProcBsdInfoFlags,
+    /// The `P_TRACED` flag of `kinfo_proc` with `sysctl`. (macOS/iOS/FreeBSD/NetBSD/OpenBSD)
+    SysctlTraced,
+}
+
+impl DetectionMethod {
+    /// All methods, including the ones unsupported on the current platform.
+    pub const ALL: &'static [DetectionMethod] = &[
+        DetectionMethod::IsDebuggerPresent,
+        DetectionMethod::RemoteDebuggerPresent,
+        DetectionMethod::DebugPort,
+        DetectionMethod::DebugObjectHandle,
+        DetectionMethod::DebugFlags,
+        DetectionMethod::NtGlobalFlag,
+        DetectionMethod::HeapFlags,
+        DetectionMethod::HardwareBreakpoints,
+        DetectionMethod::KernelDebugger,
+        DetectionMethod::TracerPid,
+        DetectionMethod::PtraceSlot,
+        DetectionMethod::Wchan,
+        DetectionMethod::DebuggableBuild,
+        DetectionMethod::ProcBsdInfoFlags,
+        DetectionMethod::SysctlTraced,
+    ];
+
+    /// Returns `true` if the method is compiled in for the current platform and features.
+    pub const fn is_supported(self) -> bool {
+        match self {
+            DetectionMethod::IsDebuggerPresent |
+            DetectionMethod::RemoteDebuggerPresent |
+            DetectionMethod::DebugPort |
+            DetectionMethod::DebugObjectHandle |
+            DetectionMethod::DebugFlags |
+            DetectionMethod::KernelDebugger => cfg!(target_os = "windows"),
+            DetectionMethod::NtGlobalFlag |
+            DetectionMethod::HeapFlags => cfg!(all(target_os = "windows", feature = "deep-detect")),
+            DetectionMethod::HardwareBreakpoints => cfg!(all(any(target_os = "windows", target_os = "linux", target_os = "android"), feature = "deep-detect")),
+            DetectionMethod::TracerPid => cfg!(any(target_os = "linux", target_os = "android")),
+            DetectionMethod::PtraceSlot => cfg!(all(any(target_os = "linux", target_os = "android", target_os = "macos"), feature = "deep-detect", feature = "std")),
+            DetectionMethod::Wchan => cfg!(all(any(target_os = "linux", target_os = "android"), feature = "deep-detect", feature = "std")),
+            DetectionMethod::DebuggableBuild => cfg!(target_os = "android"),
+            DetectionMethod::ProcBsdInfoFlags => cfg!(target_os = "macos"),
+            DetectionMethod::SysctlTraced => cfg!(any(
+                target_os = "macos",
+                all(target_os = "ios", feature = "ios"),
+                target_os = "freebsd",
+                target_os = "netbsd",
+                target_os = "openbsd",
+            )),
+        }
+    }
+
+    /// Returns the name of the method, which is the name of the variant, such as `TracerPid`.
+    pub fn name(self) -> &'static str {
+        match self {
+            DetectionMethod::IsDebuggerPresent => "IsDebuggerPresent",
+            DetectionMethod::RemoteDebuggerPresent => "RemoteDebuggerPresent",
+            DetectionMethod::DebugPort => "DebugPort",
+            DetectionMethod::DebugObjectHandle => "DebugObjectHandle",
+            DetectionMethod::DebugFlags => "DebugFlags",
+            DetectionMethod::NtGlobalFlag => "NtGlobalFlag",
+            DetectionMethod::HeapFlags => "HeapFlags",
+            DetectionMethod::HardwareBreakpoints => "HardwareBreakpoints",
+            DetectionMethod::KernelDebugger => "KernelDebugger",
+            DetectionMethod::TracerPid => crate::obfuscate::obfuscated_str!("TracerPid"),
+            DetectionMethod::PtraceSlot => "PtraceSlot",
+            DetectionMethod::Wchan => "Wchan",
+            DetectionMethod::DebuggableBuild => "DebuggableBuild",
+            DetectionMethod::ProcBsdInfoFlags => "ProcBsdInfoFlags",
+            DetectionMethod::SysctlTraced => "SysctlTraced",
+        }
+    }
+
+    /// Returns the default weight of the method for [`DetectionReport::score`], from 0.0 to 1.0.
+    ///
+    /// Methods observing the debugger directly weigh 1.0. Lower weights are given to methods
+    /// which also trigger without an attached debugger, such as `NtGlobalFlag` set by `gflags`,
+    /// a kernel debugger of the whole system, or a debuggable build of Android.
+    pub const fn weight(self) -> f64 {
+        match self {
+            DetectionMethod::IsDebuggerPresent |
+            DetectionMethod::RemoteDebuggerPresent |
+            DetectionMethod::DebugPort |
+            DetectionMethod::DebugObjectHandle |
+            DetectionMethod::DebugFlags |
+            DetectionMethod::TracerPid |
+            DetectionMethod::Wchan |
+            DetectionMethod::ProcBsdInfoFlags |
+            DetectionMethod::SysctlTraced => 1.0,
+            DetectionMethod::PtraceSlot => 0.9,
+            DetectionMethod::HardwareBreakpoints => 0.8,
+            DetectionMethod::NtGlobalFlag |
+            DetectionMethod::HeapFlags => 0.6,
+            DetectionMethod::KernelDebugger => 0.5,
+            DetectionMethod::DebuggableBuild => 0.3,
+        }
+    }
+
+    /// Runs the method.
+    ///
+    /// # Return Value
+    ///
+    /// Returns `Ok(Some(reason))` if a debugger is detected, `Ok(None)` if no debugger is present,
+    /// `Err(DetectError::Unsupported)` if the method is not [supported](Self::is_supported),
+    /// or `Err(DetectError)` if the check could not be performed.
+    /// With the `disarm` feature, or the development override of `init`, a supported method always returns `Ok(None)`.
+    pub fn run(self) -> Result<Option<DetectionReason>, DetectError> {
+        if crate::disarm::disarmed() {
+            return if self.is_supported() { Ok(None) } else { Err(DetectError::Unsupported) };
+        }
+        match self {
+            DetectionMethod::IsDebuggerPresent => {
+                #[cfg(target_os = "windows")]
+                return Ok(crate::windows::check_is_debugger_present().then_some(DetectionReason::IsDebuggerPresent));
+            }
+            DetectionMethod::RemoteDebuggerPresent => {
+                #[cfg(target_os = "windows")]
+                return Ok(crate::windows::check_remote_debugger_present()?.then_some(DetectionReason::RemoteDebugger));
+            }
+            DetectionMethod::DebugPort => {
+                #[cfg(target_os = "windows")]
+                return Ok(crate::windows::check_debug_port()?.then_some(DetectionReason::DebugPort));
+            }
+            DetectionMethod::DebugObjectHandle => {
+                #[cfg(target_os = "windows")]
+                return Ok(crate::windows::check_debug_object_handle()?.then_some(DetectionReason::DebugObjectHandle));
+            }
+            DetectionMethod::DebugFlags => {
+                #[cfg(target_os = "windows")]
+                return Ok(crate::windows::check_debug_flags()?.then_some(DetectionReason::DebugFlags));
+            }
+            DetectionMethod::NtGlobalFlag => {
+                #[cfg(all(target_os = "windows", feature = "deep-detect"))]
+                return Ok(crate::windows::nt_global_flag_debugged()?.then_some(DetectionReason::NtGlobalFlag));
+            }
+            DetectionMethod::HeapFlags => {
+                #[cfg(all(target_os = "windows", feature = "deep-detect"))]
+                return Ok(crate::windows::heap_flags_debugged()?.then_some(DetectionReason::HeapFlags));
+            }
+            DetectionMethod::HardwareBreakpoints => {
+                #[cfg(feature = "deep-detect")]
+                return Ok(crate::hardware_breakpoints_present()?.then_some(DetectionReason::HardwareBreakpoints));
+            }
+            DetectionMethod::KernelDebugger => {
+                return Ok(crate::kernel_debugger_present()?.then_some(DetectionReason::KernelDebugger));
+            }
+            DetectionMethod::TracerPid => {
+                #[cfg(any(target_os = "linux", target_os = "android"))] {
+                    let pid = crate::linux::parse_tracer_pid(&crate::linux::read_status()?)?;
+                    return Ok((pid != 0).then_some(DetectionReason::TracerPid(pid)));
+                }
+            }
+            DetectionMethod::PtraceSlot => {
+                #[cfg(all(any(target_os = "linux", target_os = "android"), feature = "deep-detect", feature = "std"))]
+                return Ok(crate::linux::self_ptrace_detected()?.then_some(DetectionReason::PtraceSlot));
+                #[cfg(all(target_os = "macos", feature = "deep-detect", feature = "std"))]
+                return Ok(crate::macos::self_ptrace_detected()?.then_some(DetectionReason::PtraceSlot));
+            }
+            DetectionMethod::Wchan => {
+                #[cfg(all(any(target_os = "linux", target_os = "android"), feature = "deep-detect", feature = "std"))]
+                return Ok(crate::linux::is_wchan_ptrace_related()?.then_some(DetectionReason::PtraceStop));
+            }
+            DetectionMethod::DebuggableBuild => {
+                #[cfg(target_os = "android")]
+                return Ok(crate::linux::is_android_debug_build().then_some(DetectionReason::DebuggableBuild));
+            }
+            DetectionMethod::ProcBsdInfoFlags => {
+                #[cfg(target_os = "macos")]
+                return Ok(crate::macos::check_proc_pidinfo_traced()?.then_some(DetectionReason::ProcTraced));
+            }
+            DetectionMethod::SysctlTraced => {
+                #[cfg(target_os = "macos")]
+                use crate::macos as platform;
+                #[cfg(all(target_os = "ios", feature = "ios"))]
+                use crate::ios as platform;
+                #[cfg(target_os = "freebsd")]
+                use crate::freebsd as platform;
+                #[cfg(target_os = "netbsd")]
+                use crate::netbsd as platform;
+                #[cfg(target_os = "openbsd")]
+                use crate::openbsd as platform;
+                #[cfg(any(
+                    target_os = "macos",
+                    all(target_os = "ios", feature = "ios"),
+                    target_os = "freebsd",
+                    target_os = "netbsd",
+                    target_os = "openbsd",
+                ))]
+                return Ok(platform::check_sysctl_traced()?.then_some(DetectionReason::SysctlTraced));
+            }
+        }
+        #[allow(unreachable_code)]
+        Err(DetectError::Unsupported)
+    }
+}
+
+// Written by hand with `name`, so the obfuscated names don't appear in the binary.
+impl core::fmt::Debug for DetectionMethod {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Returns the methods [supported](DetectionMethod::is_supported) on the current platform and features,
+/// in the order of [`DetectionMethod::ALL`].
+///
+/// Use this to build portable method lists for [`detect_with`].
+pub fn supported_methods() -> Vec<DetectionMethod> {
+    DetectionMethod::ALL.iter().copied().filter(|method| method.is_supported()).collect()
+}
+
+/// Checks if a debugger is attached with exactly the given `methods`, in order.
+///
+/// Returns on the first method that detects a debugger or fails. An unsupported method fails with
+/// `Err(DetectError::Unsupported)` instead of being skipped silently, so filter the methods with
+/// [`DetectionMethod::is_supported`] or [`supported_methods`] for portable lists.
+///
+/// # Examples
+///
+/// ```rust
+/// # fn main() {
+/// use anti_debug::DetectionMethod;
+///
+/// // Skip `CheckRemoteDebuggerPresent`, which may be hooked.
+/// let methods: Vec<_> = anti_debug::supported_methods().into_iter()
+///     .filter(|&method| method != DetectionMethod::RemoteDebuggerPresent)
+///     .collect();
+/// match anti_debug::detect_with(&methods) {
+///     Ok(true) => println!("Debugger detected!"),
+///     Ok(false) => println!("No debugger present"),
+///     Err(e) => println!("Error checking for debugger: {}", e),
+/// }
+/// # }
+/// ```
+pub fn detect_with(methods: &[DetectionMethod]) -> Result<bool, DetectError> {
+    for method in methods {
+        if method.run()?.is_some() {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// The results of all methods run by [`run_all_checks`] or [`run_all_checks_with`].
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct DetectionReport {
+    /// The result of each method, in the order they were run.
+    pub results: Vec<(DetectionMethod, Result<Option<DetectionReason>, DetectError>)>,
+    /// The result of each custom [`DetectionProbe`], in the order they were run.
+    pub probes: Vec<ProbeResult>,
+}
+
+/// How a check that could not be performed is counted.
+///
+/// A sandbox or a hooked API can make checks fail, such as by denying to read `/proc/self/status`.
+/// Threat models assuming hostility unless verified should use [`FailClosed`](Self::FailClosed).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ErrorPolicy {
+    /// Failed checks are ignored, only detections count. This is the default.
+    #[default]
+    FailOpen,
+    /// Failed checks count as detections.
+    FailClosed,
+}
+
+impl ErrorPolicy {
+    /// Returns the result of a check, or whether a failed check counts as a detection.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use anti_debug::ErrorPolicy;
+    ///
+    /// # fn main() {
+    /// if ErrorPolicy::FailClosed.apply(anti_debug::detect_with(&anti_debug::supported_methods())) {
+    ///     println!("Debugger detected or unverifiable!");
+    /// }
+    /// # }
+    /// ```
+    pub fn apply<E>(self, result: Result<bool, E>) -> bool {
+        result.unwrap_or(self == ErrorPolicy::FailClosed)
+    }
+}
+
+/// The result of a custom [`DetectionProbe`] in a [`DetectionReport`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct ProbeResult {
+    /// The [name](DetectionProbe::name) of the probe.
+    pub name: String,
+    /// The [weight](DetectionProbe::weight) of the probe.
+    pub weight: f64,
+    /// The result of the probe.
+    pub result: Result<bool, DetectError>,
+}
+
+impl DetectionReport {
+    /// Returns `true` if any method or probe detected a debugger.
+    pub fn any_detected(&self) -> bool {
+        self.results.iter().any(|(_, result)| matches!(result, Ok(Some(_))))
+            || self.probes.iter().any(|probe| matches!(probe.result, Ok(true)))
+    }
+
+    /// Returns `true` if every method and probe succeeded without detecting a debugger.
+    pub fn all_clean(&self) -> bool {
+        self.results.iter().all(|(_, result)| matches!(result, Ok(None)))
+            && self.probes.iter().all(|probe| matches!(probe.result, Ok(false)))
+    }
+
+    /// Returns `true` if any method or probe detected a debugger,
+    /// or with [`ErrorPolicy::FailClosed`], if any of them failed.
+    pub fn verdict(&self, policy: ErrorPolicy) -> bool {
+        self.any_detected() || policy == ErrorPolicy::FailClosed && !self.no_errors()
+    }
+
+    /// Returns `true` if no method or probe failed.
+    fn no_errors(&self) -> bool {
+        self.errors().next().is_none() && self.probes.iter().all(|probe| probe.result.is_ok())
+    }
+
+    /// Returns the confidence that a debugger is attached, from 0.0 to 1.0,
+    /// weighing the methods by [`DetectionMethod::weight`] and the probes by their own weights.
+    ///
+    /// The weights of the methods and probes that detected a debugger are combined as independent
+    /// evidence, i.e. the score is `1 - (1 - w1) * (1 - w2) * ...`. A detection with weight 1.0
+    /// saturates the score, and further detections only increase it. Clean methods don't lower
+    /// the score, and failed ones don't contribute either way.
+    pub fn score(&self) -> f64 {
+        self.score_with(DetectionMethod::weight)
+    }
+
+    /// Returns the confidence like [`score`](Self::score), weighing the methods by `weight` instead.
+    pub fn score_with(&self, weight: impl Fn(DetectionMethod) -> f64) -> f64 {
+        let methods = self.results.iter().filter(|(_, result)| matches!(result, Ok(Some(_)))).map(|&(method, _)| weight(method));
+        let probes = self.probes.iter().filter(|probe| matches!(probe.result, Ok(true))).map(|probe| probe.weight);
+        1.0 - methods.chain(probes).map(|weight| 1.0 - weight.clamp(0.0, 1.0)).product::<f64>()
+    }
+
+    /// Returns the methods that failed with their errors. The errors of the probes are in [`probes`](Self::probes).
+    pub fn errors(&self) -> impl Iterator<Item = (DetectionMethod, &DetectError)> {
+        self.results.iter().filter_map(|(method, result)| result.as_ref().err().map(|error| (*method, error)))
+    }
+}
+
+impl core::fmt::Display for DetectionReport {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut first = true;
+        for (method, result) in &self.results {
+            if !first {
+                writeln!(f)?;
+            }
+            first = false;
+            match result {
+                Ok(Some(reason)) => write!(f, "{method:?}: detected ({reason})")?,
+                Ok(None) => write!(f, "{method:?}: clean")?,
+                Err(error) => write!(f, "{method:?}: error ({error})")?,
+            }
+        }
+        for ProbeResult { name, result, .. } in &self.probes {
+            if !first {
+                writeln!(f)?;
+            }
+            first = false;
+            match result {
+                Ok(true) => write!(f, "{name}: detected")?,
+                Ok(false) => write!(f, "{name}: clean")?,
+                Err(error) => write!(f, "{name}: error ({error})")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Runs every [supported](DetectionMethod::is_supported) method and reports all of their results.
+///
+/// Unlike [`detect_with`], this never returns early: a method that detects a debugger or fails
+/// doesn't skip the remaining ones, so a single hooked API can't hide the others.
+///
+/// # Examples
+///
+/// ```rust
+/// # fn main() {
+/// let report = anti_debug::run_all_checks();
+/// if report.any_detected() {
+///     println!("Debugger detected!");
+/// }
+/// println!("{report}");
+/// # }
+/// ```
+pub fn run_all_checks() -> DetectionReport {
+    run_methods(&supported_methods())
+}
+
+/// Runs all of the given `methods` and reports their results.
+pub(crate) fn run_methods(methods: &[DetectionMethod]) -> DetectionReport {
+    let results = methods.iter().map(|&method| (method, method.run())).collect();
+    DetectionReport { results, probes: Vec::new() }
+}
+
+/// A check which can run alongside the built-in methods, see [`run_all_checks_with`].
+///
+/// Every [`DetectionMethod`] is a probe, named by [`DetectionMethod::name`].
+///
+/// # Examples
+///
+/// ```rust
+/// use anti_debug::{DetectError, DetectionProbe};
+///
+/// /// Detects the instrumentation agent, which sets `AGENT_ATTACHED`.
+/// struct AgentProbe;
+///
+/// impl DetectionProbe for AgentProbe {
+///     fn name(&self) -> &str {
+///         "AgentAttached"
+///     }
+///
+///     fn probe(&self) -> Result<bool, DetectError> {
+///         Ok(std::env::var_os("AGENT_ATTACHED").is_some())
+///     }
+/// }
+///
+/// # fn main() {
+/// let mut probes = anti_debug::builtin_probes();
+/// probes.push(&AgentProbe);
+/// let report = anti_debug::run_all_checks_with(&probes);
+/// if report.any_detected() {
+///     println!("Debugger detected!");
+/// }
+/// println!("{report}");
+/// # }
+/// ```
+pub trait DetectionProbe: Send + Sync {
+    /// Returns the name of the probe, which attributes its result in the [`DetectionReport`].
+    fn name(&self) -> &str;
+
+    /// Runs the probe.
+    ///
+    /// Returns `Ok(true)` if a debugger is detected, `Ok(false)` if no debugger is present,
+    /// or `Err(DetectError)` if the check could not be performed.
+    fn probe(&self) -> Result<bool, DetectError>;
+
+    /// Returns the weight of the probe for [`DetectionReport::score`], from 0.0 to 1.0. Defaults to 0.5.
+    ///
+    /// Give circumstantial heuristics, such as a debugger running somewhere on the system, a low weight,
+    /// so they only raise the score together with other detections.
+    fn weight(&self) -> f64 {
+        0.5
+    }
+
+    /// Returns the built-in method of the probe, whose result is reported with its [`DetectionReason`].
+    #[doc(hidden)]
+    fn method(&self) -> Option<DetectionMethod> {
+        None
+    }
+}
+
+impl DetectionProbe for DetectionMethod {
+    fn name(&self) -> &str {
+        DetectionMethod::name(*self)
+    }
+
+    fn probe(&self) -> Result<bool, DetectError> {
+        self.run().map(|reason| reason.is_some())
+    }
+
+    fn weight(&self) -> f64 {
+        DetectionMethod::weight(*self)
+    }
+
+    fn method(&self) -> Option<DetectionMethod> {
+        Some(*self)
+    }
+}
+
+/// Returns the [supported](DetectionMethod::is_supported) methods as probes, in the order of [`DetectionMethod::ALL`].
+///
+/// Filter or extend the list to pass it to [`run_all_checks_with`].
+pub fn builtin_probes() -> Vec<&'static dyn DetectionProbe> {
+    DetectionMethod::ALL.iter().filter(|method| method.is_supported()).map(|method| method as &dyn DetectionProbe).collect()
+}
+
+/// Runs all of the given `probes` like [`run_all_checks`], and reports their results.
+///
+/// The built-in methods are reported in [`DetectionReport::results`] with their [`DetectionReason`],
+/// and the other probes in [`DetectionReport::probes`] by their names.
+pub fn run_all_checks_with(probes: &[&dyn DetectionProbe]) -> DetectionReport {
+    let mut report = DetectionReport::default();
+    for probe in probes {
+        run_probe(*probe, &mut report);
+    }
+    report
+}
+
+/// Runs `probe` and appends its result to `report`.
+pub(crate) fn run_probe(probe: &dyn DetectionProbe, report: &mut DetectionReport) {
+    match probe.method() {
+        Some(method) => report.results.push((method, method.run())),
+        None => report.probes.push(ProbeResult { name: String::from(probe.name()), weight: probe.weight(), result: probe.probe() }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DetectionMethod;
+
+    #[test]
+    fn test_run() {
+        for &method in DetectionMethod::ALL {
+            match method.run() {
+                Ok(reason) => assert_eq!(reason, None, "{method:?}"),
+                Err(crate::DetectError::Unsupported) => {
+                    // Methods may still be unsupported by the system, such as `HWBreakpoint`.
+                    if method != DetectionMethod::HardwareBreakpoints {
+                        assert!(!method.is_supported(), "{method:?}");
+                    }
+                }
+                Err(e) => assert!(method.is_supported(), "{method:?}: {e}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let method = if cfg!(target_os = "windows") { DetectionMethod::TracerPid } else { DetectionMethod::IsDebuggerPresent };
+        assert!(!method.is_supported());
+        assert!(matches!(method.run(), Err(crate::DetectError::Unsupported)));
+        assert!(matches!(super::detect_with(&[method]), Err(crate::DetectError::Unsupported)));
+    }
+
+    #[test]
+    fn test_run_all_checks() {
+        let report = super::run_all_checks();
+        assert!(!report.any_detected(), "{report}");
+        assert_eq!(report.results.len(), super::supported_methods().len());
+        assert_eq!(report.all_clean(), report.errors().next().is_none());
+    }
+
+    #[test]
+    fn test_run_all_checks_with() {
+        struct Probe(bool);
+
+        impl super::DetectionProbe for Probe {
+            fn name(&self) -> &str {
+                "Custom"
+            }
+
+            fn probe(&self) -> Result<bool, crate::DetectError> {
+                Ok(self.0)
+            }
+        }
+
+        let mut probes = super::builtin_probes();
+        assert_eq!(probes.len(), super::supported_methods().len());
+        probes.push(&Probe(false));
+        let report = super::run_all_checks_with(&probes);
+        assert!(!report.any_detected(), "{report}");
+        assert_eq!(report.results.len(), super::supported_methods().len());
+        assert!(matches!(report.probes.as_slice(), [super::ProbeResult { name, result: Ok(false), .. }] if name == "Custom"));
+
+        let report = super::run_all_checks_with(&[&Probe(true)]);
+        assert!(report.any_detected() && !report.all_clean());
+        assert_eq!(report.to_string(), "Custom: detected");
+    }
+
+    #[test]
+    fn test_verdict() {
+        use super::ErrorPolicy;
+        use crate::DetectError;
+
+        let mut report = super::DetectionReport::default();
+        assert!(!report.verdict(ErrorPolicy::FailOpen) && !report.verdict(ErrorPolicy::FailClosed));
+
+        // `/proc/self/status` is unreadable, such as in a sandbox.
+        #[cfg(feature = "std")]
+        let error = DetectError::ProcStatusUnreadable(std::io::ErrorKind::PermissionDenied.into());
+        #[cfg(not(feature = "std"))]
+        let error = DetectError::InvalidTracerPid("".into());
+        report.results.push((DetectionMethod::TracerPid, Err(error)));
+        assert!(!report.verdict(ErrorPolicy::FailOpen));
+        assert!(report.verdict(ErrorPolicy::FailClosed));
+
+        let mut report = super::DetectionReport::default();
+        report.probes.push(super::ProbeResult { name: "Custom".into(), weight: 0.5, result: Err(DetectError::Unsupported) });
+        assert!(!report.verdict(ErrorPolicy::FailOpen));
+        assert!(report.verdict(ErrorPolicy::FailClosed));
+        report.results.push((DetectionMethod::TracerPid, Ok(Some(crate::DetectionReason::TracerPid(1)))));
+        assert!(report.verdict(ErrorPolicy::FailOpen));
+
+        assert!(!ErrorPolicy::FailOpen.apply(Err(DetectError::Unsupported)));
+        assert!(ErrorPolicy::FailClosed.apply(Err(DetectError::Unsupported)));
+        assert!(!ErrorPolicy::FailClosed.apply::<DetectError>(Ok(false)));
+    }
+
+    #[test]
+    fn test_score() {
+        use crate::{DetectError, DetectionReason};
+
+        let probe = |weight, result| super::ProbeResult { name: "Custom".into(), weight, result };
+        let mut report = super::DetectionReport::default();
+        assert_eq!(report.score(), 0.0);
+
+        // Clean and failed methods don't contribute.
+        report.results.push((DetectionMethod::DebugPort, Ok(None)));
+        report.results.push((DetectionMethod::DebugFlags, Err(DetectError::Unsupported)));
+        report.probes.push(probe(1.0, Err(DetectError::Unsupported)));
+        assert_eq!(report.score(), 0.0);
+
+        // Weak detections combine.
+        report.probes.push(probe(0.5, Ok(true)));
+        assert_eq!(report.score(), 0.5);
+        report.results.push((DetectionMethod::DebuggableBuild, Ok(Some(DetectionReason::DebuggableBuild))));
+        assert!((report.score() - 0.65).abs() < 1e-9);
+        assert!((report.score_with(|_| 0.0) - 0.5).abs() < 1e-9);
+
+        // Strong detections saturate regardless of clean ones.
+        report.results.push((DetectionMethod::TracerPid, Ok(Some(DetectionReason::TracerPid(1)))));
+        report.probes.push(probe(0.9, Ok(false)));
+        assert_eq!(report.score(), 1.0);
+    }
+
+    #[test]
+    fn test_detect_with() {
+        // `HWBreakpoint` isn't exposed by mainline kernels.
+        let methods: Vec<_> = super::supported_methods().into_iter()
+            .filter(|&method| method != DetectionMethod::HardwareBreakpoints)
+            .collect();
+        assert!(!super::detect_with(&methods).unwrap());
+        assert!(!super::detect_with(&[]).unwrap());
+    }
+}
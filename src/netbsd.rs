@@ -0,0 +1,60 @@
+//! NetBSD-specific detection checks.
+
+/// `P_TRACED`: the process is traced by a debugger.
+const P_TRACED: i32 = 0x800;
+
+/// Retrieves the `kinfo_proc2` of the process `pid` with `sysctl(CTL_KERN, KERN_PROC2, KERN_PROC_PID)`.
+fn kinfo_proc(pid: libc::pid_t) -> Result<libc::kinfo_proc2, crate::DetectError> {
+    unsafe {
+        let mut kinfo_proc = core::mem::zeroed::<libc::kinfo_proc2>();
+        let mut size = size_of::<libc::kinfo_proc2>();
+        // The last two elements are the size of each entry and the number of entries.
+        let mut mib = [libc::CTL_KERN, libc::KERN_PROC2, libc::KERN_PROC_PID, pid, size as libc::c_int, 1];
+        let result = libc::sysctl(mib.as_mut_ptr(), mib.len() as _, &mut kinfo_proc as *mut _ as _, &mut size, core::ptr::null_mut(), 0);
+        if result == -1 {
+            return Err(crate::error::last_os_error!(SysctlFailed));
+        }
+        if size != size_of::<libc::kinfo_proc2>() {
+            // The process doesn't exist.
+            return Err(crate::DetectError::OsError(libc::ESRCH));
+        }
+        Ok(kinfo_proc)
+    }
+}
+
+/// Checks with `sysctl(CTL_KERN, KERN_PROC2, KERN_PROC_PID)`, which reports `P_TRACED`
+/// in the `p_flag` field of `kinfo_proc2` while the current process is traced.
+pub fn check_sysctl_traced() -> Result<bool, crate::DetectError> {
+    let kinfo_proc = kinfo_proc(unsafe { libc::getpid() })?;
+    Ok(kinfo_proc.p_flag & P_TRACED != 0)
+}
+
+/// Returns the command name of the parent process, such as `gdb`.
+///
+/// Returns `Ok(None)` if the parent process has already exited.
+#[cfg(feature = "std")]
+pub fn parent_process_name() -> Result<Option<String>, crate::DetectError> {
+    let ppid = unsafe { libc::getppid() };
+    match kinfo_proc(ppid) {
+        Ok(kinfo_proc) => {
+            let name = unsafe { core::ffi::CStr::from_ptr(kinfo_proc.p_comm.as_ptr()) };
+            Ok(Some(name.to_string_lossy().into_owned()))
+        }
+        Err(crate::DetectError::OsError(libc::ESRCH)) => Ok(None),
+        Err(e) => Err(crate::DetectError::ParentProcessQueryFailed(e.into())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_check_sysctl_traced() {
+        assert!(!super::check_sysctl_traced().unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_parent_process_name() {
+        assert!(super::parent_process_name().is_ok());
+    }
+}
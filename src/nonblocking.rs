@@ -0,0 +1,192 @@
+//! Async versions of the detection functions for the `tokio` runtime.
+//!
+//! The synchronous functions block on file reads and system calls,
+//! which would stall the executor thread when called from async code.
+
+use std::sync::Arc;
+
+use crate::{AntiDebugError, DetectionReport, MonitorConfig};
+
+/// Checks if a debugger is present without blocking the async executor.
+///
+/// This is the async version of [`is_debugger_present`](crate::is_debugger_present).
+///
+/// # Platform-specific Behavior
+///
+/// - **Linux/Android**: Reads `/proc/self/status` with `tokio::fs`.
+///   The `ptrace` probe of the `deep-detect` feature runs on the blocking thread pool.
+/// - **Windows/macOS**: Runs [`is_debugger_present`](crate::is_debugger_present)
+///   on the blocking thread pool with `tokio::task::spawn_blocking`.
+///
+/// # Cancel Safety
+///
+/// This function is cancel-safe: it doesn't change the state of the process,
+/// so dropping the future loses nothing but the result.
+///
+/// # Panics
+///
+/// Panics if called outside a `tokio` runtime.
+pub async fn async_is_debugger_present() -> Result<bool, AntiDebugError> {
+    #[cfg(any(target_os = "linux", target_os = "android"))] {
+        let config = crate::DetectionConfig::default();
+        if config.check_tracerpid || config.check_hardware_breakpoints {
+            let path = crate::obfuscate::obfuscated!("/proc/self/status");
+            let status = tokio::fs::read_to_string(crate::obfuscate::as_str(&path)).await
+                .map_err(crate::DetectError::ProcStatusUnreadable)?;
+            if crate::detect_in_status(&config, &status)?.is_some() {
+                return Ok(true);
+            }
+        }
+        #[cfg(feature = "deep-detect")]
+        if config.check_ptrace_traceme && spawn_blocking(crate::linux::self_ptrace_detected).await?? {
+            return Ok(true);
+        }
+        #[cfg(feature = "deep-detect")]
+        if config.check_wchan && spawn_blocking(crate::linux::is_wchan_ptrace_related).await?? {
+            return Ok(true);
+        }
+        #[cfg(all(target_os = "android", feature = "deep-detect"))]
+        if config.check_debuggable_build && crate::linux::is_android_debug_build() {
+            return Ok(true);
+        }
+        Ok(false)
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "android")))] {
+        spawn_blocking(crate::is_debugger_present).await?
+    }
+}
+
+/// Attempts to prevent debuggers from attaching without blocking the async executor.
+///
+/// This is the async version of [`deny_attach`](crate::deny_attach).
+///
+/// # Platform-specific Behavior
+///
+/// - **Windows/Linux/Android**: Uses [`async_is_debugger_present`].
+/// - **macOS**: Runs [`deny_attach`](crate::deny_attach) on the blocking thread pool
+///   with `tokio::task::spawn_blocking`.
+///
+/// # Cancel Safety
+///
+/// This function is cancel-safe. On macOS, `ptrace(PT_DENY_ATTACH)` is still applied
+/// if the future is dropped after the blocking task has started, as that task can't be aborted.
+///
+/// # Panics
+///
+/// Panics if called outside a `tokio` runtime.
+pub async fn async_deny_attach() -> Result<(), AntiDebugError> {
+    #[cfg(any(target_os = "windows", target_os = "linux", target_os = "android"))] {
+        if async_is_debugger_present().await? {
+            return Err(AntiDebugError::DebuggerPresent);
+        }
+        Ok(())
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "android")))] {
+        spawn_blocking(crate::deny_attach).await?
+    }
+}
+
+/// Spawns a task polling the methods of `config` like [`Monitor`](crate::Monitor),
+/// and returns the receiver of the reports.
+///
+/// This is the async version of [`Monitor::start`](crate::Monitor::start): a report is sent
+/// whenever a debugger is newly detected, or on every poll with [`MonitorConfig::every_poll`].
+/// The polls wait with `tokio::time`, and the methods run on the blocking thread pool
+/// with `tokio::task::spawn_blocking`, so no thread is dedicated to the monitor.
+///
+/// The task ends once the receiver is dropped, without waiting for the next poll.
+///
+/// # Cancel Safety
+///
+/// A poll that has started on the blocking thread pool always runs to completion,
+/// even if the task is aborted or the runtime shuts down, so a check never leaves
+/// its temporary state, such as a signal handler, installed.
+///
+/// # Examples
+///
+/// ```rust
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let mut reports = anti_debug::async_monitor(anti_debug::MonitorConfig::default());
+/// tokio::spawn(async move {
+///     while let Some(report) = reports.recv().await {
+///         eprintln!("Debugger detected:\n{}", report);
+///     }
+/// });
+/// # }
+/// ```
+///
+/// # Panics
+///
+/// Panics if called outside a `tokio` runtime.
+pub fn async_monitor(config: MonitorConfig) -> tokio::sync::mpsc::Receiver<DetectionReport> {
+    let (sender, receiver) = tokio::sync::mpsc::channel(16);
+    tokio::spawn(async move {
+        let config = Arc::new(config);
+        let mut methods = config.methods.clone();
+        let mut random = crate::watch::Random::new();
+        let mut detected = false;
+        while !sender.is_closed() {
+            let polled = tokio::task::spawn_blocking({
+                let config = config.clone();
+                move || {
+                    let report = crate::watch::poll(&config, &mut methods, &mut random);
+                    (report, methods, random)
+                }
+            }).await;
+            // Stop if the runtime is shutting down or the poll panicked.
+            let Ok((report, polled_methods, polled_random)) = polled else { break };
+            (methods, random) = (polled_methods, polled_random);
+            let was_detected = core::mem::replace(&mut detected, report.verdict(config.error_policy));
+            if (config.every_poll || detected && !was_detected) && sender.send(report).await.is_err() {
+                break;
+            }
+            let interval = random.duration(config.min_interval, config.max_interval);
+            if tokio::time::timeout(interval, sender.closed()).await.is_ok() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+/// Runs `f` on the blocking thread pool.
+///
+/// A panic of `f` is resumed on the awaiting task.
+/// A task cancelled by the shutdown of the runtime is reported as [`AntiDebugError::OsError`].
+#[cfg_attr(all(any(target_os = "linux", target_os = "android"), not(feature = "deep-detect")), allow(dead_code))]
+async fn spawn_blocking<T: Send + 'static>(f: fn() -> T) -> Result<T, AntiDebugError> {
+    match tokio::task::spawn_blocking(f).await {
+        Ok(value) => Ok(value),
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        Err(e) => Err(AntiDebugError::OsError(std::io::Error::new(std::io::ErrorKind::Interrupted, e))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[tokio::test]
+    async fn test_async_is_debugger_present() {
+        assert!(!super::async_is_debugger_present().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_async_monitor() {
+        let config = crate::MonitorConfig {
+            min_interval: std::time::Duration::from_millis(5),
+            max_interval: std::time::Duration::from_millis(10),
+            every_poll: true,
+            ..Default::default()
+        };
+        let mut reports = super::async_monitor(config);
+        for _ in 0..3 {
+            let report = reports.recv().await.unwrap();
+            assert!(!report.any_detected(), "{report}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_async_deny_attach() {
+        super::async_deny_attach().await.unwrap();
+    }
+}
@@ -0,0 +1,182 @@
+//! Obfuscating the string literals of the checks.
+//!
+//! With the `obfuscate` feature, [`obfuscated!`] encodes a literal at compile time with a key
+//! generated for each build by `build.rs`, and decodes it on the stack at runtime,
+//! so the literal doesn't appear in the binary in plaintext.
+//! [`obfuscated_str!`] does the same for the names returned as `&'static str`, decoding them in place once.
+//! The names of the `serde` representations are not obfuscated, and neither is the debug info,
+//! which names the variants of the types, so strip it from shipped binaries.
+
+#[cfg(feature = "obfuscate")]
+include!(concat!(env!("OUT_DIR"), "/obfuscate_key.rs"));
+
+/// Returns the byte at `index` of the key stream derived from `key`, with the `splitmix64` mixer.
+#[cfg(feature = "obfuscate")]
+const fn key_byte(key: u64, index: usize) -> u8 {
+    let mut z = key.wrapping_add((index as u64).wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    (z ^ (z >> 31)) as u8
+}
+
+/// Encodes `bytes` with the key of the build.
+#[cfg(feature = "obfuscate")]
+pub(crate) const fn encode<const N: usize>(bytes: &[u8]) -> [u8; N] {
+    let mut encoded = [0u8; N];
+    let mut i = 0;
+    while i < N {
+        encoded[i] = bytes[i] ^ key_byte(KEY, i);
+        i += 1;
+    }
+    encoded
+}
+
+/// Decodes the result of [`encode`].
+///
+/// The input and the key are passed through [`black_box`](core::hint::black_box),
+/// so the compiler can't fold the decoding back into the plaintext.
+#[cfg(feature = "obfuscate")]
+#[inline(always)]
+pub(crate) fn decode<const N: usize>(encoded: [u8; N]) -> [u8; N] {
+    let key = core::hint::black_box(KEY);
+    let mut decoded = core::hint::black_box(encoded);
+    for (i, byte) in decoded.iter_mut().enumerate() {
+        *byte ^= key_byte(key, i);
+    }
+    decoded
+}
+
+/// Copies `bytes` into an array, in place of [`encode`] without the `obfuscate` feature.
+#[cfg(all(not(feature = "obfuscate"), any(feature = "std", target_os = "linux", target_os = "android")))]
+pub(crate) const fn to_array<const N: usize>(bytes: &[u8]) -> [u8; N] {
+    let mut array = [0u8; N];
+    let mut i = 0;
+    while i < N {
+        array[i] = bytes[i];
+        i += 1;
+    }
+    array
+}
+
+/// Returns the decoded bytes of [`obfuscated!`] as a string.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn as_str(bytes: &[u8]) -> &str {
+    core::str::from_utf8(bytes).unwrap_or_default()
+}
+
+/// A string literal encoded like [`obfuscated!`], decoded in place on its first use by [`obfuscated_str!`].
+#[cfg(feature = "obfuscate")]
+pub(crate) struct StaticStr<const N: usize> {
+    /// `0` while encoded, `1` while decoding, and `2` once decoded.
+    state: core::sync::atomic::AtomicU8,
+    bytes: core::cell::UnsafeCell<[u8; N]>,
+}
+
+// SAFETY: The bytes are only written once, by the thread which moves `state` from `0` to `1`,
+// and only read after `state` is `2`.
+#[cfg(feature = "obfuscate")]
+unsafe impl<const N: usize> Sync for StaticStr<N> {}
+
+#[cfg(feature = "obfuscate")]
+impl<const N: usize> StaticStr<N> {
+    /// Wraps the result of [`encode`].
+    pub(crate) const fn new(encoded: [u8; N]) -> Self {
+        Self { state: core::sync::atomic::AtomicU8::new(0), bytes: core::cell::UnsafeCell::new(encoded) }
+    }
+
+    /// Returns the decoded string, decoding it first if needed.
+    pub(crate) fn get(&'static self) -> &'static str {
+        use core::sync::atomic::Ordering;
+
+        loop {
+            match self.state.compare_exchange(0, 1, Ordering::Acquire, Ordering::Acquire) {
+                Ok(_) => {
+                    let bytes = unsafe { &mut *self.bytes.get() };
+                    *bytes = decode(*bytes);
+                    self.state.store(2, Ordering::Release);
+                    break;
+                }
+                Err(2) => break,
+                Err(_) => core::hint::spin_loop(),
+            }
+        }
+        core::str::from_utf8(unsafe { &*self.bytes.get() }).unwrap_or_default()
+    }
+}
+
+/// Returns the bytes of a string literal as an array, which is obfuscated in the binary
+/// with the `obfuscate` feature.
+#[cfg(any(feature = "std", target_os = "linux", target_os = "android"))]
+macro_rules! obfuscated {
+    ($literal:literal) => {{
+        #[cfg(feature = "obfuscate")]
+        let bytes = {
+            const ENCODED: [u8; $literal.len()] = $crate::obfuscate::encode($literal.as_bytes());
+            $crate::obfuscate::decode(ENCODED)
+        };
+        #[cfg(not(feature = "obfuscate"))]
+        let bytes = {
+            const PLAIN: [u8; $literal.len()] = $crate::obfuscate::to_array($literal.as_bytes());
+            PLAIN
+        };
+        bytes
+    }};
+}
+#[cfg(any(feature = "std", target_os = "linux", target_os = "android"))]
+pub(crate) use obfuscated;
+
+/// Returns a string literal as a `&'static str`, which is obfuscated in the binary
+/// with the `obfuscate` feature like [`obfuscated!`].
+macro_rules! obfuscated_str {
+    ($literal:literal) => {{
+        #[cfg(feature = "obfuscate")]
+        let string = {
+            static STRING: $crate::obfuscate::StaticStr<{ $literal.len() }> =
+                $crate::obfuscate::StaticStr::new($crate::obfuscate::encode($literal.as_bytes()));
+            STRING.get()
+        };
+        #[cfg(not(feature = "obfuscate"))]
+        let string: &'static str = $literal;
+        string
+    }};
+}
+pub(crate) use obfuscated_str;
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    #[cfg(any(feature = "std", target_os = "linux", target_os = "android"))]
+    fn test_obfuscated() {
+        assert_eq!(&super::obfuscated!("TracerPid:"), b"TracerPid:");
+        assert_eq!(super::obfuscated!(""), [0u8; 0]);
+    }
+
+    #[test]
+    fn test_obfuscated_str() {
+        fn name() -> &'static str {
+            super::obfuscated_str!("TracerPid")
+        }
+
+        assert_eq!(name(), "TracerPid");
+        let names: Vec<_> = (0..4).map(|_| std::thread::spawn(name)).collect();
+        for handle in names {
+            assert_eq!(handle.join().unwrap(), "TracerPid");
+        }
+        assert_eq!(super::obfuscated_str!(""), "");
+    }
+
+    #[test]
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn test_as_str() {
+        assert_eq!(super::as_str(&super::obfuscated!("/proc/self/status")), "/proc/self/status");
+        assert_eq!(super::as_str(&[0xff]), "");
+    }
+
+    #[test]
+    #[cfg(feature = "obfuscate")]
+    fn test_encode() {
+        const ENCODED: [u8; 10] = super::encode(b"TracerPid:");
+        assert_ne!(&ENCODED, b"TracerPid:");
+        assert_eq!(&super::decode(ENCODED), b"TracerPid:");
+    }
+}
@@ -0,0 +1,75 @@
+//! Aborting on panics while a debugger is attached.
+
+use std::panic::PanicHookInfo;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// The type of the panic hooks.
+type PanicHook = Box<dyn Fn(&PanicHookInfo<'_>) + Sync + Send + 'static>;
+
+/// Whether [`guard_panics`] is installed.
+static GUARDED: AtomicBool = AtomicBool::new(false);
+/// The panic hook replaced by [`guard_panics`].
+static PREVIOUS: Mutex<Option<PanicHook>> = Mutex::new(None);
+
+/// The panic hook installed by [`guard_panics`].
+fn guard(info: &PanicHookInfo<'_>) {
+    if crate::is_debugger_present().unwrap_or(false) {
+        std::process::abort();
+    }
+    if let Some(previous) = PREVIOUS.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        previous(info);
+    }
+}
+
+/// Installs a panic hook which aborts the process if a debugger is attached when panicking.
+///
+/// A panic in a protected binary is often caused by poking at it, and aborting prevents
+/// the debugger from intercepting the unwind. Without a debugger, the previous panic hook
+/// is called and the panic unwinds as usual.
+///
+/// Calling this again while installed does nothing.
+///
+/// # Panics
+///
+/// Panics if called from a panicking thread.
+pub fn guard_panics() {
+    if GUARDED.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    let previous = std::panic::take_hook();
+    *PREVIOUS.lock().unwrap_or_else(|e| e.into_inner()) = Some(previous);
+    std::panic::set_hook(Box::new(guard));
+}
+
+/// Restores the panic hook replaced by [`guard_panics`].
+///
+/// A panic hook set after [`guard_panics`] is replaced as well.
+/// Calling this while not installed does nothing.
+///
+/// # Panics
+///
+/// Panics if called from a panicking thread.
+pub fn unguard_panics() {
+    if !GUARDED.swap(false, Ordering::AcqRel) {
+        return;
+    }
+    let previous = PREVIOUS.lock().unwrap_or_else(|e| e.into_inner()).take();
+    match previous {
+        Some(previous) => std::panic::set_hook(previous),
+        None => drop(std::panic::take_hook()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_guard_panics() {
+        super::guard_panics();
+        super::guard_panics();
+        assert!(std::panic::catch_unwind(|| panic!("unwinds without a debugger")).is_err());
+        super::unguard_panics();
+        super::unguard_panics();
+        assert!(super::PREVIOUS.lock().unwrap().is_none());
+    }
+}
@@ -0,0 +1,149 @@
+//! Parent process inspection.
+
+use crate::DetectError;
+
+/// The process names of known debuggers, checked by [`debugger_parent_process`] and [`launched_by_debugger`].
+///
+/// Names are compared case-insensitively. On Linux/Android, process names are truncated to 15 bytes.
+/// Additional names can be registered with [`add_known_debugger`].
+pub const KNOWN_DEBUGGERS: &[&str] = known_debuggers!(slice);
+
+/// Expands `$callback!` with the names of [`KNOWN_DEBUGGERS`].
+macro_rules! known_debuggers {
+    ($callback:ident) => {
+        $callback!(
+            "gdb",
+            "lldb",
+            "lldb-server",
+            "debugserver",
+            "devenv.exe",
+            "windbg.exe",
+            "x64dbg.exe",
+            "ida64.exe",
+            "strace",
+            "ltrace",
+            "radare2",
+            "r2",
+            "rr",
+        )
+    };
+}
+use known_debuggers;
+
+/// Expands to a slice of the names.
+macro_rules! slice {
+    ($($name:literal,)*) => { &[$($name),*] };
+}
+use slice;
+
+/// Checks if `name` is one of [`KNOWN_DEBUGGERS`], case-insensitively.
+///
+/// The names are compared through [`obfuscated!`](crate::obfuscate::obfuscated) instead of [`KNOWN_DEBUGGERS`],
+/// so they don't appear in the binary in plaintext with the `obfuscate` feature.
+fn is_known_debugger(name: &str) -> bool {
+    macro_rules! any_matches {
+        ($($debugger:literal,)*) => { false $(|| crate::obfuscate::obfuscated!($debugger).eq_ignore_ascii_case(name.as_bytes()))* };
+    }
+    known_debuggers!(any_matches)
+}
+
+/// The process names registered with [`add_known_debugger`].
+static EXTRA_DEBUGGERS: std::sync::RwLock<Vec<String>> = std::sync::RwLock::new(Vec::new());
+
+/// Registers an additional debugger process name, such as internal tooling,
+/// to be checked along with [`KNOWN_DEBUGGERS`].
+pub fn add_known_debugger(name: &str) {
+    EXTRA_DEBUGGERS.write().unwrap_or_else(|e| e.into_inner()).push(name.to_owned());
+}
+
+/// Returns the name of the parent process.
+pub(crate) fn parent_process_name() -> Result<Option<String>, DetectError> {
+    #[cfg(target_os = "windows")] {
+        crate::windows::parent_process_name()
+    }
+    #[cfg(any(target_os = "linux", target_os = "android"))] {
+        crate::linux::parent_process_name()
+    }
+    #[cfg(target_os = "macos")] {
+        crate::macos::parent_process_name()
+    }
+    #[cfg(all(target_os = "ios", feature = "ios"))] {
+        crate::ios::parent_process_name()
+    }
+    #[cfg(target_os = "freebsd")] {
+        crate::freebsd::parent_process_name()
+    }
+    #[cfg(target_os = "netbsd")] {
+        crate::netbsd::parent_process_name()
+    }
+    #[cfg(target_os = "openbsd")] {
+        crate::openbsd::parent_process_name()
+    }
+}
+
+/// Checks if the parent process is a known debugger, which often spawns the process it debugs.
+///
+/// The name of the parent process is compared with [`KNOWN_DEBUGGERS`], the names registered
+/// with [`add_known_debugger`] and `extra_debuggers`, which can contain additional names such as internal tooling.
+///
+/// # Platform-specific Behavior
+///
+/// - **Windows**: Uses `windows::parent_process_name`.
+/// - **Linux/Android**: Uses `linux::parent_process_name`.
+/// - **macOS**: Uses `macos::parent_process_name`.
+/// - **iOS**: Uses `ios::parent_process_name`. Requires the `ios` feature.
+/// - **FreeBSD/NetBSD/OpenBSD**: Uses `freebsd::parent_process_name`, `netbsd::parent_process_name`
+///   or `openbsd::parent_process_name`.
+///
+/// # Return Value
+///
+/// Returns `Ok(Some(name))` with the name of the parent process if it is a known debugger,
+/// `Ok(None)` if not or if the parent process has already exited,
+/// or `Err(DetectError)` if the parent process could not be queried.
+pub fn debugger_parent_process(extra_debuggers: &[&str]) -> Result<Option<String>, DetectError> {
+    let Some(name) = parent_process_name()? else {
+        return Ok(None);
+    };
+    let registered = EXTRA_DEBUGGERS.read().unwrap_or_else(|e| e.into_inner());
+    let known = is_known_debugger(&name) || registered.iter().map(String::as_str)
+        .chain(extra_debuggers.iter().copied())
+        .any(|debugger| debugger.eq_ignore_ascii_case(&name));
+    Ok(known.then_some(name))
+}
+
+/// Checks if the process was launched by a known debugger frontend.
+///
+/// This is independent of the attachment checks of [`is_debugger_present`](crate::is_debugger_present),
+/// and uses [`debugger_parent_process`] without extra names: register them with [`add_known_debugger`].
+///
+/// A parent process that has already exited is not reported. On Linux/Android and macOS,
+/// such orphaned processes are reparented to `init`/`launchd` (or a subreaper), which are not debuggers.
+pub fn launched_by_debugger() -> Result<bool, DetectError> {
+    debugger_parent_process(&[]).map(|name| name.is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_is_known_debugger() {
+        for debugger in super::KNOWN_DEBUGGERS {
+            assert!(super::is_known_debugger(debugger), "{debugger}");
+            assert!(super::is_known_debugger(&debugger.to_uppercase()), "{debugger}");
+        }
+        assert!(!super::is_known_debugger("cargo"));
+        assert!(!super::is_known_debugger("gd"));
+    }
+
+    #[test]
+    fn test_debugger_parent_process() {
+        let parent = super::parent_process_name().unwrap().unwrap();
+        assert_eq!(super::debugger_parent_process(&[&parent]).unwrap(), Some(parent));
+    }
+
+    #[test]
+    fn test_launched_by_debugger() {
+        assert!(!super::launched_by_debugger().unwrap());
+        super::add_known_debugger(&super::parent_process_name().unwrap().unwrap());
+        assert!(super::launched_by_debugger().unwrap());
+    }
+}
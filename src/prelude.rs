@@ -0,0 +1,21 @@
+//! The most common items, for glob importing.
+//!
+//! ```rust
+//! use anti_debug::prelude::*;
+//!
+//! # fn main() {
+//! match is_debugger_present() {
+//!     Ok(true) => println!("Debugger detected!"),
+//!     Ok(false) => println!("No debugger present"),
+//!     Err(e) => println!("Error checking for debugger: {}", e),
+//! }
+//! # }
+//! ```
+//!
+//! The prelude contains [`is_debugger_present`], [`deny_attach`], [`DetectionReason`],
+//! [`DetectError`] and [`AntiDebugError`], and [`DebuggerStatus`] with the `std` feature.
+//! Items are only ever added to it in minor releases, and never removed.
+
+pub use crate::{AntiDebugError, DetectError, DetectionReason, deny_attach, is_debugger_present};
+#[cfg(feature = "std")]
+pub use crate::DebuggerStatus;
@@ -0,0 +1,95 @@
+/// The detection method that found a debugger.
+///
+/// Returned by [`detect_debugger`](crate::detect_debugger) so callers can log the specific signal.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum DetectionReason {
+    /// `IsDebuggerPresent` reported a debugger. (Windows)
+    IsDebuggerPresent,
+    /// `CheckRemoteDebuggerPresent` reported a debugger. (Windows)
+    RemoteDebugger,
+    /// `NtQueryInformationProcess` reported a debug port. (Windows)
+    DebugPort,
+    /// `NtQueryInformationProcess` reported a debug object handle. (Windows)
+    DebugObjectHandle,
+    /// `NtQueryInformationProcess` reported the debug flags as cleared. (Windows)
+    DebugFlags,
+    /// The `NtGlobalFlag` field of the PEB has the heap debugging flags set. (Windows)
+    NtGlobalFlag,
+    /// The process heap has the debug heap flags set. (Windows)
+    HeapFlags,
+    /// A kernel debugger is enabled and connected. (Windows)
+    KernelDebugger,
+    /// Hardware breakpoints are set on the current thread or process. (Windows/Linux/Android)
+    HardwareBreakpoints,
+    /// The `TracerPid` field in `/proc/self/status` carries the PID of the tracer. (Linux/Android)
+    TracerPid(i32),
+    /// The tracer slot of the process is already taken. (Linux/Android)
+    PtraceSlot,
+    /// A thread of the process is stopped by `ptrace` according to its `wchan`. (Linux/Android)
+    PtraceStop,
+    /// The OS is a debuggable build according to the `ro.debuggable` system property. (Android)
+    DebuggableBuild,
+    /// `proc_pidinfo` reported the process as traced. (macOS)
+    ProcTraced,
+    /// `sysctl` reported the process as traced. (macOS/iOS/FreeBSD/NetBSD/OpenBSD)
+    SysctlTraced,
+}
+
+// Written by hand, so the name of `TracerPid` can be obfuscated.
+impl core::fmt::Debug for DetectionReason {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DetectionReason::IsDebuggerPresent => f.write_str("IsDebuggerPresent"),
+            DetectionReason::RemoteDebugger => f.write_str("RemoteDebugger"),
+            DetectionReason::DebugPort => f.write_str("DebugPort"),
+            DetectionReason::DebugObjectHandle => f.write_str("DebugObjectHandle"),
+            DetectionReason::DebugFlags => f.write_str("DebugFlags"),
+            DetectionReason::NtGlobalFlag => f.write_str("NtGlobalFlag"),
+            DetectionReason::HeapFlags => f.write_str("HeapFlags"),
+            DetectionReason::KernelDebugger => f.write_str("KernelDebugger"),
+            DetectionReason::HardwareBreakpoints => f.write_str("HardwareBreakpoints"),
+            DetectionReason::TracerPid(pid) => f.debug_tuple(crate::obfuscate::obfuscated_str!("TracerPid")).field(pid).finish(),
+            DetectionReason::PtraceSlot => f.write_str("PtraceSlot"),
+            DetectionReason::PtraceStop => f.write_str("PtraceStop"),
+            DetectionReason::DebuggableBuild => f.write_str("DebuggableBuild"),
+            DetectionReason::ProcTraced => f.write_str("ProcTraced"),
+            DetectionReason::SysctlTraced => f.write_str("SysctlTraced"),
+        }
+    }
+}
+
+impl core::fmt::Display for DetectionReason {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DetectionReason::IsDebuggerPresent => write!(f, "IsDebuggerPresent reported a debugger"),
+            DetectionReason::RemoteDebugger => write!(f, "CheckRemoteDebuggerPresent reported a debugger"),
+            DetectionReason::DebugPort => write!(f, "debug port is set"),
+            DetectionReason::DebugObjectHandle => write!(f, "debug object handle is set"),
+            DetectionReason::DebugFlags => write!(f, "debug flags are cleared"),
+            DetectionReason::NtGlobalFlag => write!(f, "NtGlobalFlag has heap debugging flags"),
+            DetectionReason::HeapFlags => write!(f, "process heap has debug flags"),
+            DetectionReason::KernelDebugger => write!(f, "kernel debugger is connected"),
+            DetectionReason::HardwareBreakpoints => write!(f, "hardware breakpoints are set"),
+            DetectionReason::TracerPid(pid) => write!(f, "traced by pid {pid}"),
+            DetectionReason::PtraceSlot => write!(f, "ptrace slot is taken"),
+            DetectionReason::PtraceStop => write!(f, "thread is stopped by ptrace"),
+            DetectionReason::DebuggableBuild => write!(f, "OS is a debuggable build"),
+            DetectionReason::ProcTraced => write!(f, "process is traced"),
+            DetectionReason::SysctlTraced => write!(f, "process is traced according to sysctl"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde_round_trip() {
+        for reason in [super::DetectionReason::DebugPort, super::DetectionReason::TracerPid(42)] {
+            let json = serde_json::to_string(&reason).unwrap();
+            assert_eq!(serde_json::from_str::<super::DetectionReason>(&json).unwrap(), reason);
+        }
+    }
+}
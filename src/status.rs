@@ -0,0 +1,184 @@
+//! Aggregating all detection signals into a single report.
+
+use std::time::Duration;
+
+use crate::{DetectError, DetectionReason};
+
+/// The median sample threshold [`full_report`] passes to [`timing_anomaly_detected`](crate::timing_anomaly_detected).
+const TIMING_THRESHOLD: Duration = Duration::from_micros(100);
+
+/// All detection signals gathered by [`full_report`].
+///
+/// Unlike [`detect_debugger`](crate::detect_debugger), which stops at the first signal,
+/// this holds the result of every check, including the ones that failed.
+///
+/// With the `serde` feature this implements `Serialize`, with the errors as their [`DetectError::tag`].
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct DebuggerStatus {
+    /// Whether any check found a debugger, i.e. `reasons` isn't empty.
+    /// A timing anomaly alone doesn't count, since it may be caused by the load of the system.
+    pub is_debugger_present: bool,
+    /// The reasons of all checks that found a debugger.
+    pub reasons: Vec<DetectionReason>,
+    /// The PID of the tracer in `/proc/self/status`, if traced. (Linux/Android)
+    pub tracer_pid: Option<u32>,
+    /// The result of [`timing_anomaly_detected`](crate::timing_anomaly_detected).
+    /// `None` if the timing check wasn't performed.
+    pub timing_anomaly: Option<bool>,
+    /// The errors of the checks that could not be performed.
+    pub errors: Vec<DetectError>,
+}
+
+impl DebuggerStatus {
+    /// Records the result of a check which reports `reason` on `Ok(true)`.
+    #[cfg_attr(all(any(target_os = "linux", target_os = "android"), not(feature = "deep-detect")), allow(dead_code))]
+    fn record(&mut self, reason: DetectionReason, result: Result<bool, DetectError>) {
+        match result {
+            Ok(true) => self.reasons.push(reason),
+            Ok(false) => {}
+            Err(e) => self.errors.push(e),
+        }
+    }
+}
+
+impl core::fmt::Display for DebuggerStatus {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.is_debugger_present {
+            write!(f, "debugger detected")?;
+        } else {
+            write!(f, "no debugger detected")?;
+        }
+        for reason in &self.reasons {
+            write!(f, "\n  reason: {reason}")?;
+        }
+        if let Some(pid) = self.tracer_pid {
+            write!(f, "\n  tracer pid: {pid}")?;
+        }
+        match self.timing_anomaly {
+            Some(true) => write!(f, "\n  timing: anomaly detected")?,
+            Some(false) => write!(f, "\n  timing: normal")?,
+            None => {}
+        }
+        for error in &self.errors {
+            write!(f, "\n  error: {error}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs every check available on the current platform and with the enabled features,
+/// and reports all of their results.
+///
+/// This never short-circuits: a check that found a debugger or failed doesn't skip the remaining ones,
+/// and the failures are collected into [`DebuggerStatus::errors`].
+/// It is intended for dashboards and logging, prefer [`is_debugger_present`](crate::is_debugger_present)
+/// to react on a debugger.
+///
+/// # Platform-specific Behavior
+///
+/// - **Windows**: Runs the checks of [`is_debugger_present`](crate::is_debugger_present)
+///   with all `deep-detect` checks compiled in, and [`kernel_debugger_present`](crate::kernel_debugger_present).
+/// - **Linux/Android**: Runs the checks of [`is_debugger_present`](crate::is_debugger_present)
+///   with all `deep-detect` checks compiled in, and fills [`DebuggerStatus::tracer_pid`].
+/// - **macOS/iOS/FreeBSD/NetBSD/OpenBSD**: Runs the `sysctl` (and on macOS `proc_pidinfo`) checks.
+///
+/// The timing check [`timing_anomaly_detected`](crate::timing_anomaly_detected) runs on all platforms.
+///
+/// # Examples
+///
+/// ```rust
+/// # fn main() {
+/// let status = anti_debug::full_report();
+/// println!("{status}");
+/// # }
+/// ```
+pub fn full_report() -> DebuggerStatus {
+    let mut status = DebuggerStatus::default();
+    #[cfg(target_os = "windows")] {
+        use crate::windows;
+        if windows::check_is_debugger_present() {
+            status.reasons.push(DetectionReason::IsDebuggerPresent);
+        }
+        status.record(DetectionReason::RemoteDebugger, windows::check_remote_debugger_present());
+        status.record(DetectionReason::DebugPort, windows::check_debug_port());
+        status.record(DetectionReason::DebugObjectHandle, windows::check_debug_object_handle());
+        status.record(DetectionReason::DebugFlags, windows::check_debug_flags());
+        #[cfg(feature = "deep-detect")] {
+            status.record(DetectionReason::NtGlobalFlag, windows::nt_global_flag_debugged());
+            status.record(DetectionReason::HeapFlags, windows::heap_flags_debugged());
+            status.record(DetectionReason::HardwareBreakpoints, windows::has_hardware_breakpoints());
+        }
+        status.record(DetectionReason::KernelDebugger, crate::kernel_debugger_present());
+    }
+    #[cfg(any(target_os = "linux", target_os = "android"))] {
+        use crate::linux;
+        match linux::read_status() {
+            Ok(content) => {
+                match linux::parse_tracer_pid(&content) {
+                    Ok(0) => {}
+                    Ok(pid) => {
+                        status.tracer_pid = u32::try_from(pid).ok();
+                        status.reasons.push(DetectionReason::TracerPid(pid));
+                    }
+                    Err(e) => status.errors.push(e),
+                }
+                #[cfg(feature = "deep-detect")]
+                if linux::parse_hardware_breakpoints(&content) {
+                    status.reasons.push(DetectionReason::HardwareBreakpoints);
+                }
+            }
+            Err(e) => status.errors.push(e),
+        }
+        #[cfg(feature = "deep-detect")]
+        status.record(DetectionReason::PtraceSlot, linux::self_ptrace_detected());
+        #[cfg(feature = "deep-detect")]
+        status.record(DetectionReason::PtraceStop, linux::is_wchan_ptrace_related());
+        #[cfg(all(target_os = "android", feature = "deep-detect"))]
+        if linux::is_android_debug_build() {
+            status.reasons.push(DetectionReason::DebuggableBuild);
+        }
+    }
+    #[cfg(target_os = "macos")] {
+        status.record(DetectionReason::ProcTraced, crate::macos::check_proc_pidinfo_traced());
+        status.record(DetectionReason::SysctlTraced, crate::macos::check_sysctl_traced());
+        #[cfg(feature = "deep-detect")]
+        status.record(DetectionReason::PtraceSlot, crate::macos::self_ptrace_detected());
+    }
+    #[cfg(all(target_os = "ios", feature = "ios"))] {
+        status.record(DetectionReason::SysctlTraced, crate::ios::check_sysctl_traced());
+    }
+    #[cfg(target_os = "freebsd")] {
+        status.record(DetectionReason::SysctlTraced, crate::freebsd::check_sysctl_traced());
+    }
+    #[cfg(target_os = "netbsd")] {
+        status.record(DetectionReason::SysctlTraced, crate::netbsd::check_sysctl_traced());
+    }
+    #[cfg(target_os = "openbsd")] {
+        status.record(DetectionReason::SysctlTraced, crate::openbsd::check_sysctl_traced());
+    }
+    status.timing_anomaly = Some(crate::timing_anomaly_detected(TIMING_THRESHOLD));
+    status.is_debugger_present = !status.reasons.is_empty();
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_full_report() {
+        let status = super::full_report();
+        assert!(!status.is_debugger_present, "{status}");
+        assert!(status.reasons.is_empty());
+        assert_eq!(status.tracer_pid, None);
+        assert!(status.timing_anomaly.is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serialize() {
+        let json = serde_json::to_value(super::full_report()).unwrap();
+        assert_eq!(json["is_debugger_present"], false);
+        assert!(json["errors"].is_array());
+    }
+}
@@ -0,0 +1,118 @@
+//! Raw system calls on Linux/Android.
+//!
+//! A library injected with `LD_PRELOAD` can hook the libc wrappers the checks go through,
+//! such as `open` and `prctl`, to hide a debugger. The system calls here are performed
+//! with inline assembly on x86_64, aarch64 and arm, so no hook in userland sees them.
+//! Other architectures fall back to `libc::syscall`.
+
+use core::ffi::{CStr, c_int, c_long, c_ulong};
+
+/// Performs the system call `number` with `args`.
+///
+/// Returns the result, or the `errno` if the call failed.
+#[inline(always)]
+unsafe fn syscall(number: c_long, args: [usize; 5]) -> Result<usize, i32> {
+    let result: isize;
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        core::arch::asm!(
+            "syscall",
+            inlateout("rax") number as isize => result,
+            in("rdi") args[0],
+            in("rsi") args[1],
+            in("rdx") args[2],
+            in("r10") args[3],
+            in("r8") args[4],
+            lateout("rcx") _,
+            lateout("r11") _,
+            options(nostack),
+        );
+    }
+    #[cfg(target_arch = "aarch64")]
+    unsafe {
+        core::arch::asm!(
+            "svc 0",
+            in("x8") number,
+            inlateout("x0") args[0] as isize => result,
+            in("x1") args[1],
+            in("x2") args[2],
+            in("x3") args[3],
+            in("x4") args[4],
+            options(nostack),
+        );
+    }
+    // `r7` carries the number, but is the frame pointer in Thumb mode and can't be an operand.
+    #[cfg(target_arch = "arm")]
+    unsafe {
+        core::arch::asm!(
+            "mov {saved}, r7",
+            "mov r7, {number}",
+            "svc 0",
+            "mov r7, {saved}",
+            number = in(reg) number,
+            saved = out(reg) _,
+            inlateout("r0") args[0] as isize => result,
+            in("r1") args[1],
+            in("r2") args[2],
+            in("r3") args[3],
+            in("r4") args[4],
+            options(nostack),
+        );
+    }
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64", target_arch = "arm")))] {
+        let [a, b, c, d, e] = args;
+        result = unsafe { libc::syscall(number, a, b, c, d, e) } as isize;
+        if result == -1 {
+            return Err(crate::error::raw_os_error());
+        }
+    }
+    // The kernel returns the negated `errno` on failure.
+    if (-4095..0).contains(&result) {
+        Err(-result as i32)
+    } else {
+        Ok(result as usize)
+    }
+}
+
+/// Opens `path` relative to the current directory with `openat`.
+pub(crate) fn openat(path: &CStr, flags: c_int) -> Result<c_int, i32> {
+    let args = [libc::AT_FDCWD as usize, path.as_ptr() as usize, flags as usize, 0, 0];
+    unsafe { syscall(libc::SYS_openat, args) }.map(|fd| fd as c_int)
+}
+
+/// Reads from `fd` into `buffer` with `read`, returning the number of bytes read.
+pub(crate) fn read(fd: c_int, buffer: &mut [u8]) -> Result<usize, i32> {
+    let args = [fd as usize, buffer.as_mut_ptr() as usize, buffer.len(), 0, 0];
+    unsafe { syscall(libc::SYS_read, args) }
+}
+
+/// Closes `fd` with `close`.
+pub(crate) fn close(fd: c_int) -> Result<(), i32> {
+    unsafe { syscall(libc::SYS_close, [fd as usize, 0, 0, 0, 0]) }.map(|_| ())
+}
+
+/// Calls `prctl(option, arg)`.
+pub(crate) fn prctl(option: c_int, arg: c_ulong) -> Result<c_int, i32> {
+    unsafe { syscall(libc::SYS_prctl, [option as usize, arg as usize, 0, 0, 0]) }.map(|result| result as c_int)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_prctl() {
+        let expected = unsafe { libc::prctl(libc::PR_GET_DUMPABLE, 0, 0, 0, 0) };
+        assert_eq!(super::prctl(libc::PR_GET_DUMPABLE, 0), Ok(expected));
+        assert_eq!(super::prctl(-1, 0), Err(libc::EINVAL));
+    }
+
+    #[test]
+    fn test_read() {
+        let fd = super::openat(c"/proc/self/comm", libc::O_RDONLY | libc::O_CLOEXEC).unwrap();
+        let mut buffer = [0u8; 64];
+        let read = super::read(fd, &mut buffer).unwrap();
+        assert_eq!(super::close(fd), Ok(()));
+        assert_eq!(&buffer[..read], std::fs::read("/proc/self/comm").unwrap());
+        assert_eq!(super::close(fd), Err(libc::EBADF));
+        assert_eq!(super::openat(c"/proc/self/nonexistent", libc::O_RDONLY), Err(libc::ENOENT));
+    }
+}
@@ -0,0 +1,61 @@
+//! Reporting tampering with the environment of the process.
+
+use crate::DetectError;
+
+/// The environment tampering found by [`environment_tampering`].
+///
+/// Tampering indicates a configuration to debug or instrument the process,
+/// rather than an attached debugger.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TamperingReport {
+    /// The `Debugger` command line configured in the Image File Execution Options
+    /// for the current executable. (Windows)
+    pub ifeo_debugger: Option<String>,
+    /// The `LD_PRELOAD` environment variable, if it isn't empty. (Linux/Android)
+    pub ld_preload: Option<String>,
+    /// The known hook libraries mapped into the process. (Linux/Android)
+    pub suspicious_mappings: Vec<String>,
+}
+
+impl TamperingReport {
+    /// Returns `true` if no tampering was found.
+    pub fn is_clean(&self) -> bool {
+        self.ifeo_debugger.is_none() && self.ld_preload.is_none() && self.suspicious_mappings.is_empty()
+    }
+}
+
+/// Checks the environment of the process for tampering.
+///
+/// Unlike [`is_debugger_present`](crate::is_debugger_present), this reports configurations
+/// that debug or instrument the process, such as an Image File Execution Options debugger on Windows.
+///
+/// # Platform-specific Behavior
+///
+/// - **Windows**: Uses `windows::ifeo_debugger_configured`.
+/// - **Linux/Android**: Reads the `LD_PRELOAD` environment variable and uses `linux::suspicious_mappings`.
+/// - **Other platforms**: Returns an empty report.
+///
+/// # Return Value
+///
+/// Returns the [`TamperingReport`], or `Err(DetectError)` if a check could not be performed.
+pub fn environment_tampering() -> Result<TamperingReport, DetectError> {
+    #[allow(unused_mut)]
+    let mut report = TamperingReport::default();
+    #[cfg(target_os = "windows")] {
+        report.ifeo_debugger = crate::windows::ifeo_debugger_configured()?;
+    }
+    #[cfg(any(target_os = "linux", target_os = "android"))] {
+        report.ld_preload = std::env::var("LD_PRELOAD").ok().filter(|preload| !preload.is_empty());
+        report.suspicious_mappings = crate::linux::suspicious_mappings()?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_environment_tampering() {
+        assert!(super::environment_tampering().unwrap().is_clean());
+    }
+}
@@ -0,0 +1,282 @@
+//! Timing-based detection checks.
+
+use std::time::{Duration, Instant};
+
+/// The number of samples taken by [`timing_anomaly_detected`].
+const SAMPLES: usize = 15;
+
+/// A small fixed instruction sequence, which takes a few hundred nanoseconds without a debugger.
+#[inline(never)]
+fn workload() {
+    let mut value = 0u64;
+    for i in 0..64 {
+        value = std::hint::black_box(value.wrapping_mul(31).wrapping_add(i));
+    }
+    std::hint::black_box(value);
+}
+
+/// Reads the time stamp counter with `RDTSC`.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn read_timestamp_counter() -> u64 {
+    #[cfg(target_arch = "x86")]
+    use core::arch::x86::_rdtsc;
+    #[cfg(target_arch = "x86_64")]
+    use core::arch::x86_64::_rdtsc;
+    unsafe { _rdtsc() }
+}
+
+/// Returns the frequency of the time stamp counter in ticks per nanosecond.
+///
+/// Calibrated once against [`Instant`] over about a millisecond.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn timestamp_counter_frequency() -> f64 {
+    static FREQUENCY: std::sync::OnceLock<f64> = std::sync::OnceLock::new();
+    *FREQUENCY.get_or_init(|| {
+        let start = Instant::now();
+        let start_ticks = read_timestamp_counter();
+        while start.elapsed() < Duration::from_millis(1) {
+            std::hint::spin_loop();
+        }
+        let ticks = read_timestamp_counter().wrapping_sub(start_ticks);
+        ticks as f64 / start.elapsed().as_nanos() as f64
+    })
+}
+
+/// Measures the time taken by `f`, with `RDTSC` where available.
+fn measure(f: impl FnOnce()) -> Duration {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))] {
+        let start = read_timestamp_counter();
+        f();
+        let ticks = read_timestamp_counter().wrapping_sub(start);
+        Duration::from_nanos((ticks as f64 / timestamp_counter_frequency()) as u64)
+    }
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))] {
+        let start = Instant::now();
+        f();
+        start.elapsed()
+    }
+}
+
+/// Checks if executing a small fixed instruction sequence takes longer than `threshold`.
+///
+/// Single-stepping under a debugger inflates the latency of every instruction dramatically.
+/// The sequence is executed twice per sample, and the median of several samples is compared
+/// with `threshold`, so that preemption by the scheduler doesn't cause false positives.
+///
+/// On x86/x86-64 the time is measured with `RDTSC`, calibrated once against [`Instant`],
+/// which doesn't rely on any OS API. Other architectures use [`Instant`].
+///
+/// Without a debugger each sample takes well below a microsecond, so thresholds
+/// in the order of tens of microseconds are reasonable.
+pub fn timing_anomaly_detected(threshold: Duration) -> bool {
+    let mut samples = [Duration::ZERO; SAMPLES];
+    for sample in &mut samples {
+        *sample = measure(|| {
+            workload();
+            workload();
+        });
+    }
+    samples.sort_unstable();
+    samples[SAMPLES / 2] > threshold
+}
+
+/// A reasonable starting point for the `threshold_cycles` of [`is_execution_suspiciously_slow`].
+///
+/// The time stamp counter frequency and the cost of single-stepping vary between CPUs,
+/// so this needs tuning per hardware.
+#[cfg(all(feature = "timing", any(target_arch = "x86", target_arch = "x86_64")))]
+pub const DEFAULT_TIMING_THRESHOLD: u64 = 500_000;
+
+/// Checks if a short spin takes more than `threshold_cycles` time stamp counter ticks.
+///
+/// Issues two `RDTSC` instructions around the spin and compares their delta.
+/// Unlike [`timing_anomaly_detected`] this takes a single sample, so it is cheaper
+/// but may report a preemption by the scheduler as well.
+///
+/// See [`DEFAULT_TIMING_THRESHOLD`] for a starting point.
+#[cfg(all(feature = "timing", any(target_arch = "x86", target_arch = "x86_64")))]
+pub fn is_execution_suspiciously_slow(threshold_cycles: u64) -> bool {
+    let start = read_timestamp_counter();
+    workload();
+    let ticks = read_timestamp_counter().wrapping_sub(start);
+    ticks > threshold_cycles
+}
+
+/// Reads a monotonic high-resolution clock, which isn't adjusted by NTP.
+///
+/// - **Windows**: Uses `QueryPerformanceCounter`.
+/// - **Linux/Android**: Uses `clock_gettime(CLOCK_MONOTONIC_RAW)`.
+/// - **macOS**: Uses `mach_absolute_time`.
+/// - **Other platforms**: Uses [`Instant`].
+#[cfg(feature = "timing")]
+fn read_monotonic_clock() -> Result<Duration, crate::DetectError> {
+    #[cfg(target_os = "windows")]
+    unsafe {
+        let mut frequency = 0i64;
+        let mut counter = 0i64;
+        windows_sys::Win32::System::Performance::QueryPerformanceFrequency(&mut frequency);
+        windows_sys::Win32::System::Performance::QueryPerformanceCounter(&mut counter);
+        let (seconds, ticks) = (counter / frequency, counter % frequency);
+        Ok(Duration::from_secs(seconds as u64) + Duration::from_nanos((ticks as u128 * 1_000_000_000 / frequency as u128) as u64))
+    }
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    unsafe {
+        let mut time = core::mem::zeroed::<libc::timespec>();
+        if libc::clock_gettime(libc::CLOCK_MONOTONIC_RAW, &mut time) == -1 {
+            return Err(crate::error::last_os_error!(ClockFailed));
+        }
+        Ok(Duration::new(time.tv_sec as u64, time.tv_nsec as u32))
+    }
+    #[cfg(target_os = "macos")]
+    #[allow(deprecated)]
+    unsafe {
+        static TIMEBASE: std::sync::OnceLock<(u32, u32)> = std::sync::OnceLock::new();
+        let &(numer, denom) = TIMEBASE.get_or_init(|| {
+            let mut timebase = libc::mach_timebase_info { numer: 0, denom: 0 };
+            libc::mach_timebase_info(&mut timebase);
+            (timebase.numer, timebase.denom.max(1))
+        });
+        Ok(Duration::from_nanos((libc::mach_absolute_time() as u128 * numer as u128 / denom as u128) as u64))
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "android", target_os = "macos")))] {
+        static START: std::sync::OnceLock<Instant> = std::sync::OnceLock::new();
+        Ok(START.get_or_init(Instant::now).elapsed())
+    }
+}
+
+/// Checks if executing a small fixed instruction sequence takes longer than `threshold`,
+/// measured with the monotonic high-resolution clock of the OS.
+///
+/// Requires the `timing` feature. Several samples are taken and their median is compared
+/// with `threshold`, so a single outlier, such as a preemption by the scheduler, never reports a detection.
+///
+/// Unlike [`timing_anomaly_detected`], this doesn't use `RDTSC`:
+/// hypervisors may virtualize or trap the time stamp counter, which makes every `RDTSC`
+/// slow inside a virtual machine. The clocks of the OS use a paravirtualized clock source instead,
+/// so the overhead inside a virtual machine stays in the order of microseconds.
+///
+/// # Platform-specific Behavior
+///
+/// - **Windows**: Uses `QueryPerformanceCounter`.
+/// - **Linux/Android**: Uses `clock_gettime(CLOCK_MONOTONIC_RAW)`.
+/// - **macOS**: Uses `mach_absolute_time`.
+/// - **Other platforms**: Uses [`Instant`].
+///
+/// # Return Value
+///
+/// Returns `Ok(true)` if the median sample exceeds `threshold`, `Ok(false)` otherwise,
+/// or `Err(DetectError)` if the clock could not be read.
+#[cfg(feature = "timing")]
+pub fn timing_check(threshold: Duration) -> Result<bool, crate::DetectError> {
+    let mut samples = [Duration::ZERO; SAMPLES];
+    for sample in &mut samples {
+        let start = read_monotonic_clock()?;
+        workload();
+        workload();
+        *sample = read_monotonic_clock()?.saturating_sub(start);
+    }
+    samples.sort_unstable();
+    Ok(samples[SAMPLES / 2] > threshold)
+}
+
+/// A reasonable starting point for the `threshold` of [`is_output_debug_string_slow`].
+#[cfg(all(feature = "timing", target_os = "windows"))]
+pub const DEFAULT_OUTPUT_DEBUG_THRESHOLD: Duration = Duration::from_millis(1);
+
+/// Checks if `OutputDebugStringW` takes longer than `threshold`.
+///
+/// Requires the `timing` feature. An attached debugger is notified of the string
+/// and the call waits for it to be acknowledged, which makes it orders of magnitude slower.
+/// The time is measured with `QueryPerformanceCounter`, and the median of several calls
+/// is compared with `threshold` like [`timing_check`].
+///
+/// A running `DebugView` or another `DBWIN_BUFFER` reader slows the call down as well.
+/// See [`DEFAULT_OUTPUT_DEBUG_THRESHOLD`] for a starting point.
+///
+/// # Return Value
+///
+/// Returns `Ok(true)` if the median call exceeds `threshold`, `Ok(false)` otherwise,
+/// or `Err(DetectError)` if the clock could not be read.
+#[cfg(all(feature = "timing", target_os = "windows"))]
+pub fn is_output_debug_string_slow(threshold: Duration) -> Result<bool, crate::DetectError> {
+    const MESSAGE: &[u16] = &[b'.' as u16, 0];
+
+    let mut samples = [Duration::ZERO; SAMPLES];
+    for sample in &mut samples {
+        let start = read_monotonic_clock()?;
+        unsafe { windows_sys::Win32::System::Diagnostics::Debug::OutputDebugStringW(MESSAGE.as_ptr()) };
+        *sample = read_monotonic_clock()?.saturating_sub(start);
+    }
+    samples.sort_unstable();
+    Ok(samples[SAMPLES / 2] > threshold)
+}
+
+/// Runs `f` and checks if it took longer than `threshold`, which indicates it was likely stepped through.
+///
+/// Requires the `timing` feature. The time is measured with the same clock as [`timing_check`].
+/// `f` only runs once, so a preemption by the scheduler during `f` is reported as well.
+/// Choose `threshold` well above the usual duration of `f`.
+///
+/// Returns the result of `f`, and `false` if the clock could not be read.
+///
+/// # Examples
+///
+/// ```rust
+/// # fn main() {
+/// let (valid, stepped) = anti_debug::timed(|| "license".len() == 7, std::time::Duration::from_millis(10));
+/// if stepped {
+///     println!("License check was stepped through");
+/// }
+/// # let _ = valid;
+/// # }
+/// ```
+#[cfg(feature = "timing")]
+pub fn timed<R>(f: impl FnOnce() -> R, threshold: Duration) -> (R, bool) {
+    let start = read_monotonic_clock();
+    let result = f();
+    let end = read_monotonic_clock();
+    let stepped = match (start, end) {
+        (Ok(start), Ok(end)) => end.saturating_sub(start) > threshold,
+        _ => false,
+    };
+    (result, stepped)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_timing_anomaly_detected() {
+        assert!(!super::timing_anomaly_detected(std::time::Duration::from_millis(10)));
+        assert!(super::timing_anomaly_detected(std::time::Duration::ZERO));
+    }
+
+    #[test]
+    #[cfg(all(feature = "timing", any(target_arch = "x86", target_arch = "x86_64")))]
+    fn test_is_execution_suspiciously_slow() {
+        assert!(!super::is_execution_suspiciously_slow(super::DEFAULT_TIMING_THRESHOLD));
+        assert!(super::is_execution_suspiciously_slow(0));
+    }
+
+    #[test]
+    #[cfg(feature = "timing")]
+    fn test_timing_check() {
+        // The threshold is generous enough for virtual machines.
+        assert!(!super::timing_check(std::time::Duration::from_millis(10)).unwrap());
+        assert!(super::timing_check(std::time::Duration::ZERO).unwrap());
+    }
+
+    #[test]
+    #[cfg(all(feature = "timing", target_os = "windows"))]
+    fn test_is_output_debug_string_slow() {
+        assert!(!super::is_output_debug_string_slow(super::DEFAULT_OUTPUT_DEBUG_THRESHOLD).unwrap());
+        assert!(super::is_output_debug_string_slow(std::time::Duration::ZERO).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "timing")]
+    fn test_timed() {
+        assert_eq!(super::timed(|| 42, std::time::Duration::from_millis(10)), (42, false));
+        let slow = || std::thread::sleep(std::time::Duration::from_millis(5));
+        assert!(super::timed(slow, std::time::Duration::from_millis(1)).1);
+    }
+}
@@ -0,0 +1,185 @@
+//! Detecting virtual machines.
+
+/// The hypervisor found by [`detect_vm`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum VmVendor {
+    /// VMware.
+    VMware,
+    /// Oracle VirtualBox.
+    VirtualBox,
+    /// KVM.
+    Kvm,
+    /// Microsoft Hyper-V.
+    HyperV,
+    /// Xen.
+    Xen,
+    /// QEMU without KVM.
+    Qemu,
+    /// Parallels.
+    Parallels,
+    /// A hypervisor that reports itself but is not recognized.
+    Unknown,
+}
+
+impl core::fmt::Display for VmVendor {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            VmVendor::VMware => write!(f, "VMware"),
+            VmVendor::VirtualBox => write!(f, "VirtualBox"),
+            VmVendor::Kvm => write!(f, "KVM"),
+            VmVendor::HyperV => write!(f, "Hyper-V"),
+            VmVendor::Xen => write!(f, "Xen"),
+            VmVendor::Qemu => write!(f, "QEMU"),
+            VmVendor::Parallels => write!(f, "Parallels"),
+            VmVendor::Unknown => write!(f, "unknown hypervisor"),
+        }
+    }
+}
+
+/// Checks if the current process runs in a virtual machine, see [`detect_vm`].
+///
+/// Requires the `vm-detect` feature.
+pub fn running_in_vm() -> bool {
+    detect_vm().is_some()
+}
+
+/// Identifies the virtual machine the current process runs in.
+///
+/// Requires the `vm-detect` feature. Debuggers are often run in virtual machines,
+/// so some callers treat being analyzed in a virtual machine like being debugged.
+///
+/// # Platform-specific Behavior
+///
+/// - **x86/x86_64**: Checks the hypervisor-present bit of `CPUID` leaf 1,
+///   and matches the vendor signature of leaf `0x40000000`.
+/// - **Linux/Android**: Additionally matches the DMI strings under `/sys/class/dmi/id/`
+///   (requires the `std` feature), which also work on other architectures.
+///
+/// # Notes
+///
+/// Windows with virtualization-based security (such as Credential Guard) runs on Hyper-V
+/// even on physical machines, so [`VmVendor::HyperV`] may be reported there.
+///
+/// # Return Value
+///
+/// Returns the [`VmVendor`] if a virtual machine is detected, or `None` otherwise.
+pub fn detect_vm() -> Option<VmVendor> {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    if let Some(vendor) = cpuid_vendor() {
+        return Some(vendor);
+    }
+    #[cfg(all(any(target_os = "linux", target_os = "android"), feature = "std"))]
+    if let Some(vendor) = dmi_vendor() {
+        return Some(vendor);
+    }
+    None
+}
+
+/// Checks if the current process runs under a hypervisor, according to `CPUID` only.
+///
+/// Requires the `vm-detect` feature. Unlike [`running_in_vm`], this doesn't read any file,
+/// so it is cheap enough to call in hot paths. Use [`detect_vm`] to identify the hypervisor.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub fn is_hypervisor_present() -> bool {
+    cpuid_vendor().is_some()
+}
+
+/// Checks the hypervisor-present bit and the hypervisor vendor signature of `CPUID`.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn cpuid_vendor() -> Option<VmVendor> {
+    #[cfg(target_arch = "x86")]
+    use core::arch::x86::__cpuid;
+    #[cfg(target_arch = "x86_64")]
+    use core::arch::x86_64::__cpuid;
+
+    /// The hypervisor-present bit of `ECX` of leaf 1.
+    const HYPERVISOR_PRESENT: u32 = 1 << 31;
+    /// The leaf reporting the hypervisor vendor signature.
+    const HYPERVISOR_LEAF: u32 = 0x4000_0000;
+
+    // `CPUID` is available on every CPU Rust supports, and is marked safe by newer compilers.
+    #[allow(unused_unsafe)]
+    let (features, vendor) = unsafe { (__cpuid(1), __cpuid(HYPERVISOR_LEAF)) };
+    if features.ecx & HYPERVISOR_PRESENT == 0 {
+        return None;
+    }
+    let mut signature = [0u8; 12];
+    signature[..4].copy_from_slice(&vendor.ebx.to_le_bytes());
+    signature[4..8].copy_from_slice(&vendor.ecx.to_le_bytes());
+    signature[8..].copy_from_slice(&vendor.edx.to_le_bytes());
+    Some(parse_cpuid_signature(&signature))
+}
+
+/// Matches the hypervisor vendor signature of `CPUID` leaf `0x40000000`.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn parse_cpuid_signature(signature: &[u8; 12]) -> VmVendor {
+    match signature {
+        b"VMwareVMware" => VmVendor::VMware,
+        b"VBoxVBoxVBox" => VmVendor::VirtualBox,
+        b"KVMKVMKVM\0\0\0" => VmVendor::Kvm,
+        b"Microsoft Hv" => VmVendor::HyperV,
+        b"XenVMMXenVMM" => VmVendor::Xen,
+        b"TCGTCGTCGTCG" => VmVendor::Qemu,
+        b" lrpepyh  vr" | b"prl hyperv  " => VmVendor::Parallels,
+        _ => VmVendor::Unknown,
+    }
+}
+
+/// Matches the DMI strings of the system and board vendors and the product name.
+#[cfg(all(any(target_os = "linux", target_os = "android"), feature = "std"))]
+fn dmi_vendor() -> Option<VmVendor> {
+    ["sys_vendor", "product_name", "board_vendor"].into_iter()
+        .filter_map(|name| std::fs::read_to_string(format!("/sys/class/dmi/id/{name}")).ok())
+        .find_map(|value| parse_dmi_string(&value))
+}
+
+/// Matches a DMI string, such as `VMware, Inc.` or `innotek GmbH`.
+#[cfg(all(any(target_os = "linux", target_os = "android"), feature = "std"))]
+fn parse_dmi_string(value: &str) -> Option<VmVendor> {
+    const PATTERNS: &[(&str, VmVendor)] = &[
+        ("VMware", VmVendor::VMware),
+        ("VirtualBox", VmVendor::VirtualBox),
+        ("innotek", VmVendor::VirtualBox),
+        ("KVM", VmVendor::Kvm),
+        ("Virtual Machine", VmVendor::HyperV),
+        ("Xen", VmVendor::Xen),
+        ("QEMU", VmVendor::Qemu),
+        ("Parallels", VmVendor::Parallels),
+    ];
+    PATTERNS.iter().find(|(pattern, _)| value.contains(pattern)).map(|&(_, vendor)| vendor)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_detect_vm() {
+        assert_eq!(super::running_in_vm(), super::detect_vm().is_some());
+    }
+
+    #[test]
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn test_is_hypervisor_present() {
+        assert_eq!(super::is_hypervisor_present(), super::cpuid_vendor().is_some());
+        if super::is_hypervisor_present() {
+            assert!(super::running_in_vm());
+        }
+    }
+
+    #[test]
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn test_parse_cpuid_signature() {
+        assert_eq!(super::parse_cpuid_signature(b"KVMKVMKVM\0\0\0"), super::VmVendor::Kvm);
+        assert_eq!(super::parse_cpuid_signature(b"Microsoft Hv"), super::VmVendor::HyperV);
+        assert_eq!(super::parse_cpuid_signature(b"GenuineIntel"), super::VmVendor::Unknown);
+    }
+
+    #[test]
+    #[cfg(all(any(target_os = "linux", target_os = "android"), feature = "std"))]
+    fn test_parse_dmi_string() {
+        assert_eq!(super::parse_dmi_string("innotek GmbH\n"), Some(super::VmVendor::VirtualBox));
+        assert_eq!(super::parse_dmi_string("QEMU Standard PC (Q35 + ICH9, 2009)\n"), Some(super::VmVendor::Qemu));
+        assert_eq!(super::parse_dmi_string("Dell Inc.\n"), None);
+    }
+}
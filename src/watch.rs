@@ -0,0 +1,705 @@
+//! Background monitoring for debuggers attaching late.
+
+use std::hash::{BuildHasher, RandomState};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, mpsc};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::{DetectionMethod, DetectionProbe, DetectionReason, DetectionReport, ErrorPolicy};
+
+/// When the callback of [`watch_debugger_with`] is invoked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum WatchPolicy {
+    /// Invoke the callback the first time a debugger is detected, then stop polling.
+    #[default]
+    Once,
+    /// Invoke the callback on every poll that detects a debugger.
+    Repeated,
+}
+
+/// The detection state of a thread spawned by [`watch_debugger`], shared with its [`WatchHandle`].
+#[derive(Debug)]
+struct WatchState {
+    /// The time the offsets of `first_detected` are relative to.
+    start: Instant,
+    /// The offset of the first poll of the current detection in nanoseconds plus 1, or 0 if not detected.
+    first_detected: AtomicU64,
+}
+
+impl WatchState {
+    /// Records the result of a poll.
+    fn update(&self, detected: bool) {
+        if !detected {
+            self.first_detected.store(0, Ordering::Release);
+        } else if self.first_detected.load(Ordering::Acquire) == 0 {
+            let offset = self.start.elapsed().as_nanos().min(u64::MAX as u128 - 1) as u64;
+            self.first_detected.store(offset + 1, Ordering::Release);
+        }
+    }
+
+    /// Returns when the current detection was first seen.
+    fn first_detected_at(&self) -> Option<Instant> {
+        match self.first_detected.load(Ordering::Acquire) {
+            0 => None,
+            offset => Some(self.start + Duration::from_nanos(offset - 1)),
+        }
+    }
+}
+
+/// The handle to a thread spawned by [`watch_debugger`].
+///
+/// Dropping the handle stops the thread without waiting for it.
+#[derive(Debug)]
+pub struct WatchHandle {
+    stop: mpsc::Sender<()>,
+    thread: std::thread::JoinHandle<()>,
+    state: Arc<WatchState>,
+}
+
+impl WatchHandle {
+    /// Returns `true` if the latest poll detected a debugger.
+    ///
+    /// This is lock-free, so it is cheap enough to call on every frame.
+    /// Polls whose checks fail don't change the result.
+    pub fn detected(&self) -> bool {
+        self.state.first_detected_at().is_some()
+    }
+
+    /// Returns when the debugger detected by the latest poll was first seen,
+    /// i.e. the first of the consecutive polls that detected it.
+    ///
+    /// Returns `None` if the latest poll detected no debugger. This is lock-free like [`detected`](Self::detected).
+    /// With [`WatchPolicy::Once`], polling stops on the first detection, so the result stays as seen then.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn main() {
+    /// use std::time::Duration;
+    ///
+    /// let handle = anti_debug::watch_debugger_with(Duration::from_millis(100), anti_debug::WatchPolicy::Repeated, |_| {});
+    /// // In the main loop:
+    /// if handle.first_detected_at().is_some_and(|at| at.elapsed() > Duration::from_secs(5)) {
+    ///     std::process::exit(1);
+    /// }
+    /// # }
+    /// ```
+    pub fn first_detected_at(&self) -> Option<Instant> {
+        self.state.first_detected_at()
+    }
+
+    /// Returns how long the debugger detected by the latest poll has been seen, see [`first_detected_at`](Self::first_detected_at).
+    pub fn detected_for(&self) -> Option<Duration> {
+        self.first_detected_at().map(|at| at.elapsed())
+    }
+
+    /// Stops the thread and waits for it to finish.
+    ///
+    /// Returns `Err` with the panic payload if the callback panicked.
+    pub fn stop(self) -> std::thread::Result<()> {
+        drop(self.stop);
+        self.thread.join()
+    }
+
+    /// Returns `true` if the thread has finished, such as after a detection with [`WatchPolicy::Once`].
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+}
+
+/// Spawns a thread polling [`detect_debugger`](crate::detect_debugger) every `interval`,
+/// and invokes `on_detect` the first time a debugger is detected.
+///
+/// Debuggers often attach after startup, which a single check at the start of `main` misses.
+/// Polls whose checks fail are skipped.
+///
+/// # Examples
+///
+/// ```rust
+/// # fn main() {
+/// let handle = anti_debug::watch_debugger(std::time::Duration::from_secs(1), |reason| {
+///     eprintln!("Debugger detected: {}", reason);
+/// });
+/// // ...
+/// handle.stop().unwrap();
+/// # }
+/// ```
+pub fn watch_debugger(interval: Duration, on_detect: impl Fn(DetectionReason) + Send + 'static) -> WatchHandle {
+    watch_debugger_with(interval, WatchPolicy::Once, on_detect)
+}
+
+/// Spawns a thread polling [`detect_debugger`](crate::detect_debugger) every `interval`,
+/// and invokes `on_detect` according to `policy`.
+///
+/// See [`watch_debugger`].
+pub fn watch_debugger_with(interval: Duration, policy: WatchPolicy, on_detect: impl Fn(DetectionReason) + Send + 'static) -> WatchHandle {
+    let (stop, stopped) = mpsc::channel::<()>();
+    let state = Arc::new(WatchState { start: Instant::now(), first_detected: AtomicU64::new(0) });
+    let thread = std::thread::Builder::new()
+        .name("anti-debug-watch".into())
+        .spawn({
+            let state = state.clone();
+            move || loop {
+                match crate::detect_debugger() {
+                    Ok(Some(reason)) => {
+                        state.update(true);
+                        on_detect(reason);
+                        if policy == WatchPolicy::Once {
+                            break;
+                        }
+                    }
+                    Ok(None) => state.update(false),
+                    Err(_) => {}
+                }
+                if let Err(mpsc::RecvTimeoutError::Disconnected) | Ok(()) = stopped.recv_timeout(interval) {
+                    break;
+                }
+            }
+        })
+        .expect("failed to spawn the watch thread");
+    WatchHandle { stop, thread, state }
+}
+
+/// The handle to a thread spawned by [`spawn_watchdog`].
+///
+/// Dropping the handle doesn't stop the thread.
+#[derive(Debug)]
+pub struct Watchdog {
+    stopped: Arc<AtomicBool>,
+    thread: std::thread::JoinHandle<()>,
+}
+
+impl Watchdog {
+    /// Stops the thread and waits for it to finish.
+    ///
+    /// Returns `Err` with the panic payload if the callback panicked.
+    pub fn stop(self) -> std::thread::Result<()> {
+        self.stopped.store(true, Ordering::Release);
+        self.thread.thread().unpark();
+        self.thread.join()
+    }
+
+    /// Returns `true` if the thread has finished, such as after a detection.
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+}
+
+/// Spawns a thread polling [`is_debugger_present`](crate::is_debugger_present) every `interval`,
+/// and invokes `on_detect` once when a debugger is first detected.
+///
+/// Unlike [`watch_debugger`], the thread keeps running until detection or [`Watchdog::stop`],
+/// even if the [`Watchdog`] is dropped.
+/// Checks failing with transient errors are logged with the `log` feature, and polling continues.
+///
+/// # Examples
+///
+/// ```rust
+/// # fn main() {
+/// let watchdog = anti_debug::spawn_watchdog(std::time::Duration::from_secs(1), || {
+///     std::process::abort();
+/// });
+/// // ...
+/// watchdog.stop().unwrap();
+/// # }
+/// ```
+pub fn spawn_watchdog(interval: Duration, on_detect: impl Fn() + Send + 'static) -> Watchdog {
+    let stopped = Arc::new(AtomicBool::new(false));
+    let thread = std::thread::Builder::new()
+        .name("anti-debug-watchdog".into())
+        .spawn({
+            let stopped = stopped.clone();
+            move || {
+                while !stopped.load(Ordering::Acquire) {
+                    match crate::is_debugger_present() {
+                        Ok(true) => {
+                            on_detect();
+                            break;
+                        }
+                        Ok(false) => {}
+                        Err(e) => crate::log_warn!("debugger check failed: {e}"),
+                    }
+                    // Spurious wakeups only cause an early poll.
+                    std::thread::park_timeout(interval);
+                }
+            }
+        })
+        .expect("failed to spawn the watchdog thread");
+    Watchdog { stopped, thread }
+}
+
+/// Controls what a [`Monitor`] runs and how often.
+///
+/// Start from [`MonitorConfig::default`], then set the fields:
+///
+/// ```rust
+/// let mut config = anti_debug::MonitorConfig::default();
+/// config.min_interval = std::time::Duration::from_millis(200);
+/// config.max_interval = std::time::Duration::from_millis(800);
+/// ```
+#[derive(Clone)]
+#[non_exhaustive]
+pub struct MonitorConfig {
+    /// The shortest interval between two polls. Defaults to 500 milliseconds.
+    pub min_interval: Duration,
+    /// The longest interval between two polls. Defaults to 1.5 seconds.
+    ///
+    /// Each interval is drawn uniformly from [`min_interval`](Self::min_interval) to this,
+    /// so the polls can't be predicted and raced. Set both to the same value for a fixed interval.
+    pub max_interval: Duration,
+    /// The methods run on each poll. Defaults to [`supported_methods`](crate::supported_methods).
+    pub methods: Vec<DetectionMethod>,
+    /// The custom probes run on each poll after the methods. Defaults to none.
+    pub probes: Vec<Arc<dyn DetectionProbe>>,
+    /// Run the methods in a random order on each poll. Defaults to `true`.
+    pub shuffle: bool,
+    /// Interleave the methods with a random number of harmless system calls,
+    /// so the system call trace of the thread doesn't form an obvious signature. Defaults to `false`.
+    pub decoys: bool,
+    /// Invoke the callback on every poll, not only when a debugger is newly detected.
+    /// Defaults to `false`.
+    pub every_poll: bool,
+    /// The capacity of the channel returned by [`Monitor::channel`], at least 1. Defaults to 16.
+    pub capacity: usize,
+    /// Whether a failed method or probe counts as a detection. Defaults to [`ErrorPolicy::FailOpen`].
+    pub error_policy: ErrorPolicy,
+}
+
+impl Default for MonitorConfig {
+    fn default() -> Self {
+        Self {
+            min_interval: Duration::from_millis(500),
+            max_interval: Duration::from_millis(1500),
+            methods: crate::supported_methods(),
+            probes: Vec::new(),
+            shuffle: true,
+            decoys: false,
+            every_poll: false,
+            capacity: 16,
+            error_policy: ErrorPolicy::FailOpen,
+        }
+    }
+}
+
+impl core::fmt::Debug for MonitorConfig {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let probes: Vec<_> = self.probes.iter().map(|probe| probe.name()).collect();
+        f.debug_struct("MonitorConfig")
+            .field("min_interval", &self.min_interval)
+            .field("max_interval", &self.max_interval)
+            .field("methods", &self.methods)
+            .field("probes", &probes)
+            .field("shuffle", &self.shuffle)
+            .field("decoys", &self.decoys)
+            .field("every_poll", &self.every_poll)
+            .field("capacity", &self.capacity)
+            .field("error_policy", &self.error_policy)
+            .finish()
+    }
+}
+
+/// A random number generator for the [`Monitor`].
+///
+/// [`RandomState`] is seeded from the entropy of the OS, which keeps it free of a fixed seed
+/// without depending on an RNG crate.
+pub(crate) struct Random {
+    state: RandomState,
+    counter: u64,
+}
+
+impl Random {
+    pub(crate) fn new() -> Self {
+        Self { state: RandomState::new(), counter: 0 }
+    }
+
+    /// Returns a random number in `0..bound`.
+    fn below(&mut self, bound: u64) -> u64 {
+        self.counter += 1;
+        self.state.hash_one(self.counter) % bound
+    }
+
+    /// Returns a random duration from `min` to `max`.
+    pub(crate) fn duration(&mut self, min: Duration, max: Duration) -> Duration {
+        let span = max.saturating_sub(min).as_nanos().min(u64::MAX as u128 - 1) as u64;
+        min + Duration::from_nanos(self.below(span + 1))
+    }
+
+    /// Shuffles `items` with the Fisher-Yates algorithm.
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i as u64 + 1) as usize);
+        }
+    }
+
+    /// Performs up to 3 harmless system calls.
+    fn decoy(&mut self) {
+        for _ in 0..self.below(4) {
+            match self.below(3) {
+                0 => std::thread::yield_now(),
+                1 => { std::hint::black_box(std::process::id()); }
+                _ => { let _ = std::hint::black_box(std::env::current_dir()); }
+            }
+        }
+    }
+}
+
+/// Runs one poll of the [`Monitor`], in the order of `methods` after shuffling it as configured,
+/// followed by the probes of `config`.
+pub(crate) fn poll(config: &MonitorConfig, methods: &mut [DetectionMethod], random: &mut Random) -> DetectionReport {
+    if config.shuffle {
+        random.shuffle(methods);
+    }
+    let mut report = DetectionReport::default();
+    let probes = methods.iter().map(|method| method as &dyn DetectionProbe).chain(config.probes.iter().map(|probe| &**probe));
+    for probe in probes {
+        if config.decoys {
+            random.decoy();
+        }
+        crate::method::run_probe(probe, &mut report);
+    }
+    report
+}
+
+/// Polls the detection suite on a background thread, see [`Monitor::start`].
+#[derive(Debug)]
+pub enum Monitor {}
+
+impl Monitor {
+    /// Spawns a thread running the methods of `config` on every poll,
+    /// and invokes `on_detect` with the report whenever a debugger is newly detected.
+    ///
+    /// A debugger that stays attached is reported only once. After a poll without any detection,
+    /// the next detection is reported again, so attaching and detaching a debugger repeatedly
+    /// produces one report per attach. With [`MonitorConfig::every_poll`], every poll is reported instead.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn main() {
+    /// let handle = anti_debug::Monitor::start(anti_debug::MonitorConfig::default(), |report| {
+    ///     eprintln!("Debugger detected:\n{}", report);
+    /// });
+    /// // ...
+    /// handle.join().unwrap();
+    /// # }
+    /// ```
+    pub fn start(config: MonitorConfig, mut on_detect: impl FnMut(DetectionReport) + Send + 'static) -> MonitorHandle {
+        let every_poll = config.every_poll;
+        Self::spawn(config, move |was_detected, detected, report| {
+            if every_poll || detected && !was_detected {
+                on_detect(report);
+            }
+            true
+        })
+    }
+
+    /// Spawns a thread running the methods of `config` on every poll like [`Monitor::start`],
+    /// and returns the receiver of the [`DetectionEvent`]s of every attach and detach.
+    ///
+    /// The channel is bounded by [`MonitorConfig::capacity`]. While it is full, the thread doesn't block:
+    /// it keeps the newest unsent event and retries on the next poll, so a consumer falling behind
+    /// misses intermediate transitions but still receives the latest state.
+    /// [`MonitorConfig::every_poll`] is ignored.
+    ///
+    /// The thread stops with the returned [`MonitorHandle`],
+    /// or on the first event after the receiver is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn main() {
+    /// let (handle, events) = anti_debug::Monitor::channel(anti_debug::MonitorConfig::default());
+    /// std::thread::spawn(move || {
+    ///     for event in events {
+    ///         eprintln!("Debugger {:?} at {:?}", event.transition, event.timestamp);
+    ///     }
+    /// });
+    /// // ...
+    /// handle.join().unwrap();
+    /// # }
+    /// ```
+    pub fn channel(config: MonitorConfig) -> (MonitorHandle, mpsc::Receiver<DetectionEvent>) {
+        let (sender, receiver) = mpsc::sync_channel(config.capacity.max(1));
+        let mut outbox = Outbox { sender, pending: None };
+        let handle = Self::spawn(config, move |was_detected, detected, report| {
+            let transition = match (was_detected, detected) {
+                (false, true) => Some(Transition::Attached),
+                (true, false) => Some(Transition::Detached),
+                _ => None,
+            };
+            let event = transition.map(|transition| DetectionEvent { timestamp: SystemTime::now(), report, transition });
+            outbox.push(event)
+        });
+        (handle, receiver)
+    }
+
+    /// Spawns the thread of a monitor, which calls `on_poll` with whether a debugger was detected
+    /// by the previous and the current poll and the report of the current one, until `on_poll` returns `false`.
+    fn spawn(config: MonitorConfig, mut on_poll: impl FnMut(bool, bool, DetectionReport) -> bool + Send + 'static) -> MonitorHandle {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let thread = std::thread::Builder::new()
+            .name("anti-debug-monitor".into())
+            .spawn({
+                let stopped = stopped.clone();
+                move || {
+                    let mut random = Random::new();
+                    let mut methods = config.methods.clone();
+                    let mut detected = false;
+                    while !stopped.load(Ordering::Acquire) {
+                        let report = poll(&config, &mut methods, &mut random);
+                        let was_detected = core::mem::replace(&mut detected, report.verdict(config.error_policy));
+                        if !on_poll(was_detected, detected, report) {
+                            break;
+                        }
+                        // Spurious wakeups only cause an early poll.
+                        std::thread::park_timeout(random.duration(config.min_interval, config.max_interval));
+                    }
+                }
+            })
+            .expect("failed to spawn the monitor thread");
+        MonitorHandle { stopped, thread: Some(thread) }
+    }
+}
+
+/// The direction of a [`DetectionEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Transition {
+    /// A debugger is detected after a poll without any detection, or on the first poll.
+    Attached,
+    /// No debugger is detected after a poll with a detection.
+    Detached,
+}
+
+/// A change of the detection state sent by [`Monitor::channel`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct DetectionEvent {
+    /// When the poll of the event finished.
+    pub timestamp: SystemTime,
+    /// The report of the poll.
+    pub report: DetectionReport,
+    /// Whether a debugger attached or detached.
+    pub transition: Transition,
+}
+
+/// The sending side of [`Monitor::channel`], coalescing events while the channel is full.
+struct Outbox {
+    sender: mpsc::SyncSender<DetectionEvent>,
+    /// The newest event that didn't fit into the channel.
+    pending: Option<DetectionEvent>,
+}
+
+impl Outbox {
+    /// Sends `event`, or the pending event if `event` is `None`, without blocking.
+    ///
+    /// Returns `false` if the receiver is dropped.
+    fn push(&mut self, event: Option<DetectionEvent>) -> bool {
+        let Some(event) = event.or_else(|| self.pending.take()) else {
+            return true;
+        };
+        match self.sender.try_send(event) {
+            Ok(()) => { self.pending = None; true }
+            Err(mpsc::TrySendError::Full(event)) => { self.pending = Some(event); true }
+            Err(mpsc::TrySendError::Disconnected(_)) => false,
+        }
+    }
+}
+
+/// The handle to a thread spawned by [`Monitor::start`] or [`Monitor::channel`].
+///
+/// Dropping the handle stops the thread without waiting for it.
+#[derive(Debug)]
+pub struct MonitorHandle {
+    stopped: Arc<AtomicBool>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+impl MonitorHandle {
+    /// Stops the thread without waiting for it. The poll in progress, if any, still finishes.
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Release);
+        if let Some(thread) = &self.thread {
+            thread.thread().unpark();
+        }
+    }
+
+    /// Stops the thread and waits for it to finish.
+    ///
+    /// Returns `Err` with the panic payload if the callback panicked.
+    pub fn join(mut self) -> std::thread::Result<()> {
+        self.stop();
+        match self.thread.take() {
+            Some(thread) => thread.join(),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns `true` if the thread has finished, such as after the callback panicked.
+    pub fn is_finished(&self) -> bool {
+        self.thread.as_ref().is_none_or(|thread| thread.is_finished())
+    }
+}
+
+impl Drop for MonitorHandle {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_watch_debugger() {
+        let handle = super::watch_debugger(std::time::Duration::from_millis(10), |reason| panic!("{reason}"));
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert!(!handle.is_finished());
+        assert!(!handle.detected());
+        assert_eq!(handle.first_detected_at(), None);
+        assert_eq!(handle.detected_for(), None);
+        handle.stop().unwrap();
+    }
+
+    #[test]
+    fn test_watch_state() {
+        let state = super::WatchState { start: std::time::Instant::now(), first_detected: std::sync::atomic::AtomicU64::new(0) };
+        state.update(true);
+        let at = state.first_detected_at().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        state.update(true);
+        assert_eq!(state.first_detected_at(), Some(at));
+        state.update(false);
+        assert_eq!(state.first_detected_at(), None);
+        state.update(true);
+        assert!(state.first_detected_at().unwrap() > at);
+    }
+
+    #[test]
+    fn test_monitor() {
+        struct Probe;
+
+        impl crate::DetectionProbe for Probe {
+            fn name(&self) -> &str {
+                "Custom"
+            }
+
+            fn probe(&self) -> Result<bool, crate::DetectError> {
+                Ok(false)
+            }
+        }
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let config = super::MonitorConfig {
+            min_interval: std::time::Duration::from_millis(5),
+            max_interval: std::time::Duration::from_millis(10),
+            probes: vec![std::sync::Arc::new(Probe)],
+            decoys: true,
+            every_poll: true,
+            ..Default::default()
+        };
+        let mut methods = config.methods.clone();
+        let handle = super::Monitor::start(config, move |report| sender.send(report).unwrap());
+        methods.sort_by_key(|&method| method as u8);
+        for _ in 0..3 {
+            let report = receiver.recv().unwrap();
+            assert!(!report.any_detected(), "{report}");
+            let mut ran: Vec<_> = report.results.iter().map(|&(method, _)| method).collect();
+            ran.sort_by_key(|&method| method as u8);
+            assert_eq!(ran, methods);
+            assert!(matches!(report.probes.as_slice(), [crate::ProbeResult { name, result: Ok(false), .. }] if name == "Custom"));
+        }
+        assert!(!handle.is_finished());
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_monitor_channel() {
+        let config = super::MonitorConfig {
+            min_interval: std::time::Duration::from_millis(5),
+            max_interval: std::time::Duration::from_millis(10),
+            ..Default::default()
+        };
+        let (handle, events) = super::Monitor::channel(config);
+        let event = events.recv_timeout(std::time::Duration::from_millis(50));
+        assert!(matches!(event, Err(std::sync::mpsc::RecvTimeoutError::Timeout)), "{event:?}");
+        handle.join().unwrap();
+        assert!(matches!(events.recv(), Err(std::sync::mpsc::RecvError)));
+    }
+
+    #[test]
+    fn test_monitor_error_policy() {
+        struct Probe;
+
+        impl crate::DetectionProbe for Probe {
+            fn name(&self) -> &str {
+                "Failing"
+            }
+
+            fn probe(&self) -> Result<bool, crate::DetectError> {
+                Err(crate::DetectError::Unsupported)
+            }
+        }
+
+        let config = |error_policy| super::MonitorConfig {
+            min_interval: std::time::Duration::from_millis(5),
+            max_interval: std::time::Duration::from_millis(10),
+            probes: vec![std::sync::Arc::new(Probe)],
+            error_policy,
+            ..Default::default()
+        };
+        let (handle, events) = super::Monitor::channel(config(crate::ErrorPolicy::FailClosed));
+        assert_eq!(events.recv().unwrap().transition, super::Transition::Attached);
+        handle.join().unwrap();
+        let (handle, events) = super::Monitor::channel(config(crate::ErrorPolicy::FailOpen));
+        assert!(events.recv_timeout(std::time::Duration::from_millis(50)).is_err());
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_outbox() {
+        let event = |transition| super::DetectionEvent {
+            timestamp: std::time::SystemTime::now(),
+            report: crate::DetectionReport::default(),
+            transition,
+        };
+        let (sender, receiver) = std::sync::mpsc::sync_channel(1);
+        let mut outbox = super::Outbox { sender, pending: None };
+        assert!(outbox.push(Some(event(super::Transition::Attached))));
+        assert!(outbox.push(Some(event(super::Transition::Detached))));
+        assert!(outbox.push(Some(event(super::Transition::Attached))));
+        assert_eq!(receiver.try_recv().unwrap().transition, super::Transition::Attached);
+        assert!(outbox.push(None));
+        assert_eq!(receiver.try_recv().unwrap().transition, super::Transition::Attached);
+        assert!(receiver.try_recv().is_err());
+        drop(receiver);
+        assert!(!outbox.push(Some(event(super::Transition::Detached))));
+    }
+
+    #[test]
+    fn test_random() {
+        let orders: Vec<_> = (0..2).map(|_| {
+            let mut methods = crate::DetectionMethod::ALL.to_vec();
+            super::Random::new().shuffle(&mut methods);
+            methods
+        }).collect();
+        assert_ne!(orders[0], orders[1]);
+
+        let mut random = super::Random::new();
+        let (min, max) = (std::time::Duration::from_millis(500), std::time::Duration::from_millis(1500));
+        for _ in 0..100 {
+            let interval = random.duration(min, max);
+            assert!(interval >= min && interval <= max, "{interval:?}");
+        }
+        assert_eq!(random.duration(max, max), max);
+        assert_eq!(random.duration(max, min), max);
+    }
+
+    #[test]
+    fn test_spawn_watchdog() {
+        let watchdog = super::spawn_watchdog(std::time::Duration::from_secs(60), || panic!("debugger detected"));
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert!(!watchdog.is_finished());
+        watchdog.stop().unwrap();
+    }
+}
@@ -0,0 +1,1420 @@
+//! Windows-specific detection checks.
+//!
+//! Each check is exposed on its own, so custom detection policies can be built
+//! (e.g. only trusting kernel-level signals).
+//! [`is_debugger_present`](crate::is_debugger_present) calls through to these checks.
+
+/// Checks with `IsDebuggerPresent`, which reads the `BeingDebugged` flag of the PEB.
+pub fn check_is_debugger_present() -> bool {
+    unsafe {
+        let result = windows_sys::Win32::System::Diagnostics::Debug::IsDebuggerPresent();
+        result != windows_sys::Win32::Foundation::FALSE
+    }
+}
+
+/// Checks with `CheckRemoteDebuggerPresent` on the current process.
+///
+/// Despite its name, this also detects debuggers attached from the local machine.
+pub fn check_remote_debugger_present() -> Result<bool, crate::DetectError> {
+    unsafe {
+        let mut p_debugger_present = windows_sys::Win32::Foundation::FALSE;
+        let result = windows_sys::Win32::System::Diagnostics::Debug::CheckRemoteDebuggerPresent(
+            windows_sys::Win32::System::Threading::GetCurrentProcess(),
+            &mut p_debugger_present,
+        );
+        if result == windows_sys::Win32::Foundation::FALSE {
+            return Err(crate::error::last_os_error!(RemoteDebuggerCheckFailed));
+        }
+        Ok(p_debugger_present != windows_sys::Win32::Foundation::FALSE)
+    }
+}
+
+/// Checks with `NtQueryInformationProcess(ProcessDebugPort)`,
+/// which is non-zero when the kernel has a debug port for the current process.
+///
+/// The debug port is returned as a `DWORD_PTR`, so it is pointer-sized.
+pub fn check_debug_port() -> Result<bool, crate::DetectError> {
+    unsafe {
+        let mut p_debug_port = 0usize;
+        let mut return_length = 0u32;
+        let result = windows_sys::Wdk::System::Threading::NtQueryInformationProcess(
+            windows_sys::Win32::System::Threading::GetCurrentProcess(),
+            windows_sys::Wdk::System::Threading::ProcessDebugPort,
+            &mut p_debug_port as *mut _ as _,
+            size_of::<usize>() as _,
+            &mut return_length,
+        );
+        if result < 0 {
+            return Err(crate::DetectError::NtQueryFailed(result));
+        }
+        if return_length as usize != size_of::<usize>() {
+            return Err(crate::DetectError::NtQueryFailed(windows_sys::Win32::Foundation::STATUS_INFO_LENGTH_MISMATCH));
+        }
+        Ok(p_debug_port != 0)
+    }
+}
+
+/// Checks with `NtQueryInformationProcess(ProcessDebugObjectHandle)`,
+/// which returns the debug object of the current process.
+///
+/// The handle stays valid even when anti-anti-debugging tools clear the debug port.
+/// `STATUS_PORT_NOT_SET` is returned when there is no debug object.
+/// The returned handle is closed right away, so repeated checks don't leak handles.
+pub fn check_debug_object_handle() -> Result<bool, crate::DetectError> {
+    unsafe {
+        let mut debug_object: windows_sys::Win32::Foundation::HANDLE = core::ptr::null_mut();
+        let result = windows_sys::Wdk::System::Threading::NtQueryInformationProcess(
+            windows_sys::Win32::System::Threading::GetCurrentProcess(),
+            windows_sys::Wdk::System::Threading::ProcessDebugObjectHandle,
+            &mut debug_object as *mut _ as _,
+            size_of::<windows_sys::Win32::Foundation::HANDLE>() as _,
+            &mut 0,
+        );
+        if result == windows_sys::Win32::Foundation::STATUS_PORT_NOT_SET {
+            return Ok(false);
+        }
+        if result < 0 {
+            return Err(crate::DetectError::NtQueryFailed(result));
+        }
+        if debug_object.is_null() {
+            return Ok(false);
+        }
+        windows_sys::Win32::Foundation::CloseHandle(debug_object);
+        Ok(true)
+    }
+}
+
+/// Checks with `NtQueryInformationProcess(ProcessDebugFlags)`,
+/// which returns the inverse of the `NoDebugInherit` flag of the current process.
+///
+/// The value is `0` while the process is being debugged.
+pub fn check_debug_flags() -> Result<bool, crate::DetectError> {
+    unsafe {
+        let mut debug_flags = 0u32;
+        let result = windows_sys::Wdk::System::Threading::NtQueryInformationProcess(
+            windows_sys::Win32::System::Threading::GetCurrentProcess(),
+            windows_sys::Wdk::System::Threading::ProcessDebugFlags,
+            &mut debug_flags as *mut _ as _,
+            size_of::<u32>() as _,
+            &mut 0,
+        );
+        if result < 0 {
+            return Err(crate::DetectError::NtQueryFailed(result));
+        }
+        Ok(debug_flags == 0)
+    }
+}
+
+/// Checks with `NtQuerySystemInformation(SystemKernelDebuggerInformation)`,
+/// which reports if a kernel debugger is enabled and connected.
+///
+/// A kernel debugger enabled at boot but not connected (`KernelDebuggerNotPresent`) is not reported.
+pub fn check_kernel_debugger() -> Result<bool, crate::DetectError> {
+    const SYSTEM_KERNEL_DEBUGGER_INFORMATION: windows_sys::Wdk::System::SystemInformation::SYSTEM_INFORMATION_CLASS = 35;
+
+    unsafe {
+        // SYSTEM_KERNEL_DEBUGGER_INFORMATION { KernelDebuggerEnabled, KernelDebuggerNotPresent }
+        let mut information = [0u8; 2];
+        let result = windows_sys::Wdk::System::SystemInformation::NtQuerySystemInformation(
+            SYSTEM_KERNEL_DEBUGGER_INFORMATION,
+            information.as_mut_ptr() as _,
+            information.len() as _,
+            core::ptr::null_mut(),
+        );
+        if result < 0 {
+            return Err(crate::DetectError::SystemQueryFailed(result));
+        }
+        let [enabled, not_present] = information;
+        Ok(enabled != 0 && not_present == 0)
+    }
+}
+
+/// Checks the `KdDebuggerEnabled` field of `KUSER_SHARED_DATA`,
+/// which is mapped at `0x7FFE0000` in every process.
+///
+/// Bit 0 is set when a kernel debugger is enabled, and bit 1 when it is connected.
+/// This reads memory only, so it can't be intercepted by hooking `ntdll`.
+pub fn check_kuser_shared_data_kernel_debugger() -> bool {
+    const KUSER_SHARED_DATA: usize = 0x7FFE0000;
+    const KD_DEBUGGER_ENABLED_OFFSET: usize = 0x2D4;
+
+    let flags = unsafe { ((KUSER_SHARED_DATA + KD_DEBUGGER_ENABLED_OFFSET) as *const u8).read_volatile() };
+    flags & 0x03 == 0x03
+}
+
+/// Returns the image name of the parent process, such as `devenv.exe`.
+///
+/// Walks a `CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS)` snapshot for the parent PID and its image name.
+/// Returns `Ok(None)` if the parent process has already exited.
+///
+/// Windows doesn't reparent orphaned processes, so the parent PID may have been reused
+/// by an unrelated process. A process created after the current one is not reported as its parent.
+#[cfg(feature = "std")]
+pub fn parent_process_name() -> Result<Option<String>, crate::DetectError> {
+    let processes = process_snapshot().map_err(crate::DetectError::ParentProcessQueryFailed)?;
+    let pid = std::process::id();
+    let Some(&(_, parent, _)) = processes.iter().find(|(process, _, _)| *process == pid) else {
+        return Err(crate::DetectError::ParentProcessQueryFailed(std::io::ErrorKind::NotFound.into()));
+    };
+    if process_created_after_current(parent) {
+        return Ok(None);
+    }
+    Ok(processes.into_iter().find(|(process, _, _)| *process == parent).map(|(_, _, name)| name))
+}
+
+/// Returns the PID, parent PID and image name of every running process.
+///
+/// Walks a `CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS)` snapshot,
+/// which doesn't open the processes, so it works for processes of other users as well.
+#[cfg(feature = "std")]
+fn process_snapshot() -> std::io::Result<Vec<(u32, u32, String)>> {
+    use windows_sys::Win32::System::Diagnostics::ToolHelp::{PROCESSENTRY32W, Process32FirstW, Process32NextW};
+
+    let mut processes = Vec::new();
+    unsafe {
+        let snapshot = windows_sys::Win32::System::Diagnostics::ToolHelp::CreateToolhelp32Snapshot(
+            windows_sys::Win32::System::Diagnostics::ToolHelp::TH32CS_SNAPPROCESS,
+            0,
+        );
+        if snapshot == windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE {
+            return Err(std::io::Error::last_os_error());
+        }
+        let mut entry = core::mem::zeroed::<PROCESSENTRY32W>();
+        entry.dwSize = size_of::<PROCESSENTRY32W>() as _;
+        let mut result = Process32FirstW(snapshot, &mut entry);
+        while result != windows_sys::Win32::Foundation::FALSE {
+            processes.push((entry.th32ProcessID, entry.th32ParentProcessID, utf16_until_nul(&entry.szExeFile)));
+            result = Process32NextW(snapshot, &mut entry);
+        }
+        windows_sys::Win32::Foundation::CloseHandle(snapshot);
+    }
+    Ok(processes)
+}
+
+/// Converts a NUL-terminated UTF-16 buffer, replacing invalid data.
+#[cfg(feature = "std")]
+fn utf16_until_nul(buffer: &[u16]) -> String {
+    let length = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    String::from_utf16_lossy(&buffer[..length])
+}
+
+/// Checks if the process with `pid` was created after the current process.
+///
+/// Processes that can't be opened or queried are assumed to be created before.
+#[cfg(feature = "std")]
+fn process_created_after_current(pid: u32) -> bool {
+    fn creation_time(process: windows_sys::Win32::Foundation::HANDLE) -> Option<u64> {
+        let mut creation = windows_sys::Win32::Foundation::FILETIME::default();
+        let mut unused = windows_sys::Win32::Foundation::FILETIME::default();
+        let result = unsafe {
+            windows_sys::Win32::System::Threading::GetProcessTimes(process, &mut creation, &mut unused, &mut unused, &mut unused)
+        };
+        (result != windows_sys::Win32::Foundation::FALSE)
+            .then_some((creation.dwHighDateTime as u64) << 32 | creation.dwLowDateTime as u64)
+    }
+
+    unsafe {
+        let process = windows_sys::Win32::System::Threading::OpenProcess(
+            windows_sys::Win32::System::Threading::PROCESS_QUERY_LIMITED_INFORMATION,
+            windows_sys::Win32::Foundation::FALSE,
+            pid,
+        );
+        if process.is_null() {
+            return false;
+        }
+        let parent = creation_time(process);
+        windows_sys::Win32::Foundation::CloseHandle(process);
+        let current = creation_time(windows_sys::Win32::System::Threading::GetCurrentProcess());
+        matches!((parent, current), (Some(parent), Some(current)) if parent > current)
+    }
+}
+
+/// The registry key of the Image File Execution Options.
+#[cfg(feature = "std")]
+const IFEO_KEY: &str = r"SOFTWARE\Microsoft\Windows NT\CurrentVersion\Image File Execution Options";
+
+/// Returns the `Debugger` command line configured in the Image File Execution Options (IFEO)
+/// for the file name of the current executable.
+///
+/// A `Debugger` value under `HKLM\...\Image File Execution Options\<exe name>` makes Windows
+/// launch the executable under that debugger. Both the 64-bit and the 32-bit (WOW64) registry views
+/// are checked. Views that can't be accessed are skipped.
+///
+/// This indicates a configuration rather than an attached debugger.
+#[cfg(feature = "std")]
+pub fn ifeo_debugger_configured() -> Result<Option<String>, crate::DetectError> {
+    let executable = std::env::current_exe().map_err(crate::DetectError::ExecutableUnreadable)?;
+    let Some(name) = executable.file_name() else {
+        return Ok(None);
+    };
+    ifeo_debugger_in(windows_sys::Win32::System::Registry::HKEY_LOCAL_MACHINE, IFEO_KEY, &name.to_string_lossy())
+}
+
+/// Returns the `Debugger` value of the IFEO key `key` under `root` for the executable `name`.
+#[cfg(feature = "std")]
+fn ifeo_debugger_in(root: windows_sys::Win32::System::Registry::HKEY, key: &str, name: &str) -> Result<Option<String>, crate::DetectError> {
+    use windows_sys::Win32::System::Registry::{RRF_SUBKEY_WOW6432KEY, RRF_SUBKEY_WOW6464KEY};
+
+    let subkey = format!(r"{key}\{name}");
+    for view in [RRF_SUBKEY_WOW6464KEY, RRF_SUBKEY_WOW6432KEY] {
+        if let Some(debugger) = registry_string(root, &subkey, "Debugger", view)? {
+            return Ok(Some(debugger));
+        }
+    }
+    Ok(None)
+}
+
+/// Reads the string value `value` of the registry key `subkey` under `root` in the registry view `view`.
+///
+/// Returns `Ok(None)` if the key or value doesn't exist or can't be accessed.
+#[cfg(feature = "std")]
+fn registry_string(
+    root: windows_sys::Win32::System::Registry::HKEY,
+    subkey: &str,
+    value: &str,
+    view: windows_sys::Win32::System::Registry::REG_ROUTINE_FLAGS,
+) -> Result<Option<String>, crate::DetectError> {
+    use windows_sys::Win32::Foundation::{ERROR_ACCESS_DENIED, ERROR_FILE_NOT_FOUND, ERROR_MORE_DATA, ERROR_SUCCESS};
+    use windows_sys::Win32::System::Registry::{RRF_NOEXPAND, RRF_RT_REG_EXPAND_SZ, RRF_RT_REG_SZ};
+
+    let subkey = subkey.encode_utf16().chain([0]).collect::<Vec<_>>();
+    let value = value.encode_utf16().chain([0]).collect::<Vec<_>>();
+    let flags = view | RRF_RT_REG_SZ | RRF_RT_REG_EXPAND_SZ | RRF_NOEXPAND;
+    let mut buffer = Vec::<u16>::new();
+    loop {
+        let mut size = (buffer.len() * size_of::<u16>()) as u32;
+        let data = if buffer.is_empty() { core::ptr::null_mut() } else { buffer.as_mut_ptr() as _ };
+        let result = unsafe {
+            windows_sys::Win32::System::Registry::RegGetValueW(root, subkey.as_ptr(), value.as_ptr(), flags, core::ptr::null_mut(), data, &mut size)
+        };
+        match result {
+            ERROR_SUCCESS if !buffer.is_empty() || size == 0 => return Ok(Some(utf16_until_nul(&buffer))),
+            ERROR_SUCCESS | ERROR_MORE_DATA => buffer.resize((size as usize).div_ceil(size_of::<u16>()), 0),
+            ERROR_FILE_NOT_FOUND | ERROR_ACCESS_DENIED => return Ok(None),
+            error => return Err(crate::DetectError::RegistryQueryFailed(std::io::Error::from_raw_os_error(error as _))),
+        }
+    }
+}
+
+/// The image names of debuggers and memory editors checked by `debugger_processes`.
+#[cfg(feature = "artifact-scan")]
+const KNOWN_DEBUGGER_PROCESSES: &[&str] = &[
+    "x64dbg.exe",
+    "x32dbg.exe",
+    "ollydbg.exe",
+    "windbg.exe",
+    "ImmunityDebugger.exe",
+    "ida.exe",
+    "ida64.exe",
+    "cheatengine-x86_64.exe",
+    "cheatengine-x86_64-SSE4-AVX2.exe",
+    "cheatengine-i386.exe",
+    "dnSpy.exe",
+];
+
+/// The window classes of debuggers checked by `debugger_windows`.
+#[cfg(feature = "artifact-scan")]
+const KNOWN_DEBUGGER_WINDOW_CLASSES: &[&str] = &[
+    "OLLYDBG",
+    "WinDbgFrameClass",
+];
+
+/// Returns the image names of the running processes that are known debuggers or memory editors,
+/// such as `x64dbg.exe` or `cheatengine-x86_64.exe`.
+///
+/// The processes aren't opened, so processes of other users are found as well.
+#[cfg(feature = "artifact-scan")]
+pub fn debugger_processes() -> Result<Vec<String>, crate::DetectError> {
+    let processes = process_snapshot().map_err(crate::DetectError::ProcessSnapshotFailed)?;
+    Ok(processes.into_iter()
+        .map(|(_, _, name)| name)
+        .filter(|name| KNOWN_DEBUGGER_PROCESSES.iter().any(|known| known.eq_ignore_ascii_case(name)))
+        .collect())
+}
+
+/// Returns the class names or titles of the top-level windows that belong to known debuggers.
+///
+/// Enumerates the windows with `EnumWindows` and matches the known window classes,
+/// such as `OLLYDBG` and `WinDbgFrameClass`, and the titles of Qt windows for x64dbg/x32dbg
+/// and of Cheat Engine.
+#[cfg(feature = "artifact-scan")]
+pub fn debugger_windows() -> Vec<String> {
+    unsafe extern "system" fn collect(
+        window: windows_sys::Win32::Foundation::HWND,
+        windows: windows_sys::Win32::Foundation::LPARAM,
+    ) -> windows_sys::core::BOOL {
+        let windows = unsafe { &mut *(windows as *mut Vec<(String, String)>) };
+        let mut class = [0u16; 256];
+        let mut title = [0u16; 512];
+        unsafe {
+            let length = windows_sys::Win32::UI::WindowsAndMessaging::GetClassNameW(window, class.as_mut_ptr(), class.len() as _);
+            let class = String::from_utf16_lossy(&class[..length.max(0) as usize]);
+            let length = windows_sys::Win32::UI::WindowsAndMessaging::GetWindowTextW(window, title.as_mut_ptr(), title.len() as _);
+            let title = String::from_utf16_lossy(&title[..length.max(0) as usize]);
+            windows.push((class, title));
+        }
+        windows_sys::Win32::Foundation::TRUE
+    }
+
+    let mut windows = Vec::<(String, String)>::new();
+    unsafe {
+        windows_sys::Win32::UI::WindowsAndMessaging::EnumWindows(Some(collect), &mut windows as *mut _ as _);
+    }
+    windows.into_iter().filter_map(|(class, title)| {
+        if KNOWN_DEBUGGER_WINDOW_CLASSES.contains(&class.as_str()) {
+            return Some(class);
+        }
+        let qt = class.starts_with("Qt5") || class.starts_with("Qt6");
+        if qt && (title.contains("x64dbg") || title.contains("x32dbg")) || title.starts_with("Cheat Engine") {
+            return Some(title);
+        }
+        None
+    }).collect()
+}
+
+/// Checks if a debugger consumes the `DBG_PRINTEXCEPTION_C` exception raised by `OutputDebugString`.
+///
+/// The exception is raised with `RaiseException` under a temporary vectored exception handler.
+/// An attached debugger receives the exception first and swallows it as debug output,
+/// so the handler never runs. Without a debugger, the handler runs and continues the execution.
+/// This catches debuggers that patch the PEB and the query APIs but still consume debug events.
+///
+/// The handler only handles the exception raised by the calling thread while it is probing,
+/// so this is safe to call from multiple threads concurrently.
+/// The handler is removed before returning, even if the probe unwinds.
+///
+/// This must not be called from inside another structured exception handling frame,
+/// such as a `__try` block of C code or an exception filter,
+/// which could handle the exception before the vectored handler and break the result.
+///
+/// Some EDR products flag this pattern, so it is only available with the `exception-tricks` feature.
+#[cfg(feature = "exception-tricks")]
+pub fn check_debug_string_exception() -> Result<bool, crate::DetectError> {
+    // The arguments of `DBG_PRINTEXCEPTION_C` are the length of the string including the NUL and its address.
+    let message = c"anti-debug";
+    let arguments = [message.count_bytes() + 1, message.as_ptr() as usize];
+    let observed = exception_observed(windows_sys::Win32::Foundation::DBG_PRINTEXCEPTION_C, || unsafe {
+        windows_sys::Win32::System::Diagnostics::Debug::RaiseException(
+            windows_sys::Win32::Foundation::DBG_PRINTEXCEPTION_C as _,
+            0,
+            arguments.len() as _,
+            arguments.as_ptr(),
+        );
+    })?;
+    Ok(!observed)
+}
+
+/// Checks if a debugger intercepts a `STATUS_BREAKPOINT` exception before the vectored exception handlers.
+///
+/// The exception is raised with `RaiseException` under a temporary vectored exception handler.
+/// A debugger receives the first chance of the exception and usually handles it as its own breakpoint,
+/// so the handler never runs. Without a debugger, the handler runs and continues the execution.
+/// A debugger which passes the exception on to the process is not detected.
+///
+/// The same restrictions as [`check_debug_string_exception`] apply.
+#[cfg(feature = "exception-tricks")]
+pub fn is_debugger_intercepting_exceptions() -> Result<bool, crate::DetectError> {
+    let code = windows_sys::Win32::Foundation::STATUS_BREAKPOINT;
+    let observed = exception_observed(code, || unsafe {
+        windows_sys::Win32::System::Diagnostics::Debug::RaiseException(code as _, 0, 0, core::ptr::null());
+    })?;
+    Ok(!observed)
+}
+
+/// Checks if a debugger consumes the string of `OutputDebugStringW`, by the last error it leaves.
+///
+/// Without a debugger, `OutputDebugStringW` looks for a debug output monitor (the `DBWinMutex` mutex),
+/// which fails and overwrites the last error. A debugger receives the string as a debug event,
+/// so the last error stays as set before the call. This doesn't call any of the well-known
+/// detection APIs, so it catches tools which only hook those.
+///
+/// The last error of the calling thread is restored before returning.
+///
+/// # Notes
+///
+/// This is a heuristic: a running debug output monitor, such as DebugView,
+/// leaves the last error untouched as well and causes a false positive.
+pub fn check_output_debug_string() -> bool {
+    use windows_sys::Win32::Foundation::{GetLastError, SetLastError};
+
+    /// An error code which `OutputDebugStringW` never sets itself.
+    const SENTINEL: u32 = 0x2A7E_0D5B;
+    const MESSAGE: &[u16] = &[b'.' as u16, 0];
+
+    unsafe {
+        let saved = GetLastError();
+        SetLastError(SENTINEL);
+        windows_sys::Win32::System::Diagnostics::Debug::OutputDebugStringW(MESSAGE.as_ptr());
+        let unchanged = GetLastError() == SENTINEL;
+        SetLastError(saved);
+        unchanged
+    }
+}
+
+/// Checks if closing an invalid handle raises `EXCEPTION_INVALID_HANDLE`,
+/// which the kernel only raises while a debugger is attached.
+///
+/// `CloseHandle` is called on a randomized invalid handle under a temporary vectored exception handler,
+/// so the process doesn't die from the exception.
+/// Returns `Ok(true)` if the debugger passed the exception on to the handler.
+///
+/// Handle tracing, such as enabled by the Handles check of Application Verifier,
+/// raises the exception without a debugger as well,
+/// so `Err(DetectError::HandleTracingEnabled)` is returned instead of a false positive.
+///
+/// The same restrictions as [`check_debug_string_exception`] apply.
+#[cfg(feature = "exception-tricks")]
+pub fn invalid_handle_probe() -> Result<bool, crate::DetectError> {
+    if handle_tracing_enabled() {
+        return Err(crate::DetectError::HandleTracingEnabled);
+    }
+    let handle = random_invalid_handle();
+    exception_observed(windows_sys::Win32::Foundation::EXCEPTION_INVALID_HANDLE, || unsafe {
+        windows_sys::Win32::Foundation::CloseHandle(handle);
+    })
+}
+
+/// Checks if handle tracing is enabled for the current process
+/// with `NtQueryInformationProcess(ProcessHandleTracing)`, which fails while it is disabled.
+#[cfg(feature = "exception-tricks")]
+fn handle_tracing_enabled() -> bool {
+    unsafe {
+        // PROCESS_HANDLE_TRACING_QUERY { Handle, TotalTraces, HandleTrace[1] }
+        let mut query = [0usize; 64];
+        let result = windows_sys::Wdk::System::Threading::NtQueryInformationProcess(
+            windows_sys::Win32::System::Threading::GetCurrentProcess(),
+            windows_sys::Wdk::System::Threading::ProcessHandleTracing,
+            query.as_mut_ptr() as _,
+            size_of_val(&query) as _,
+            &mut 0,
+        );
+        result >= 0 || result == windows_sys::Win32::Foundation::STATUS_INFO_LENGTH_MISMATCH
+    }
+}
+
+/// Returns a random handle value that isn't open in the current process.
+///
+/// The value is randomized per call, so bypasses special-casing a constant handle don't work.
+#[cfg(feature = "exception-tricks")]
+fn random_invalid_handle() -> windows_sys::Win32::Foundation::HANDLE {
+    use core::hash::BuildHasher;
+
+    let random = std::collections::hash_map::RandomState::new();
+    (0u64..).find_map(|attempt| {
+        // Handle values are multiples of 4 far below this range.
+        let value = 0x4000_0000 | (random.hash_one(attempt) as usize & 0x3FFF_FFFC);
+        let handle = value as windows_sys::Win32::Foundation::HANDLE;
+        let mut flags = 0;
+        let open = unsafe { windows_sys::Win32::Foundation::GetHandleInformation(handle, &mut flags) };
+        (open == windows_sys::Win32::Foundation::FALSE).then_some(handle)
+    }).unwrap()
+}
+
+/// Checks if the exception `code` raised by `raise` on the current thread reaches a vectored exception handler.
+///
+/// The handler is installed before `raise` and removed afterward, even if `raise` unwinds.
+/// It continues the execution on the exception, and ignores exceptions of other threads.
+#[cfg(feature = "exception-tricks")]
+fn exception_observed(code: windows_sys::Win32::Foundation::NTSTATUS, raise: impl FnOnce()) -> Result<bool, crate::DetectError> {
+    use windows_sys::Win32::System::Diagnostics::Debug::{AddVectoredExceptionHandler, EXCEPTION_POINTERS, RemoveVectoredExceptionHandler};
+
+    std::thread_local! {
+        /// The exception code the current thread is probing, and whether its handler ran.
+        static PROBE: core::cell::Cell<Option<(windows_sys::Win32::Foundation::NTSTATUS, bool)>> = const { core::cell::Cell::new(None) };
+    }
+
+    unsafe extern "system" fn handler(info: *mut EXCEPTION_POINTERS) -> i32 {
+        let code = unsafe { (*(*info).ExceptionRecord).ExceptionCode };
+        if PROBE.get().is_none_or(|(probed, _)| probed != code) {
+            return windows_sys::Win32::System::Diagnostics::Debug::EXCEPTION_CONTINUE_SEARCH;
+        }
+        PROBE.set(Some((code, true)));
+        windows_sys::Win32::System::Diagnostics::Debug::EXCEPTION_CONTINUE_EXECUTION
+    }
+
+    /// Removes the handler and ends the probe of the current thread when dropped.
+    struct Registration(*mut core::ffi::c_void);
+
+    impl Drop for Registration {
+        fn drop(&mut self) {
+            PROBE.set(None);
+            unsafe { RemoveVectoredExceptionHandler(self.0); }
+        }
+    }
+
+    let registration = unsafe { AddVectoredExceptionHandler(1, Some(handler)) };
+    if registration.is_null() {
+        return Err(crate::DetectError::ExceptionHandlerUnavailable);
+    }
+    let registration = Registration(registration);
+    PROBE.set(Some((code, false)));
+    raise();
+    let observed = PROBE.get().is_some_and(|(_, observed)| observed);
+    drop(registration);
+    Ok(observed)
+}
+
+/// Hides `thread` from debuggers with `NtSetInformationThread(ThreadHideFromDebugger)`.
+///
+/// The kernel stops reporting debug events of a hidden thread, so a debugger attaching later
+/// doesn't see its breakpoints or exceptions, and an exception in it kills the process instead.
+/// This can't be undone, and it is per thread: threads created afterwards are not hidden.
+///
+/// `thread` must be a handle with `THREAD_SET_INFORMATION` access, such as `GetCurrentThread()`.
+// The handle is only validated by the kernel, never dereferenced.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub fn deny_attach_thread(thread: windows_sys::Win32::Foundation::HANDLE) -> Result<(), crate::DetectError> {
+    let status = unsafe {
+        windows_sys::Wdk::System::Threading::NtSetInformationThread(
+            thread,
+            windows_sys::Wdk::System::Threading::ThreadHideFromDebugger,
+            core::ptr::null(),
+            0,
+        )
+    };
+    if status < 0 {
+        return Err(crate::DetectError::HideThreadFailed(status));
+    }
+    Ok(())
+}
+
+/// Hides every thread of the current process from debuggers like [`deny_attach_thread`],
+/// and returns the number of hidden threads.
+///
+/// The threads are enumerated with `CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD)`.
+/// Threads that exit during the enumeration are skipped, and threads created afterwards are not hidden,
+/// so call this once all long-lived threads are started. Hiding a hidden thread again has no effect,
+/// so this can be called repeatedly.
+pub fn deny_attach_all_threads() -> Result<usize, crate::DetectError> {
+    use windows_sys::Win32::System::Threading::{OpenThread, THREAD_SET_INFORMATION};
+
+    let mut hidden = 0;
+    let mut error = None;
+    for_each_thread(|id| unsafe {
+        let thread = OpenThread(THREAD_SET_INFORMATION, windows_sys::Win32::Foundation::FALSE, id);
+        if thread.is_null() {
+            return true;
+        }
+        let result = deny_attach_thread(thread);
+        windows_sys::Win32::Foundation::CloseHandle(thread);
+        match result {
+            Ok(()) => hidden += 1,
+            Err(e) => error = Some(e),
+        }
+        error.is_none()
+    })?;
+    match error {
+        Some(error) => Err(error),
+        None => Ok(hidden),
+    }
+}
+
+/// Reads the memory of the current process at `address` into `buffer` with `ReadProcessMemory`,
+/// which fails instead of crashing on unreadable memory.
+#[cfg(feature = "memory-integrity")]
+pub(crate) fn read_memory(address: *const u8, buffer: &mut [u8]) -> Result<(), crate::DetectError> {
+    unsafe {
+        let mut read = 0;
+        let result = windows_sys::Win32::System::Diagnostics::Debug::ReadProcessMemory(
+            windows_sys::Win32::System::Threading::GetCurrentProcess(),
+            address as _,
+            buffer.as_mut_ptr() as _,
+            buffer.len(),
+            &mut read,
+        );
+        if result == windows_sys::Win32::Foundation::FALSE {
+            return Err(crate::DetectError::MemoryUnreadable(std::io::Error::last_os_error()));
+        }
+        if read != buffer.len() {
+            return Err(crate::DetectError::MemoryUnreadable(std::io::ErrorKind::UnexpectedEof.into()));
+        }
+    }
+    Ok(())
+}
+
+/// Checks if hardware breakpoints are set on the current thread.
+///
+/// Retrieves the debug registers with `GetThreadContext(CONTEXT_DEBUG_REGISTERS)`
+/// and checks if any breakpoint is enabled:
+/// `DR0`-`DR3` and the enable bits of `DR7` on x86/x86-64,
+/// and the enable bits of the breakpoint/watchpoint control registers on ARM64.
+///
+/// This can be polled on its own to catch breakpoint-based tracing
+/// that doesn't trip the other checks.
+#[cfg(feature = "deep-detect")]
+pub fn has_hardware_breakpoints() -> Result<bool, crate::DetectError> {
+    let context = current_thread_debug_registers()?;
+    Ok(debug_registers_set(&context.0))
+}
+
+/// Checks if hardware breakpoints are set on the current thread,
+/// or on any thread of the process if `check_all_threads` is `true`.
+///
+/// A debugger may set hardware breakpoints on another thread than the one running the check.
+/// The other threads are enumerated with `CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD)`,
+/// and each is suspended while its debug registers are retrieved.
+/// Threads that exit or can't be opened during the enumeration are skipped.
+#[cfg(feature = "deep-detect")]
+pub fn hardware_breakpoints_set(check_all_threads: bool) -> Result<bool, crate::DetectError> {
+    use windows_sys::Win32::System::Threading::{OpenThread, ResumeThread, SuspendThread, THREAD_GET_CONTEXT, THREAD_SUSPEND_RESUME};
+
+    if has_hardware_breakpoints()? {
+        return Ok(true);
+    }
+    if !check_all_threads {
+        return Ok(false);
+    }
+    let current = unsafe { windows_sys::Win32::System::Threading::GetCurrentThreadId() };
+    let mut found = Ok(false);
+    for_each_thread(|id| unsafe {
+        if id == current {
+            return true;
+        }
+        let thread = OpenThread(THREAD_GET_CONTEXT | THREAD_SUSPEND_RESUME, windows_sys::Win32::Foundation::FALSE, id);
+        if thread.is_null() {
+            return true;
+        }
+        if SuspendThread(thread) != u32::MAX {
+            let context = thread_debug_registers(thread);
+            ResumeThread(thread);
+            found = context.map(|context| debug_registers_set(&context.0));
+        }
+        windows_sys::Win32::Foundation::CloseHandle(thread);
+        matches!(found, Ok(false))
+    })?;
+    found
+}
+
+/// Calls `visit` with the ID of each thread of the current process, until it returns `false`.
+///
+/// The threads are enumerated with `CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD)`,
+/// so threads created during the enumeration may be missed.
+fn for_each_thread(mut visit: impl FnMut(u32) -> bool) -> Result<(), crate::DetectError> {
+    use windows_sys::Win32::System::Diagnostics::ToolHelp::{THREADENTRY32, Thread32First, Thread32Next};
+
+    unsafe {
+        let snapshot = windows_sys::Win32::System::Diagnostics::ToolHelp::CreateToolhelp32Snapshot(
+            windows_sys::Win32::System::Diagnostics::ToolHelp::TH32CS_SNAPTHREAD,
+            0,
+        );
+        if snapshot == windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE {
+            return Err(crate::error::last_os_error!(ProcessSnapshotFailed));
+        }
+        let process = windows_sys::Win32::System::Threading::GetCurrentProcessId();
+        let mut entry = core::mem::zeroed::<THREADENTRY32>();
+        entry.dwSize = size_of::<THREADENTRY32>() as _;
+        let mut result = Thread32First(snapshot, &mut entry);
+        while result != windows_sys::Win32::Foundation::FALSE {
+            if entry.th32OwnerProcessID == process && !visit(entry.th32ThreadID) {
+                break;
+            }
+            result = Thread32Next(snapshot, &mut entry);
+        }
+        windows_sys::Win32::Foundation::CloseHandle(snapshot);
+    }
+    Ok(())
+}
+
+/// Clears all hardware breakpoints of the current thread.
+///
+/// Zeroes the debug registers with `SetThreadContext(CONTEXT_DEBUG_REGISTERS)` as an active countermeasure:
+/// `DR0`-`DR3` and `DR7` on x86/x86-64,
+/// and the breakpoint/watchpoint control and value registers on ARM64.
+#[cfg(feature = "deep-detect")]
+pub fn clear_hardware_breakpoints() -> Result<(), crate::DetectError> {
+    let mut context = current_thread_debug_registers()?;
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "arm64ec"))] {
+        context.0.Dr0 = 0;
+        context.0.Dr1 = 0;
+        context.0.Dr2 = 0;
+        context.0.Dr3 = 0;
+        context.0.Dr7 = 0;
+    }
+    #[cfg(target_arch = "aarch64")] {
+        context.0.Bcr = [0; 8];
+        context.0.Bvr = [0; 8];
+        context.0.Wcr = [0; 2];
+        context.0.Wvr = [0; 2];
+    }
+    unsafe {
+        let result = windows_sys::Win32::System::Diagnostics::Debug::SetThreadContext(
+            windows_sys::Win32::System::Threading::GetCurrentThread(),
+            &context.0,
+        );
+        if result == windows_sys::Win32::Foundation::FALSE {
+            return Err(crate::error::last_os_error!(ThreadContextFailed));
+        }
+    }
+    Ok(())
+}
+
+/// A `CONTEXT` aligned as required by `GetThreadContext` on x86-64.
+#[cfg(feature = "deep-detect")]
+#[repr(C, align(16))]
+struct AlignedContext(windows_sys::Win32::System::Diagnostics::Debug::CONTEXT);
+
+/// Retrieves the debug registers of the current thread.
+#[cfg(feature = "deep-detect")]
+fn current_thread_debug_registers() -> Result<AlignedContext, crate::DetectError> {
+    thread_debug_registers(unsafe { windows_sys::Win32::System::Threading::GetCurrentThread() })
+}
+
+/// Retrieves the debug registers of `thread`.
+///
+/// A thread other than the current one must be suspended.
+#[cfg(feature = "deep-detect")]
+fn thread_debug_registers(thread: windows_sys::Win32::Foundation::HANDLE) -> Result<AlignedContext, crate::DetectError> {
+    #[cfg(any(target_arch = "x86_64", target_arch = "arm64ec"))]
+    const CONTEXT_DEBUG_REGISTERS: u32 = windows_sys::Win32::System::Diagnostics::Debug::CONTEXT_DEBUG_REGISTERS_AMD64;
+    #[cfg(target_arch = "x86")]
+    const CONTEXT_DEBUG_REGISTERS: u32 = windows_sys::Win32::System::Diagnostics::Debug::CONTEXT_DEBUG_REGISTERS_X86;
+    #[cfg(target_arch = "aarch64")]
+    const CONTEXT_DEBUG_REGISTERS: u32 = windows_sys::Win32::System::Diagnostics::Debug::CONTEXT_DEBUG_REGISTERS_ARM64;
+
+    unsafe {
+        let mut context: AlignedContext = core::mem::zeroed();
+        context.0.ContextFlags = CONTEXT_DEBUG_REGISTERS;
+        let result = windows_sys::Win32::System::Diagnostics::Debug::GetThreadContext(thread, &mut context.0);
+        if result == windows_sys::Win32::Foundation::FALSE {
+            return Err(crate::error::last_os_error!(ThreadContextFailed));
+        }
+        Ok(context)
+    }
+}
+
+/// Checks if any hardware breakpoint is enabled in the debug registers of `context`.
+#[cfg(feature = "deep-detect")]
+fn debug_registers_set(context: &windows_sys::Win32::System::Diagnostics::Debug::CONTEXT) -> bool {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "arm64ec"))] {
+        // The bits 0-7 of `DR7` are the local and global enable bits of `DR0`-`DR3`.
+        context.Dr0 != 0 || context.Dr1 != 0 || context.Dr2 != 0 || context.Dr3 != 0
+            || context.Dr7 & 0xFF != 0
+    }
+    #[cfg(target_arch = "aarch64")] {
+        // The bit 0 of each control register is its enable bit.
+        context.Bcr.iter().chain(context.Wcr.iter()).any(|control| control & 1 != 0)
+    }
+}
+
+/// `FLG_HEAP_ENABLE_TAIL_CHECK`: fills the tail of each heap block with a pattern checked on free.
+#[cfg(feature = "deep-detect")]
+const FLG_HEAP_ENABLE_TAIL_CHECK: u32 = 0x10;
+/// `FLG_HEAP_ENABLE_FREE_CHECK`: fills freed heap blocks with a pattern checked on reuse.
+#[cfg(feature = "deep-detect")]
+const FLG_HEAP_ENABLE_FREE_CHECK: u32 = 0x20;
+/// `FLG_HEAP_VALIDATE_PARAMETERS`: validates the parameters of each heap call.
+#[cfg(feature = "deep-detect")]
+const FLG_HEAP_VALIDATE_PARAMETERS: u32 = 0x40;
+/// The `NtGlobalFlag` bits set by the loader when a process is created under a debugger.
+#[cfg(feature = "deep-detect")]
+const NT_GLOBAL_FLAG_DEBUGGER_MASK: u32 = FLG_HEAP_ENABLE_TAIL_CHECK | FLG_HEAP_ENABLE_FREE_CHECK | FLG_HEAP_VALIDATE_PARAMETERS;
+
+/// The offset of the `NtGlobalFlag` field in the native PEB.
+///
+/// x86-64 and ARM64 share the same 64-bit PEB layout.
+#[cfg(all(feature = "deep-detect", target_pointer_width = "64"))]
+const NT_GLOBAL_FLAG_OFFSET: usize = 0xBC;
+/// The offset of the `NtGlobalFlag` field in the native PEB.
+#[cfg(all(feature = "deep-detect", target_pointer_width = "32"))]
+const NT_GLOBAL_FLAG_OFFSET: usize = 0x68;
+
+/// Checks the `NtGlobalFlag` field of the PEB for the heap debugging flags
+/// the loader sets when a process is created under a debugger.
+///
+/// The field lives at offset `0x68` of the 32-bit PEB and `0xBC` of the 64-bit PEB.
+/// The PEB address is queried with `NtQueryInformationProcess(ProcessBasicInformation)`.
+/// A 32-bit process running under WOW64 owns both PEBs, and both are inspected.
+#[cfg(feature = "deep-detect")]
+pub(crate) fn nt_global_flag_debugged() -> Result<bool, crate::DetectError> {
+    // Check the native PEB.
+    unsafe {
+        let mut pbi = windows_sys::Win32::System::Threading::PROCESS_BASIC_INFORMATION::default();
+        let result = windows_sys::Wdk::System::Threading::NtQueryInformationProcess(
+            windows_sys::Win32::System::Threading::GetCurrentProcess(),
+            windows_sys::Wdk::System::Threading::ProcessBasicInformation,
+            &mut pbi as *mut _ as _,
+            size_of::<windows_sys::Win32::System::Threading::PROCESS_BASIC_INFORMATION>() as _,
+            &mut 0,
+        );
+        if result < 0 {
+            return Err(crate::DetectError::NtQueryFailed(result));
+        }
+        if pbi.PebBaseAddress.is_null() {
+            return Err(crate::DetectError::NtQueryFailed(windows_sys::Win32::Foundation::STATUS_UNSUCCESSFUL));
+        }
+        if nt_global_flag_set(pbi.PebBaseAddress.cast::<u8>().add(NT_GLOBAL_FLAG_OFFSET)) {
+            return Ok(true);
+        }
+    }
+    // Check the 64-bit PEB of a WOW64 process.
+    #[cfg(target_arch = "x86")]
+    {
+        if wow64_nt_global_flag_debugged()? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Checks the `NtGlobalFlag` field of the PEB like the `deep-detect` check of [`is_debugger_present`](crate::is_debugger_present),
+/// but locates the PEB through the TEB instead of calling any API that could be hooked.
+///
+/// The TEB is read from `fs:[0x18]` on x86 and `x18` on ARM64, whose PEB pointer is at
+/// offset `0x30` and `0x60` respectively, and the PEB is read from `gs:[0x60]` on x86-64.
+/// These offsets are part of the stable ABI of every Windows version since NT.
+///
+/// This catches debuggers that patch the `BeingDebugged` byte but leave the heap flags set.
+/// If the WOW64 state can't be determined, only the native PEB is inspected.
+#[cfg(all(feature = "deep-detect", any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+pub fn check_peb_ntglobalflag() -> bool {
+    unsafe {
+        let peb = current_peb();
+        if !peb.is_null() && nt_global_flag_set(peb.add(NT_GLOBAL_FLAG_OFFSET)) {
+            return true;
+        }
+    }
+    #[cfg(target_arch = "x86")]
+    {
+        if wow64_nt_global_flag_debugged().unwrap_or(false) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Checks the `BeingDebugged` byte of the PEB like [`check_is_debugger_present`],
+/// but reads it directly instead of calling `IsDebuggerPresent`, which could be hooked.
+///
+/// The PEB is located with inline assembly as in [`check_peb_ntglobalflag`],
+/// and the byte is at offset `2` of the PEB on every architecture.
+/// Reading the PEB through the segment registers is inherently unsafe,
+/// but relies only on the stable ABI of Windows, so it never fails.
+#[cfg(all(feature = "deep-detect", any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+pub fn check_peb_being_debugged() -> bool {
+    /// The offset of the `BeingDebugged` field in the PEB.
+    const BEING_DEBUGGED_OFFSET: usize = 2;
+
+    unsafe {
+        let peb = current_peb();
+        !peb.is_null() && peb.add(BEING_DEBUGGED_OFFSET).read_volatile() != 0
+    }
+}
+
+/// Returns the address of the PEB of the current process, read through the TEB.
+#[cfg(all(feature = "deep-detect", any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+unsafe fn current_peb() -> *const u8 {
+    let peb: *const u8;
+    unsafe {
+        #[cfg(target_arch = "x86_64")]
+        core::arch::asm!("mov {}, gs:[0x60]", out(reg) peb, options(nostack, readonly, preserves_flags));
+        #[cfg(target_arch = "x86")]
+        core::arch::asm!("mov {}, fs:[0x30]", out(reg) peb, options(nostack, readonly, preserves_flags));
+        #[cfg(target_arch = "aarch64")]
+        {
+            let teb: *const u8;
+            core::arch::asm!("mov {}, x18", out(reg) teb, options(nomem, nostack, preserves_flags));
+            peb = teb.add(0x60).cast::<*const u8>().read();
+        }
+    }
+    peb
+}
+
+/// Checks the `NtGlobalFlag` at `address` against [`NT_GLOBAL_FLAG_DEBUGGER_MASK`].
+#[cfg(feature = "deep-detect")]
+unsafe fn nt_global_flag_set(address: *const u8) -> bool {
+    let nt_global_flag = unsafe { address.cast::<u32>().read_unaligned() };
+    nt_global_flag & NT_GLOBAL_FLAG_DEBUGGER_MASK == NT_GLOBAL_FLAG_DEBUGGER_MASK
+}
+
+/// Checks the `NtGlobalFlag` field of the 64-bit PEB of a WOW64 process.
+///
+/// Returns `Ok(false)` if the current process is not running under WOW64.
+#[cfg(all(feature = "deep-detect", target_arch = "x86"))]
+fn wow64_nt_global_flag_debugged() -> Result<bool, crate::DetectError> {
+    const NT_GLOBAL_FLAG_OFFSET_64: usize = 0xBC;
+    const TEB64_SELF_OFFSET: usize = 0x30;
+    const TEB64_PEB_OFFSET: usize = 0x60;
+    // The 64-bit TEB of a WOW64 thread is placed right before its 32-bit TEB.
+    const TEB64_DISTANCE: usize = 0x2000;
+
+    unsafe {
+        let mut is_wow64 = windows_sys::Win32::Foundation::FALSE;
+        let result = windows_sys::Win32::System::Threading::IsWow64Process(
+            windows_sys::Win32::System::Threading::GetCurrentProcess(),
+            &mut is_wow64,
+        );
+        if result == windows_sys::Win32::Foundation::FALSE {
+            return Err(crate::error::last_os_error!(Wow64CheckFailed));
+        }
+        if is_wow64 == windows_sys::Win32::Foundation::FALSE {
+            return Ok(false);
+        }
+        let teb32: usize;
+        core::arch::asm!("mov {}, fs:[0x18]", out(reg) teb32, options(nostack, readonly, preserves_flags));
+        let teb64 = teb32.wrapping_sub(TEB64_DISTANCE) as *const u8;
+        // The structures of WOW64 live below 4GiB, so the low half of each pointer is enough.
+        // Bail out instead of reading garbage if the layout is not the expected one.
+        let teb64_self = teb64.add(TEB64_SELF_OFFSET).cast::<u32>().read_unaligned();
+        if teb64_self as usize != teb64 as usize {
+            return Ok(false);
+        }
+        let peb64 = teb64.add(TEB64_PEB_OFFSET).cast::<u32>().read_unaligned() as *const u8;
+        Ok(!peb64.is_null() && nt_global_flag_set(peb64.add(NT_GLOBAL_FLAG_OFFSET_64)))
+    }
+}
+
+/// Checks the `Flags` and `ForceFlags` fields of the process heap for the debug heap
+/// the loader enables when a process is created under a debugger.
+///
+/// The process heap is first queried with `HeapQueryInformation(HeapCompatibilityInformation)`:
+/// the low-fragmentation heap front end is never enabled on a debug heap, so it rules out a debugger.
+///
+/// Otherwise, the undocumented fields are read at the offsets used since Windows Vista
+/// (`0x40`/`0x44` for 32-bit and `0x70`/`0x74` for 64-bit processes) only after the
+/// segment signature confirms the heap has the expected NT heap layout.
+/// A heap with any other layout (e.g. the segment heap) is reported as not debugged.
+#[cfg(feature = "deep-detect")]
+pub(crate) fn heap_flags_debugged() -> Result<bool, crate::DetectError> {
+    const HEAP_TAIL_CHECKING_ENABLED: u32 = 0x20;
+    const HEAP_FREE_CHECKING_ENABLED: u32 = 0x40;
+    const HEAP_VALIDATE_PARAMETERS_ENABLED: u32 = 0x40000000;
+    const HEAP_DEBUG_FLAGS: u32 = HEAP_TAIL_CHECKING_ENABLED | HEAP_FREE_CHECKING_ENABLED | HEAP_VALIDATE_PARAMETERS_ENABLED;
+    const HEAP_SEGMENT_SIGNATURE: u32 = 0xFFEEFFEE;
+    const HEAP_COMPATIBILITY_LFH: u32 = 2;
+    #[cfg(target_pointer_width = "64")]
+    const SIGNATURE_OFFSET: usize = 0x10;
+    #[cfg(target_pointer_width = "64")]
+    const FLAGS_OFFSET: usize = 0x70;
+    #[cfg(target_pointer_width = "64")]
+    const FORCE_FLAGS_OFFSET: usize = 0x74;
+    #[cfg(target_pointer_width = "32")]
+    const SIGNATURE_OFFSET: usize = 0x08;
+    #[cfg(target_pointer_width = "32")]
+    const FLAGS_OFFSET: usize = 0x40;
+    #[cfg(target_pointer_width = "32")]
+    const FORCE_FLAGS_OFFSET: usize = 0x44;
+
+    unsafe {
+        let handle = windows_sys::Win32::System::Memory::GetProcessHeap();
+        if handle.is_null() {
+            return Err(crate::DetectError::ProcessHeapUnavailable);
+        }
+        let mut compatibility = 0u32;
+        let result = windows_sys::Win32::System::Memory::HeapQueryInformation(
+            handle,
+            windows_sys::Win32::System::Memory::HeapCompatibilityInformation,
+            &mut compatibility as *mut _ as _,
+            size_of::<u32>(),
+            core::ptr::null_mut(),
+        );
+        if result != windows_sys::Win32::Foundation::FALSE && compatibility == HEAP_COMPATIBILITY_LFH {
+            return Ok(false);
+        }
+        let heap = handle as *const u8;
+        if heap.add(SIGNATURE_OFFSET).cast::<u32>().read_unaligned() != HEAP_SEGMENT_SIGNATURE {
+            return Ok(false);
+        }
+        let flags = heap.add(FLAGS_OFFSET).cast::<u32>().read_unaligned();
+        let force_flags = heap.add(FORCE_FLAGS_OFFSET).cast::<u32>().read_unaligned();
+        Ok(flags & HEAP_DEBUG_FLAGS != 0 || force_flags != 0)
+    }
+}
+
+/// The offset of `e_lfanew`, the offset of the NT headers, in the DOS header.
+#[cfg(feature = "memory-integrity")]
+const E_LFANEW_OFFSET: usize = 0x3C;
+/// The offset of `FileHeader.NumberOfSections` in the NT headers.
+#[cfg(feature = "memory-integrity")]
+const NUMBER_OF_SECTIONS_OFFSET: usize = 0x06;
+/// The offset of `FileHeader.SizeOfOptionalHeader` in the NT headers.
+#[cfg(feature = "memory-integrity")]
+const SIZE_OF_OPTIONAL_HEADER_OFFSET: usize = 0x14;
+/// The offset of `OptionalHeader` in the NT headers.
+#[cfg(feature = "memory-integrity")]
+const OPTIONAL_HEADER_OFFSET: usize = 0x18;
+/// The size of `IMAGE_SECTION_HEADER`.
+#[cfg(feature = "memory-integrity")]
+const SECTION_HEADER_SIZE: usize = 0x28;
+/// `IMAGE_SCN_MEM_EXECUTE`: the section can be executed as code.
+#[cfg(feature = "memory-integrity")]
+const IMAGE_SCN_MEM_EXECUTE: u32 = 0x20000000;
+
+/// Returns the base address of the main module and the address and size of each of its executable sections.
+///
+/// The section table is read from the loaded image.
+#[cfg(feature = "memory-integrity")]
+pub(crate) fn executable_code_regions() -> (usize, Vec<(usize, usize)>) {
+    unsafe {
+        let base = windows_sys::Win32::System::LibraryLoader::GetModuleHandleW(core::ptr::null()) as *const u8;
+        let read_u16 = |offset: usize| base.add(offset).cast::<u16>().read_unaligned();
+        let read_u32 = |offset: usize| base.add(offset).cast::<u32>().read_unaligned();
+        let nt_headers = read_u32(E_LFANEW_OFFSET) as usize;
+        let number_of_sections = read_u16(nt_headers + NUMBER_OF_SECTIONS_OFFSET) as usize;
+        let sections = nt_headers + OPTIONAL_HEADER_OFFSET + read_u16(nt_headers + SIZE_OF_OPTIONAL_HEADER_OFFSET) as usize;
+        let regions = (0..number_of_sections)
+            .map(|index| sections + index * SECTION_HEADER_SIZE)
+            .filter(|&section| read_u32(section + 0x24) & IMAGE_SCN_MEM_EXECUTE != 0)
+            .map(|section| (base as usize + read_u32(section + 0x0C) as usize, read_u32(section + 0x08) as usize))
+            .collect();
+        (base as usize, regions)
+    }
+}
+
+/// Checks if `INT3` (`0xCC`) bytes were written into the executable sections of the current executable.
+///
+/// The section table is read from the loaded image, and every executable section
+/// is compared with its raw data in the file on disk. Only bytes that are `0xCC` in memory
+/// but not on disk are reported, so `0xCC` bytes the compiler emitted
+/// (padding, immediates and data embedded in code) are never flagged.
+/// Bytes patched by the loader through base relocations are skipped.
+///
+/// Pages that `VirtualQuery` reports as uncommitted, guarded or not readable are skipped
+/// with a warning through the `log` feature.
+#[cfg(all(feature = "memory-integrity", any(target_arch = "x86", target_arch = "x86_64")))]
+pub(crate) fn image_has_int3_patches() -> Result<bool, crate::DetectError> {
+    use std::os::windows::ffi::OsStringExt;
+    use std::os::windows::fs::FileExt;
+    use windows_sys::Win32::System::Memory::{MEMORY_BASIC_INFORMATION, MEM_COMMIT, PAGE_EXECUTE, PAGE_GUARD, PAGE_NOACCESS};
+
+    #[cfg(target_pointer_width = "64")]
+    const BASE_RELOCATION_DIRECTORY_OFFSET: usize = 0x70 + 5 * 8;
+    #[cfg(target_pointer_width = "32")]
+    const BASE_RELOCATION_DIRECTORY_OFFSET: usize = 0x60 + 5 * 8;
+    const IMAGE_REL_BASED_HIGHLOW: u16 = 3;
+    const IMAGE_REL_BASED_DIR64: u16 = 10;
+
+    let path = {
+        let mut buffer = vec![0u16; 1024];
+        loop {
+            let length = unsafe {
+                windows_sys::Win32::System::LibraryLoader::GetModuleFileNameW(core::ptr::null_mut(), buffer.as_mut_ptr(), buffer.len() as u32)
+            } as usize;
+            if length == 0 {
+                return Err(crate::DetectError::ExecutableUnreadable(std::io::Error::last_os_error()));
+            }
+            if length < buffer.len() {
+                break std::ffi::OsString::from_wide(&buffer[..length]);
+            }
+            buffer.resize(buffer.len() * 2, 0);
+        }
+    };
+    let file = std::fs::File::open(path).map_err(crate::DetectError::ExecutableUnreadable)?;
+
+    unsafe {
+        let base = windows_sys::Win32::System::LibraryLoader::GetModuleHandleW(core::ptr::null()) as *const u8;
+        let read_u16 = |offset: usize| base.add(offset).cast::<u16>().read_unaligned();
+        let read_u32 = |offset: usize| base.add(offset).cast::<u32>().read_unaligned();
+        let nt_headers = read_u32(E_LFANEW_OFFSET) as usize;
+        let number_of_sections = read_u16(nt_headers + NUMBER_OF_SECTIONS_OFFSET) as usize;
+        let optional_header = nt_headers + OPTIONAL_HEADER_OFFSET;
+        let sections = optional_header + read_u16(nt_headers + SIZE_OF_OPTIONAL_HEADER_OFFSET) as usize;
+
+        // The start RVAs and sizes of the fields patched by the loader.
+        let mut relocations = Vec::new();
+        let directory = read_u32(optional_header + BASE_RELOCATION_DIRECTORY_OFFSET) as usize;
+        let directory_end = directory + read_u32(optional_header + BASE_RELOCATION_DIRECTORY_OFFSET + 4) as usize;
+        let mut block = directory;
+        while directory != 0 && block + 8 <= directory_end {
+            let page = read_u32(block) as usize;
+            let block_size = read_u32(block + 4) as usize;
+            if block_size < 8 {
+                break;
+            }
+            for entry in (block + 8..block + block_size).step_by(2) {
+                let entry = read_u16(entry);
+                let size = match entry >> 12 {
+                    IMAGE_REL_BASED_HIGHLOW => 4,
+                    IMAGE_REL_BASED_DIR64 => 8,
+                    _ => continue,
+                };
+                relocations.push((page + (entry & 0xFFF) as usize, size));
+            }
+            block += block_size;
+        }
+        relocations.sort_unstable();
+        let relocated = |rva: usize| {
+            let index = relocations.partition_point(|&(start, _)| start <= rva);
+            relocations[..index].last().is_some_and(|&(start, size)| rva < start + size)
+        };
+
+        for section in (0..number_of_sections).map(|index| sections + index * SECTION_HEADER_SIZE) {
+            if read_u32(section + 0x24) & IMAGE_SCN_MEM_EXECUTE == 0 {
+                continue;
+            }
+            let virtual_size = read_u32(section + 0x08) as usize;
+            let virtual_address = read_u32(section + 0x0C) as usize;
+            let size_of_raw_data = read_u32(section + 0x10) as usize;
+            let pointer_to_raw_data = read_u32(section + 0x14) as u64;
+            let length = virtual_size.min(size_of_raw_data);
+            let mut disk = vec![0; length];
+            let mut read = 0;
+            while read < length {
+                match file.seek_read(&mut disk[read..], pointer_to_raw_data + read as u64) {
+                    Ok(0) => return Err(crate::DetectError::ExecutableUnreadable(std::io::ErrorKind::UnexpectedEof.into())),
+                    Ok(n) => read += n,
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                    Err(e) => return Err(crate::DetectError::ExecutableUnreadable(e)),
+                }
+            }
+
+            let mut offset = 0;
+            while offset < length {
+                let mut info = core::mem::zeroed::<MEMORY_BASIC_INFORMATION>();
+                let result = windows_sys::Win32::System::Memory::VirtualQuery(
+                    base.add(virtual_address + offset) as _,
+                    &mut info,
+                    size_of::<MEMORY_BASIC_INFORMATION>(),
+                );
+                if result == 0 {
+                    crate::log_warn!("skipping unqueryable section data at {:#x}", virtual_address + offset);
+                    break;
+                }
+                let region_end = (info.BaseAddress as usize + info.RegionSize - base as usize - virtual_address).min(length);
+                if info.State != MEM_COMMIT || info.Protect & (PAGE_NOACCESS | PAGE_GUARD | PAGE_EXECUTE) != 0 {
+                    crate::log_warn!("skipping unreadable section data at {:#x}-{:#x}", virtual_address + offset, virtual_address + region_end);
+                } else {
+                    let memory = core::slice::from_raw_parts(base.add(virtual_address + offset), region_end - offset);
+                    let patched = memory.iter().zip(&disk[offset..region_end]).enumerate()
+                        .any(|(index, (&memory, &disk))| memory == 0xCC && disk != 0xCC && !relocated(virtual_address + offset + index));
+                    if patched {
+                        return Ok(true);
+                    }
+                }
+                offset = region_end;
+            }
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_check_is_debugger_present() {
+        assert!(!super::check_is_debugger_present());
+    }
+
+    #[test]
+    fn test_check_remote_debugger_present() {
+        assert!(!super::check_remote_debugger_present().unwrap());
+    }
+
+    #[test]
+    fn test_check_debug_port() {
+        // `ProcessDebugPort` fails with `STATUS_INFO_LENGTH_MISMATCH` if the buffer is not pointer-sized.
+        assert!(!super::check_debug_port().unwrap());
+    }
+
+    #[test]
+    fn test_check_debug_object_handle() {
+        // Without a debugger the query fails with `STATUS_PORT_NOT_SET`, which is not an error.
+        assert!(!super::check_debug_object_handle().unwrap());
+    }
+
+    #[test]
+    fn test_check_debug_flags() {
+        assert!(!super::check_debug_flags().unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_parent_process_name() {
+        assert!(super::parent_process_name().unwrap().is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "artifact-scan")]
+    fn test_debugger_processes() {
+        assert!(super::debugger_processes().unwrap().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "artifact-scan")]
+    fn test_debugger_windows() {
+        assert!(super::debugger_windows().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_ifeo_debugger_configured() {
+        assert_eq!(super::ifeo_debugger_configured().unwrap(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_ifeo_debugger_in() {
+        use windows_sys::Win32::System::Registry::{HKEY_CURRENT_USER, REG_SZ, RegDeleteTreeW, RegSetKeyValueW};
+
+        let root = format!(r"Software\anti-debug-test-{}", std::process::id());
+        let key = format!(r"{root}\Image File Execution Options");
+        assert_eq!(super::ifeo_debugger_in(HKEY_CURRENT_USER, &key, "app.exe").unwrap(), None);
+
+        let subkey = format!(r"{key}\app.exe").encode_utf16().chain([0]).collect::<Vec<_>>();
+        let value = "Debugger".encode_utf16().chain([0]).collect::<Vec<_>>();
+        let debugger = r"C:\Tools\x64dbg.exe".encode_utf16().chain([0]).collect::<Vec<_>>();
+        unsafe {
+            let result = RegSetKeyValueW(HKEY_CURRENT_USER, subkey.as_ptr(), value.as_ptr(), REG_SZ, debugger.as_ptr() as _, (debugger.len() * 2) as _);
+            assert_eq!(result, windows_sys::Win32::Foundation::ERROR_SUCCESS);
+        }
+        let result = super::ifeo_debugger_in(HKEY_CURRENT_USER, &key, "app.exe");
+        let root = root.encode_utf16().chain([0]).collect::<Vec<_>>();
+        unsafe { RegDeleteTreeW(HKEY_CURRENT_USER, root.as_ptr()); }
+        assert_eq!(result.unwrap().as_deref(), Some(r"C:\Tools\x64dbg.exe"));
+    }
+
+    #[test]
+    fn test_check_kernel_debugger() {
+        assert!(!super::check_kernel_debugger().unwrap());
+    }
+
+    #[test]
+    fn test_check_kuser_shared_data_kernel_debugger() {
+        assert!(!super::check_kuser_shared_data_kernel_debugger());
+    }
+
+    #[test]
+    #[cfg(feature = "exception-tricks")]
+    fn test_check_debug_string_exception() {
+        let threads = (0..4).map(|_| std::thread::spawn(super::check_debug_string_exception)).collect::<Vec<_>>();
+        assert!(!super::check_debug_string_exception().unwrap());
+        for thread in threads {
+            assert!(!thread.join().unwrap().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_deny_attach_thread() {
+        std::thread::spawn(|| {
+            super::deny_attach_thread(unsafe { windows_sys::Win32::System::Threading::GetCurrentThread() }).unwrap();
+        }).join().unwrap();
+        let error = super::deny_attach_thread(core::ptr::null_mut()).unwrap_err();
+        assert!(matches!(error, crate::DetectError::HideThreadFailed(_)), "{error}");
+    }
+
+    #[test]
+    fn test_deny_attach_all_threads() {
+        /// Returns whether the current thread is hidden from debuggers.
+        fn hidden() -> bool {
+            let mut hidden = 0u8;
+            let status = unsafe {
+                windows_sys::Wdk::System::Threading::NtQueryInformationThread(
+                    windows_sys::Win32::System::Threading::GetCurrentThread(),
+                    windows_sys::Wdk::System::Threading::ThreadHideFromDebugger,
+                    &mut hidden as *mut _ as _,
+                    1,
+                    core::ptr::null_mut(),
+                )
+            };
+            assert!(status >= 0, "{status:#010X}");
+            hidden != 0
+        }
+
+        let barrier = std::sync::Arc::new(std::sync::Barrier::new(5));
+        let threads: Vec<_> = (0..4).map(|_| {
+            let barrier = barrier.clone();
+            std::thread::spawn(move || {
+                barrier.wait();
+                barrier.wait();
+                hidden()
+            })
+        }).collect();
+        barrier.wait();
+        assert!(super::deny_attach_all_threads().unwrap() >= 5);
+        assert!(super::deny_attach_all_threads().unwrap() >= 5);
+        barrier.wait();
+        assert!(hidden());
+        for thread in threads {
+            assert!(thread.join().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_check_output_debug_string() {
+        unsafe { windows_sys::Win32::Foundation::SetLastError(5) };
+        assert!(!super::check_output_debug_string());
+        assert_eq!(unsafe { windows_sys::Win32::Foundation::GetLastError() }, 5);
+    }
+
+    #[test]
+    #[cfg(feature = "exception-tricks")]
+    fn test_is_debugger_intercepting_exceptions() {
+        assert!(!super::is_debugger_intercepting_exceptions().unwrap());
+        assert!(!super::is_debugger_intercepting_exceptions().unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "exception-tricks")]
+    fn test_invalid_handle_probe() {
+        #[link(name = "ntdll")]
+        unsafe extern "system" {
+            fn NtSetInformationProcess(process: windows_sys::Win32::Foundation::HANDLE, class: i32, information: *const core::ffi::c_void, length: u32) -> i32;
+        }
+
+        assert!(!super::invalid_handle_probe().unwrap());
+
+        // Handle tracing must make the probe bail out rather than report a debugger.
+        let process = unsafe { windows_sys::Win32::System::Threading::GetCurrentProcess() };
+        let class = windows_sys::Wdk::System::Threading::ProcessHandleTracing;
+        // PROCESS_HANDLE_TRACING_ENABLE { Flags }
+        let enable = 0u32;
+        let result = unsafe { NtSetInformationProcess(process, class, &enable as *const _ as _, size_of::<u32>() as _) };
+        assert!(result >= 0);
+        let probe = super::invalid_handle_probe();
+        // A zero length disables handle tracing again.
+        unsafe { NtSetInformationProcess(process, class, core::ptr::null(), 0); }
+        assert!(matches!(probe, Err(crate::DetectError::HandleTracingEnabled)));
+    }
+
+    #[test]
+    #[cfg(feature = "deep-detect")]
+    fn test_has_hardware_breakpoints() {
+        assert!(!super::has_hardware_breakpoints().unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "deep-detect")]
+    fn test_hardware_breakpoints_set() {
+        assert!(!super::hardware_breakpoints_set(false).unwrap());
+        let thread = std::thread::spawn(|| std::thread::sleep(std::time::Duration::from_millis(200)));
+        assert!(!super::hardware_breakpoints_set(true).unwrap());
+        thread.join().unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "deep-detect")]
+    fn test_clear_hardware_breakpoints() {
+        super::clear_hardware_breakpoints().unwrap();
+        assert!(!super::has_hardware_breakpoints().unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "deep-detect")]
+    fn test_nt_global_flag_debugged() {
+        assert_eq!(super::NT_GLOBAL_FLAG_DEBUGGER_MASK, 0x70);
+        assert!(!super::nt_global_flag_debugged().unwrap());
+    }
+
+    #[test]
+    #[cfg(all(feature = "deep-detect", any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+    fn test_check_peb_ntglobalflag() {
+        assert!(!super::check_peb_ntglobalflag());
+    }
+
+    #[test]
+    #[cfg(all(feature = "deep-detect", any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+    fn test_check_peb_being_debugged() {
+        assert_eq!(super::check_peb_being_debugged(), super::check_is_debugger_present());
+    }
+
+    #[test]
+    #[cfg(feature = "deep-detect")]
+    fn test_heap_flags_debugged() {
+        assert!(!super::heap_flags_debugged().unwrap());
+    }
+
+    #[test]
+    #[cfg(all(feature = "memory-integrity", any(target_arch = "x86", target_arch = "x86_64")))]
+    fn test_image_has_int3_patches() {
+        assert!(!super::image_has_int3_patches().unwrap());
+    }
+}
@@ -0,0 +1,33 @@
+//! Tests `deny_attach_strict` in a process of its own, as it can't be undone by the crate.
+
+#![cfg(any(target_os = "linux", target_os = "android"))]
+
+#[test]
+fn test_deny_attach_strict() {
+    anti_debug::deny_attach_strict().unwrap();
+    // The `disarm` feature leaves the process dumpable.
+    let dumpable = i32::from(cfg!(feature = "disarm"));
+    assert_eq!(unsafe { libc::prctl(libc::PR_GET_DUMPABLE, 0, 0, 0, 0) }, dumpable);
+    // The checks still work in a non-dumpable process.
+    assert!(!anti_debug::is_debugger_present().unwrap());
+    #[cfg(all(feature = "deep-detect", feature = "std"))] {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        // The flag is process-wide, so it's watched from another thread while the probe runs.
+        let done = AtomicBool::new(false);
+        std::thread::scope(|scope| {
+            let watcher = scope.spawn(|| {
+                while !done.load(Ordering::Acquire) {
+                    assert_eq!(unsafe { libc::prctl(libc::PR_GET_DUMPABLE, 0, 0, 0, 0) }, dumpable);
+                    std::thread::yield_now();
+                }
+            });
+            for _ in 0..16 {
+                assert!(!anti_debug::is_debugger_present_fork().unwrap());
+            }
+            done.store(true, Ordering::Release);
+            watcher.join().unwrap();
+        });
+    }
+    assert_eq!(unsafe { libc::prctl(libc::PR_GET_DUMPABLE, 0, 0, 0, 0) }, dumpable);
+}
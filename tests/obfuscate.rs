@@ -0,0 +1,49 @@
+//! Checks that the `obfuscate` feature keeps the literals of the checks out of the binary.
+
+#![cfg(all(feature = "obfuscate", feature = "std", any(target_os = "linux", target_os = "android")))]
+
+/// Reverses `needle`, so the literals of the test don't appear in the binary themselves.
+fn reversed(needle: &[u8]) -> Vec<u8> {
+    needle.iter().rev().copied().collect()
+}
+
+/// Returns the contents of the sections of the ELF `binary` but the debug info,
+/// which names the variants of the types of the crate regardless of the feature.
+fn loaded_sections(binary: &[u8]) -> Vec<&[u8]> {
+    let read = |offset: usize, size: usize| {
+        binary[offset..offset + size].iter().rev().fold(0, |value, &byte| value << 8 | byte as usize)
+    };
+    // The offsets of the fields differ between 32-bit and 64-bit ELF files.
+    let wide = binary[4] == 2;
+    let (header, offset, size, word) = if wide { ([0x28, 0x3a, 0x3c, 0x3e], 0x18, 0x20, 8) } else { ([0x20, 0x2e, 0x30, 0x32], 0x10, 0x14, 4) };
+    let (table, entry_size, count, names) = (read(header[0], word), read(header[1], 2), read(header[2], 2), read(header[3], 2));
+    let section = |index: usize| {
+        let entry = table + index * entry_size;
+        (read(entry, 4), &binary[read(entry + offset, word)..][..read(entry + size, word)])
+    };
+    let (_, strings) = section(names);
+    (0..count).map(section).filter(|&(name, _)| !strings[name..].starts_with(b".debug")).map(|(_, data)| data).collect()
+}
+
+#[test]
+fn test_no_plaintext() {
+    // Link the checks into the binary.
+    assert!(!anti_debug::is_debugger_present().unwrap());
+    assert!(!anti_debug::launched_by_debugger().unwrap());
+    // Link the names and messages of the errors and reasons into the binary.
+    let error = anti_debug::DetectError::InvalidTracerPid(String::new());
+    let formatted = format!("{error} {error:?} {} {:?}", error.tag(), error.api());
+    assert!(formatted.contains(std::str::from_utf8(&reversed(b"diPrecarT")).unwrap()), "{formatted}");
+    let error = anti_debug::AntiDebugError::from(error);
+    let method = anti_debug::DetectionMethod::TracerPid;
+    let reason = anti_debug::DetectionReason::TracerPid(1);
+    let formatted = format!("{error} {method:?} {} {reason:?}", method.name());
+    assert_eq!(formatted.matches(std::str::from_utf8(&reversed(b"diPrecarT")).unwrap()).count(), 4, "{formatted}");
+    let binary = std::fs::read(std::env::current_exe().unwrap()).unwrap();
+    let sections = loaded_sections(&binary);
+    for needle in [&b"diPrecarT"[..], b"sutats/fles/corp/", b"spam/fles/corp/", b"epocs_ecartp/amay/lenrek/sys/corp/", b"revres-bdll"] {
+        let needle = reversed(needle);
+        let found = sections.iter().any(|section| section.windows(needle.len()).any(|window| window == needle));
+        assert!(!found, "{}", String::from_utf8_lossy(&needle));
+    }
+}